@@ -0,0 +1,205 @@
+//! Support for driving nodes from an async runtime, enabled by the `async` feature.
+//!
+//! The [`spin_async()`] future executes the callbacks of a node, so it can be spawned on an async
+//! runtime instead of dedicating a thread to [`spin()`][1]. While it is running, the futures
+//! returned by [`Client::call_async()`][2] resolve on their own, and messages can be received as a
+//! [`Stream`] with [`Node::create_subscription_stream()`].
+//!
+//! [1]: crate::spin
+//! [2]: crate::Client::call_async
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+use futures::{Future, Stream, StreamExt};
+use rosidl_runtime_rs::Message;
+
+use crate::executor::execute_ready_entities;
+use crate::{Node, QoSProfile, RclReturnCode, RclrsError, Subscription, WaitSetCache};
+
+/// Returns a future that spins the node until its context is shut down or an error occurs.
+///
+/// Each poll waits on the node's wait set for at most [`SpinAsync::max_wait()`], executes the
+/// callbacks of the ready entities, and then yields to the other tasks of the runtime. No thread
+/// is spawned, but the task blocks the thread that polls it while waiting, so that the maximum
+/// wait bounds how long the other tasks on that thread are delayed.
+///
+/// Like [`spin_once()`][1], this keeps the node's entities in its wait set between polls.
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::{Context, RclrsError};
+/// # use std::sync::Arc;
+/// let context = Context::new([])?;
+/// let node = Arc::new(rclrs::create_node(&context, "my_node")?);
+/// // E.g. with tokio: tokio::spawn(rclrs::spin_async(Arc::clone(&node)));
+/// futures::executor::block_on(rclrs::spin_async(node))?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::spin_once
+pub fn spin_async(node: Arc<Node>) -> SpinAsync {
+    SpinAsync {
+        node,
+        max_wait: SpinAsync::DEFAULT_MAX_WAIT,
+    }
+}
+
+/// The future returned by [`spin_async()`].
+pub struct SpinAsync {
+    node: Arc<Node>,
+    max_wait: Duration,
+}
+
+impl SpinAsync {
+    const DEFAULT_MAX_WAIT: Duration = Duration::from_millis(10);
+
+    /// Sets the maximum time that a single poll waits for the node's entities.
+    ///
+    /// A shorter wait lets the other tasks on the same thread run sooner, at the cost of waking up
+    /// more often. The default is 10 ms.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+}
+
+impl Future for SpinAsync {
+    type Output = Result<(), RclrsError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        if !self.node.context.ok() {
+            return Poll::Ready(Ok(()));
+        }
+        match WaitSetCache::wait_once(
+            &self.node.wait_set_cache,
+            &[&*self.node],
+            Some(self.max_wait),
+        )
+        .and_then(execute_ready_entities)
+        {
+            Ok(_)
+            | Err(RclrsError::RclError {
+                code: RclReturnCode::Timeout,
+                ..
+            }) => (),
+            Err(err) => return Poll::Ready(Err(err)),
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// A [`Stream`] of the messages received by a subscription.
+///
+/// Created by [`Node::create_subscription_stream()`]. Messages are only received while the
+/// node is spun, e.g. by [`spin_async()`], and are buffered until the stream is polled.
+pub struct SubscriptionStream<T: Message> {
+    subscription: Arc<Subscription<T>>,
+    receiver: UnboundedReceiver<T>,
+}
+
+impl<T: Message> SubscriptionStream<T> {
+    /// Returns the subscription that receives the messages.
+    pub fn subscription(&self) -> &Arc<Subscription<T>> {
+        &self.subscription
+    }
+}
+
+impl<T: Message> Stream for SubscriptionStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<T>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+impl Node {
+    /// Creates a [`Subscription`] whose messages are received through a [`SubscriptionStream`].
+    ///
+    /// The subscription is in the node's default callback group.
+    // TODO: make subscription's lifetime depend on node's lifetime
+    pub fn create_subscription_stream<T: Message>(
+        &mut self,
+        topic: &str,
+        qos: QoSProfile,
+    ) -> Result<SubscriptionStream<T>, RclrsError> {
+        let (sender, receiver) = unbounded();
+        let subscription = self.create_subscription(topic, qos, move |msg: T| {
+            // The stream may have been dropped while the subscription is still alive
+            let _ = sender.unbounded_send(msg);
+        })?;
+        Ok(SubscriptionStream {
+            subscription,
+            receiver,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::{select, Either};
+
+    use super::*;
+    use crate::vendor::builtin_interfaces::msg::Time as TimeMsg;
+    use crate::vendor::rosgraph_msgs::msg::Clock as ClockMsg;
+    use crate::{Context, QOS_PROFILE_DEFAULT};
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn spin_async_and_subscription_stream_are_send() {
+        assert_send::<SpinAsync>();
+        assert_send::<SubscriptionStream<ClockMsg>>();
+    }
+
+    #[test]
+    fn test_subscription_stream() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(&context, "test_subscription_stream")?;
+        let mut stream = node.create_subscription_stream::<ClockMsg>(
+            "test_subscription_stream",
+            QOS_PROFILE_DEFAULT,
+        )?;
+        let publisher =
+            node.create_publisher::<ClockMsg>("test_subscription_stream", QOS_PROFILE_DEFAULT)?;
+        // Keep publishing, in case the first messages are sent before discovery has finished
+        let _timer = node.create_timer(Duration::from_millis(10), move || {
+            let msg = ClockMsg {
+                clock: TimeMsg { sec: 7, nanosec: 0 },
+            };
+            publisher.publish(msg).unwrap();
+        })?;
+
+        let msg =
+            crate::spin_until_future_complete(&node, stream.next(), Some(Duration::from_secs(5)))?;
+        assert_eq!(msg.unwrap().clock.sec, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_spin_async_resolves_subscription_stream() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(&context, "test_spin_async")?;
+        let mut stream =
+            node.create_subscription_stream::<ClockMsg>("test_spin_async", QOS_PROFILE_DEFAULT)?;
+        let publisher =
+            node.create_publisher::<ClockMsg>("test_spin_async", QOS_PROFILE_DEFAULT)?;
+        let _timer = node.create_timer(Duration::from_millis(10), move || {
+            let msg = ClockMsg {
+                clock: TimeMsg { sec: 7, nanosec: 0 },
+            };
+            publisher.publish(msg).unwrap();
+        })?;
+
+        let spin = spin_async(Arc::new(node));
+        match futures::executor::block_on(select(spin, stream.next())) {
+            Either::Right((msg, _)) => assert_eq!(msg.unwrap().clock.sec, 7),
+            Either::Left((result, _)) => panic!("spin_async stopped early: {:?}", result),
+        }
+        Ok(())
+    }
+}
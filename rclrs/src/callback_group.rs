@@ -0,0 +1,107 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::{ClientBase, ServiceBase, SubscriptionBase, Timer, Waitable};
+
+/// Determines whether the callbacks in a [`CallbackGroup`] may run concurrently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CallbackGroupType {
+    /// At most one callback of the group runs at a time.
+    MutuallyExclusive,
+    /// Callbacks of different entities in the group may run concurrently.
+    Reentrant,
+}
+
+/// A group of subscriptions, services, clients, timers and waitables whose callbacks are scheduled together.
+///
+/// Callback groups only make a difference for executors that run callbacks concurrently, such as
+/// the [`MultiThreadedExecutor`][1]: callbacks from different groups may always run in parallel,
+/// and callbacks within the same group only if it is [`Reentrant`][2].
+///
+/// Every node has a mutually exclusive default callback group, which is used by
+/// [`Node::create_subscription`][3] and the other entity creation functions. Entities can be
+/// assigned to another group when they are created, e.g. with
+/// [`Node::create_subscription_with_callback_group`][4].
+///
+/// The only available way to instantiate callback groups is via
+/// [`Node::create_callback_group()`][5], this is to ensure that [`Node`][6]s can track all the
+/// callback groups that have been created.
+///
+/// [1]: crate::MultiThreadedExecutor
+/// [2]: CallbackGroupType::Reentrant
+/// [3]: crate::Node::create_subscription
+/// [4]: crate::Node::create_subscription_with_callback_group
+/// [5]: crate::Node::create_callback_group
+/// [6]: crate::Node
+pub struct CallbackGroup {
+    group_type: CallbackGroupType,
+    pub(crate) subscriptions: Mutex<Vec<Weak<dyn SubscriptionBase>>>,
+    pub(crate) clients: Mutex<Vec<Weak<dyn ClientBase>>>,
+    pub(crate) services: Mutex<Vec<Weak<dyn ServiceBase>>>,
+    pub(crate) timers: Mutex<Vec<Weak<Timer>>>,
+    pub(crate) waitables: Mutex<Vec<Weak<dyn Waitable>>>,
+    // Set by the multi-threaded executor while a callback of this group is running, if the group
+    // is mutually exclusive.
+    pub(crate) in_use: AtomicBool,
+}
+
+impl CallbackGroup {
+    /// Creates a new callback group without any entities.
+    pub(crate) fn new(group_type: CallbackGroupType) -> Self {
+        Self {
+            group_type,
+            subscriptions: Mutex::new(Vec::new()),
+            clients: Mutex::new(Vec::new()),
+            services: Mutex::new(Vec::new()),
+            timers: Mutex::new(Vec::new()),
+            waitables: Mutex::new(Vec::new()),
+            in_use: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the type of the callback group.
+    pub fn group_type(&self) -> CallbackGroupType {
+        self.group_type
+    }
+
+    pub(crate) fn live_subscriptions(&self) -> Vec<Arc<dyn SubscriptionBase>> {
+        live_entities(&self.subscriptions)
+    }
+
+    pub(crate) fn live_clients(&self) -> Vec<Arc<dyn ClientBase>> {
+        live_entities(&self.clients)
+    }
+
+    pub(crate) fn live_services(&self) -> Vec<Arc<dyn ServiceBase>> {
+        live_entities(&self.services)
+    }
+
+    pub(crate) fn live_timers(&self) -> Vec<Arc<Timer>> {
+        live_entities(&self.timers)
+    }
+
+    pub(crate) fn live_waitables(&self) -> Vec<Arc<dyn Waitable>> {
+        live_entities(&self.waitables)
+    }
+}
+
+// Upgrades the weak pointers, and forgets about entities that have been dropped.
+fn live_entities<T: ?Sized>(entities_mtx: &Mutex<Vec<Weak<T>>>) -> Vec<Arc<T>> {
+    let mut entities = entities_mtx.lock().unwrap();
+    entities.retain(|entity| entity.strong_count() > 0);
+    entities.iter().filter_map(Weak::upgrade).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn callback_group_is_send_and_sync() {
+        assert_send::<CallbackGroup>();
+        assert_sync::<CallbackGroup>();
+    }
+}
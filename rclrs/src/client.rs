@@ -196,6 +196,22 @@ where
         Ok(rx.await.unwrap())
     }
 
+    /// Checks whether a service server for this client is available.
+    ///
+    /// Requests that are sent before the server has been discovered may be lost, so it can be
+    /// necessary to wait until this returns true.
+    pub fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        let mut is_ready = false;
+        let client = &*self.handle.lock();
+        let node = &*self.handle.rcl_node_mtx.lock().unwrap();
+        unsafe {
+            // SAFETY: The node and client are valid, and the output pointer is valid.
+            rcl_service_server_is_available(node, client, &mut is_ready)
+        }
+        .ok()?;
+        Ok(is_ready)
+    }
+
     /// Fetches a new response.
     ///
     /// When there is no new message, this will return a
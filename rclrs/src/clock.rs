@@ -0,0 +1,775 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::os::raw::c_void;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::rcl_bindings::*;
+use crate::{Context, Duration, RclrsError, Time, ToResult};
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_clock_t {}
+
+impl Drop for rcl_clock_t {
+    fn drop(&mut self) {
+        // SAFETY: No preconditions for this function (besides passing in a valid clock).
+        unsafe {
+            rcl_clock_fini(self);
+        }
+    }
+}
+
+/// The source of time used by a [`Clock`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClockType {
+    /// ROS time.
+    ///
+    /// This is the same as [`SystemTime`][1], unless it is overridden, e.g. by simulated time
+    /// received on the `/clock` topic.
+    ///
+    /// [1]: ClockType::SystemTime
+    RosTime,
+    /// The wall-clock time of the system, which may jump e.g. when the system time is adjusted.
+    SystemTime,
+    /// A monotonic clock that never jumps, but whose starting point is unspecified.
+    SteadyTime,
+}
+
+impl From<ClockType> for rcl_clock_type_t {
+    fn from(clock_type: ClockType) -> Self {
+        match clock_type {
+            ClockType::RosTime => rcl_clock_type_t::RCL_ROS_TIME,
+            ClockType::SystemTime => rcl_clock_type_t::RCL_SYSTEM_TIME,
+            ClockType::SteadyTime => rcl_clock_type_t::RCL_STEADY_TIME,
+        }
+    }
+}
+
+/// The kind of change of the clock's time source that happened during a [`TimeJump`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClockChange {
+    /// The ROS time override was neither activated nor deactivated.
+    RosTimeNoChange,
+    /// The ROS time override was activated, e.g. by enabling simulated time.
+    RosTimeActivated,
+    /// The ROS time override was deactivated.
+    RosTimeDeactivated,
+    /// The system time jumped, which is only detected for [`ClockType::SystemTime`] clocks.
+    SystemTimeNoChange,
+}
+
+impl From<rcl_clock_change_t> for ClockChange {
+    fn from(clock_change: rcl_clock_change_t) -> Self {
+        match clock_change {
+            rcl_clock_change_t::RCL_ROS_TIME_NO_CHANGE => ClockChange::RosTimeNoChange,
+            rcl_clock_change_t::RCL_ROS_TIME_ACTIVATED => ClockChange::RosTimeActivated,
+            rcl_clock_change_t::RCL_ROS_TIME_DEACTIVATED => ClockChange::RosTimeDeactivated,
+            rcl_clock_change_t::RCL_SYSTEM_TIME_NO_CHANGE => ClockChange::SystemTimeNoChange,
+        }
+    }
+}
+
+/// A discontinuous change in the time of a [`Clock`], as passed to jump callbacks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimeJump {
+    /// How the time source of the clock changed.
+    pub clock_change: ClockChange,
+    /// The difference between the time after and before the jump.
+    ///
+    /// This is negative when the time jumped backwards.
+    pub delta: Duration,
+}
+
+/// Determines which [`TimeJump`]s trigger the callbacks registered with
+/// [`Clock::create_jump_callback`].
+///
+/// The default threshold does not trigger on any jump.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct JumpThreshold {
+    /// Trigger when the ROS time override is activated or deactivated.
+    pub on_clock_change: bool,
+    /// Trigger when the time jumps forward by at least this duration.
+    ///
+    /// Must be positive, or zero to disable this threshold.
+    pub min_forward: Duration,
+    /// Trigger when the time jumps backward by at least this duration.
+    ///
+    /// Must be negative, or zero to disable this threshold.
+    pub min_backward: Duration,
+}
+
+impl From<JumpThreshold> for rcl_jump_threshold_t {
+    fn from(threshold: JumpThreshold) -> Self {
+        rcl_jump_threshold_t {
+            on_clock_change: threshold.on_clock_change,
+            min_forward: rcl_duration_t {
+                nanoseconds: threshold.min_forward.nanoseconds(),
+            },
+            min_backward: rcl_duration_t {
+                nanoseconds: threshold.min_backward.nanoseconds(),
+            },
+        }
+    }
+}
+
+type PreJumpCallback = Box<dyn FnMut() + 'static + Send>;
+type PostJumpCallback = Box<dyn FnMut(&TimeJump) + 'static + Send>;
+
+struct JumpCallbacks {
+    pre_callback: Mutex<PreJumpCallback>,
+    post_callback: Mutex<PostJumpCallback>,
+    // Set when the handler is dropped, so that callbacks which have already been triggered by
+    // rcl are not run anymore.
+    removed: AtomicBool,
+}
+
+// The post-jump callbacks that have been triggered by rcl, but not run yet, and the first panic
+// of a pre-jump callback.
+#[derive(Default)]
+struct PendingJumpCallbacks {
+    post_callbacks: Vec<(Arc<JumpCallbacks>, TimeJump)>,
+    first_panic: Option<Box<dyn Any + Send>>,
+}
+
+// A clock whose time is being changed by this thread.
+struct JumpingClock {
+    rcl_clock_mtx: *const Mutex<rcl_clock_t>,
+    // The clock stays locked while rcl runs the pre-jump callbacks, so they access it through
+    // this pointer instead of locking it again.
+    rcl_clock: *mut rcl_clock_t,
+    // The callbacks of handlers that were dropped by pre-jump callbacks. They can only be removed
+    // from the clock once rcl is done with the jump.
+    removed_callbacks: Vec<Arc<JumpCallbacks>>,
+}
+
+thread_local! {
+    // rcl triggers the jump callbacks synchronously while the clock is locked. The pre-jump
+    // callbacks are run right away, but the post-jump callbacks are collected here and run by the
+    // same thread once the clock has been unlocked.
+    static PENDING_JUMP_CALLBACKS: RefCell<PendingJumpCallbacks> =
+        const {
+            RefCell::new(PendingJumpCallbacks {
+                post_callbacks: Vec::new(),
+                first_panic: None,
+            })
+        };
+    // The clocks whose time is being changed by this thread. There can be more than one if a
+    // pre-jump callback changes the time of another clock.
+    static JUMPING_CLOCKS: RefCell<Vec<JumpingClock>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A registration of jump callbacks on a [`Clock`].
+///
+/// The callbacks stay registered for as long as this handler exists, and are removed from the
+/// clock when it is dropped.
+///
+/// The only available way to instantiate jump handlers is via
+/// [`Clock::create_jump_callback()`].
+pub struct JumpHandler {
+    clock: Clock,
+    // The address of the callbacks is passed to rcl as user data, so they are behind an Arc to
+    // keep the address stable.
+    callbacks: Arc<JumpCallbacks>,
+}
+
+impl Drop for JumpHandler {
+    fn drop(&mut self) {
+        self.callbacks.removed.store(true, Ordering::Release);
+        // A pre-jump callback can't remove callbacks while rcl is running them
+        let deferred = JUMPING_CLOCKS.with(|clocks| {
+            match clocks
+                .borrow_mut()
+                .iter_mut()
+                .find(|clock| clock.rcl_clock_mtx == Arc::as_ptr(&self.clock.rcl_clock_mtx))
+            {
+                Some(clock) => {
+                    clock.removed_callbacks.push(Arc::clone(&self.callbacks));
+                    true
+                }
+                None => false,
+            }
+        });
+        if !deferred {
+            remove_jump_callback(
+                &mut self.clock.rcl_clock_mtx.lock().unwrap(),
+                &self.callbacks,
+            );
+        }
+    }
+}
+
+fn remove_jump_callback(rcl_clock: &mut rcl_clock_t, callbacks: &Arc<JumpCallbacks>) {
+    let ret = unsafe {
+        // SAFETY: The callback and user data are the same that were registered in
+        // Clock::create_jump_callback(), and the clock is valid.
+        rcl_clock_remove_jump_callback(
+            rcl_clock,
+            Some(on_time_jump),
+            Arc::as_ptr(callbacks) as *mut c_void,
+        )
+    };
+    // This only fails for invalid arguments or if the callback was not registered, neither
+    // of which can happen.
+    debug_assert_eq!(ret, 0);
+}
+
+// The callback that is registered with rcl for every JumpHandler.
+unsafe extern "C" fn on_time_jump(
+    time_jump: *const rcl_time_jump_t,
+    before_jump: bool,
+    user_data: *mut c_void,
+) {
+    // SAFETY: The user data was obtained with Arc::as_ptr() from the callbacks of a JumpHandler,
+    // which are kept alive until this callback has been removed from the clock.
+    let callbacks = user_data as *const JumpCallbacks;
+    if (*callbacks).removed.load(Ordering::Acquire) {
+        return;
+    }
+    if before_jump {
+        // A panic must not unwind into rcl, so it is resumed once the time has been changed. A
+        // callback that panicked before is still run, hence the poisoning is ignored.
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            (*(*callbacks)
+                .pre_callback
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner))()
+        }));
+        if let Err(payload) = result {
+            PENDING_JUMP_CALLBACKS.with(|pending| {
+                pending.borrow_mut().first_panic.get_or_insert(payload);
+            });
+        }
+    } else {
+        Arc::increment_strong_count(callbacks);
+        let callbacks = Arc::from_raw(callbacks);
+        // SAFETY: rcl passes a valid time jump.
+        let time_jump = TimeJump {
+            clock_change: ClockChange::from((*time_jump).clock_change),
+            delta: Duration::from_nanoseconds((*time_jump).delta.nanoseconds),
+        };
+        PENDING_JUMP_CALLBACKS.with(|pending| {
+            pending
+                .borrow_mut()
+                .post_callbacks
+                .push((callbacks, time_jump))
+        });
+    }
+}
+
+impl PendingJumpCallbacks {
+    // Runs the post-jump callbacks. All callbacks are run even if one of them panics, and the
+    // first panic, possibly of a pre-jump callback, is resumed afterwards.
+    fn run(self) {
+        let mut first_panic = self.first_panic;
+        for (callbacks, time_jump) in self.post_callbacks {
+            // An earlier callback may have dropped the handler
+            if callbacks.removed.load(Ordering::Acquire) {
+                continue;
+            }
+            // A callback that panicked before is still run, hence the poisoning is ignored
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                (*callbacks
+                    .post_callback
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner))(&time_jump)
+            }));
+            if let Err(payload) = result {
+                first_panic.get_or_insert(payload);
+            }
+        }
+        if let Some(payload) = first_panic {
+            resume_unwind(payload);
+        }
+    }
+}
+
+/// Wakes up a thread in [`Clock::sleep_until`] when the time jumps or the context is shut down.
+#[derive(Default)]
+pub(crate) struct SleepWaker {
+    // Incremented on every wake-up, so that the sleeping thread notices wake-ups that happened
+    // while it was not waiting yet.
+    wake_ups: Mutex<u64>,
+    condvar: Condvar,
+}
+
+impl SleepWaker {
+    pub(crate) fn wake(&self) {
+        *self.wake_ups.lock().unwrap() += 1;
+        self.condvar.notify_all();
+    }
+}
+
+/// A clock that can be queried for the current [`Time`].
+///
+/// Cloning a clock is cheap, and all clones refer to the same underlying `rcl` clock.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Clock, ClockType, RclrsError};
+/// let clock = Clock::new(ClockType::SteadyTime)?;
+/// let start = clock.now();
+/// let end = clock.now();
+/// assert!(start <= end);
+/// assert_eq!(start.clock_type(), ClockType::SteadyTime);
+/// # Ok::<(), RclrsError>(())
+/// ```
+#[derive(Clone)]
+pub struct Clock {
+    clock_type: ClockType,
+    // The clock is behind an Arc so that its address stays the same, since timers store a
+    // pointer to it.
+    pub(crate) rcl_clock_mtx: Arc<Mutex<rcl_clock_t>>,
+}
+
+impl Clock {
+    /// Creates a new clock of the given type.
+    pub fn new(clock_type: ClockType) -> Result<Self, RclrsError> {
+        // SAFETY: The zeroed clock is only used after being initialized by rcl_clock_init.
+        let rcl_clock_mtx = Arc::new(Mutex::new(unsafe { std::mem::zeroed::<rcl_clock_t>() }));
+        unsafe {
+            // SAFETY: No preconditions for this function.
+            let mut allocator = rcutils_get_default_allocator();
+            // SAFETY: The allocator is copied by this function, so it can be dropped afterwards.
+            rcl_clock_init(
+                clock_type.into(),
+                &mut *rcl_clock_mtx.lock().unwrap(),
+                &mut allocator,
+            )
+            .ok()?;
+        }
+        Ok(Self {
+            clock_type,
+            rcl_clock_mtx,
+        })
+    }
+
+    /// Returns the type of the clock.
+    pub fn clock_type(&self) -> ClockType {
+        self.clock_type
+    }
+
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Time {
+        let mut nanoseconds = 0;
+        let ret = unsafe {
+            // SAFETY: The clock is valid and the output pointer is valid.
+            self.with_rcl_clock(|rcl_clock| rcl_clock_get_now(rcl_clock, &mut nanoseconds))
+        };
+        // This cannot fail – rcl_clock_get_now only checks that the arguments are valid and
+        // initialized, which they always are in our case. Hence, only debug_assert instead of
+        // returning Result.
+        debug_assert_eq!(ret, 0);
+        Time::from_nanoseconds(nanoseconds, self.clock_type)
+    }
+
+    /// Checks whether the ROS time of this clock is overridden, e.g. by simulated time.
+    ///
+    /// This is always `false` for clocks that are not of type [`ClockType::RosTime`].
+    pub fn ros_time_is_active(&self) -> bool {
+        if self.clock_type != ClockType::RosTime {
+            return false;
+        }
+        let mut is_enabled = false;
+        let ret = unsafe {
+            // SAFETY: The clock is a valid ROS clock and the output pointer is valid.
+            self.with_rcl_clock(|rcl_clock| {
+                rcl_is_enabled_ros_time_override(rcl_clock, &mut is_enabled)
+            })
+        };
+        // Like rcl_clock_get_now, this only fails for invalid arguments.
+        debug_assert_eq!(ret, 0);
+        is_enabled
+    }
+
+    /// Registers callbacks that are run when the time of the clock jumps.
+    ///
+    /// The `pre_callback` runs immediately before the jump, and the `post_callback` runs
+    /// immediately after it, e.g. when a bag is restarted or a simulation is reset while
+    /// simulated time is used. Only jumps exceeding the `threshold` run the callbacks.
+    ///
+    /// The callbacks are registered until the returned [`JumpHandler`] is dropped.
+    ///
+    /// The callbacks are run by whichever thread changes the time of the clock. The `pre_callback`
+    /// runs while the clock is locked for the change, like in rclcpp. It may read the clock, and
+    /// [`Clock::now`] returns the time before the jump there, but changing the time of the clock
+    /// or creating jump callbacks or timers on it panics. The `post_callback` runs once the clock
+    /// has been unlocked, so it may use the clock without restrictions.
+    ///
+    /// Both callbacks may drop their own [`JumpHandler`], after which its callbacks are not run
+    /// anymore. If a callback panics, the remaining callbacks still run before the panic is
+    /// propagated to the thread that changed the time.
+    pub fn create_jump_callback<Pre, Post>(
+        &self,
+        threshold: JumpThreshold,
+        pre_callback: Pre,
+        post_callback: Post,
+    ) -> Result<JumpHandler, RclrsError>
+    where
+        Pre: FnMut() + 'static + Send,
+        Post: FnMut(&TimeJump) + 'static + Send,
+    {
+        let callbacks = Arc::new(JumpCallbacks {
+            pre_callback: Mutex::new(Box::new(pre_callback)),
+            post_callback: Mutex::new(Box::new(post_callback)),
+            removed: AtomicBool::new(false),
+        });
+        unsafe {
+            // SAFETY: The clock is valid. The user data stays valid until the callback is removed
+            // again when the handler is dropped.
+            rcl_clock_add_jump_callback(
+                &mut *self.lock(),
+                threshold.into(),
+                Some(on_time_jump),
+                Arc::as_ptr(&callbacks) as *mut c_void,
+            )
+            .ok()?;
+        }
+        Ok(JumpHandler {
+            clock: self.clone(),
+            callbacks,
+        })
+    }
+
+    /// Blocks the current thread until the clock has reached the given time.
+    ///
+    /// If the ROS time of the clock is overridden, e.g. by simulated time, this waits until the
+    /// clock is set to the given time or later. This requires that the time is updated by another
+    /// thread, e.g. by spinning the node that receives the `/clock` topic.
+    ///
+    /// The sleep is interrupted when the context is shut down, in which case
+    /// [`RclrsError::ContextShutDown`] is returned.
+    ///
+    /// # Panics
+    /// Panics if the clock type of `until` differs from that of the clock.
+    pub fn sleep_until(&self, until: Time, context: &Context) -> Result<(), RclrsError> {
+        assert_eq!(
+            until.clock_type(),
+            self.clock_type,
+            "Cannot sleep until a time of a different clock type"
+        );
+        let waker = Arc::new(SleepWaker::default());
+        context.add_shutdown_sleep_waker(&waker);
+        // The time of a ROS clock can also change through jumps, e.g. when simulated time is
+        // received, which wake up the sleeping thread too. Other clocks are only re-checked after
+        // the timeout, since a system clock might jump backwards during the sleep.
+        let _handler = if self.clock_type == ClockType::RosTime {
+            let threshold = JumpThreshold {
+                on_clock_change: true,
+                min_forward: Duration::from_nanoseconds(1),
+                min_backward: Duration::from_nanoseconds(-1),
+            };
+            let waker = Arc::clone(&waker);
+            Some(self.create_jump_callback(threshold, || {}, move |_| waker.wake())?)
+        } else {
+            None
+        };
+        loop {
+            let wake_ups_seen = *waker.wake_ups.lock().unwrap();
+            if !context.ok() {
+                return Err(RclrsError::ContextShutDown);
+            }
+            let remaining = until - self.now();
+            if remaining.nanoseconds() <= 0 {
+                return Ok(());
+            }
+            let ros_time_is_active = self.ros_time_is_active();
+            let guard = waker.wake_ups.lock().unwrap();
+            if ros_time_is_active {
+                // The time only changes through jumps
+                let _guard = waker
+                    .condvar
+                    .wait_while(guard, |wake_ups| *wake_ups == wake_ups_seen)
+                    .unwrap();
+            } else {
+                // The remaining duration is positive here, so it can always be converted
+                let timeout = remaining.to_std().unwrap_or_default();
+                let _guard = waker
+                    .condvar
+                    .wait_timeout_while(guard, timeout, |wake_ups| *wake_ups == wake_ups_seen)
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Enables or disables overriding the ROS time of this clock.
+    ///
+    /// While the override is enabled, the clock returns the time last set with
+    /// [`Clock::set_ros_time_override`] instead of the system time.
+    pub(crate) fn set_ros_time_override_enabled(&self, enabled: bool) -> Result<(), RclrsError> {
+        self.change_time(|rcl_clock| unsafe {
+            // SAFETY: The clock is valid. These functions fail if it is not a ROS clock.
+            if enabled {
+                rcl_enable_ros_time_override(rcl_clock)
+            } else {
+                rcl_disable_ros_time_override(rcl_clock)
+            }
+        })
+    }
+
+    /// Sets the ROS time that is returned by the clock while the override is enabled.
+    pub(crate) fn set_ros_time_override(&self, time: Time) -> Result<(), RclrsError> {
+        self.change_time(|rcl_clock| unsafe {
+            // SAFETY: The clock is valid. This function fails if it is not a ROS clock.
+            rcl_set_ros_time_override(rcl_clock, time.nanoseconds())
+        })
+    }
+
+    /// Locks the clock in order to change it.
+    ///
+    /// # Panics
+    /// Panics if called by a pre-jump callback of this clock, since the clock is already locked
+    /// and must not be changed during the jump.
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_clock_t> {
+        assert!(
+            self.jumping_rcl_clock().is_none(),
+            "A clock cannot be changed by its own pre-jump callbacks"
+        );
+        self.rcl_clock_mtx.lock().unwrap()
+    }
+
+    // Returns the clock if its time is being changed by this thread, i.e. when called by one of
+    // its pre-jump callbacks.
+    fn jumping_rcl_clock(&self) -> Option<*mut rcl_clock_t> {
+        JUMPING_CLOCKS.with(|clocks| {
+            clocks
+                .borrow()
+                .iter()
+                .find(|clock| clock.rcl_clock_mtx == Arc::as_ptr(&self.rcl_clock_mtx))
+                .map(|clock| clock.rcl_clock)
+        })
+    }
+
+    // Calls an rcl function that reads the clock, without locking it again if this thread is
+    // changing its time.
+    fn with_rcl_clock<R>(&self, f: impl FnOnce(*mut rcl_clock_t) -> R) -> R {
+        match self.jumping_rcl_clock() {
+            Some(rcl_clock) => f(rcl_clock),
+            None => f(&mut *self.rcl_clock_mtx.lock().unwrap()),
+        }
+    }
+
+    // Calls an rcl function that may trigger jump callbacks. The pre-jump callbacks are run by rcl
+    // before the time is changed, the post-jump callbacks once the clock has been unlocked.
+    fn change_time(&self, f: impl FnOnce(*mut rcl_clock_t) -> rcl_ret_t) -> Result<(), RclrsError> {
+        let mut rcl_clock = self.lock();
+        // If this is called by a jump callback of another clock, its pending callbacks are set
+        // aside until this jump is done
+        let outer_pending = PENDING_JUMP_CALLBACKS.with(|pending| pending.take());
+        JUMPING_CLOCKS.with(|clocks| {
+            clocks.borrow_mut().push(JumpingClock {
+                rcl_clock_mtx: Arc::as_ptr(&self.rcl_clock_mtx),
+                rcl_clock: &mut *rcl_clock,
+                removed_callbacks: Vec::new(),
+            })
+        });
+        let ret = f(&mut *rcl_clock);
+        // The jumps of other clocks that are started by the callbacks have ended already, so the
+        // last clock is this one
+        let jumping_clock = JUMPING_CLOCKS.with(|clocks| clocks.borrow_mut().pop());
+        for callbacks in jumping_clock
+            .into_iter()
+            .flat_map(|clock| clock.removed_callbacks)
+        {
+            remove_jump_callback(&mut rcl_clock, &callbacks);
+        }
+        drop(rcl_clock);
+        PENDING_JUMP_CALLBACKS
+            .with(|pending| pending.replace(outer_pending))
+            .run();
+        ret.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn clock_is_send_and_sync() {
+        assert_send::<Clock>();
+        assert_sync::<Clock>();
+        assert_send::<JumpHandler>();
+        assert_sync::<JumpHandler>();
+    }
+
+    #[test]
+    fn test_clock_types() -> Result<(), RclrsError> {
+        for clock_type in [
+            ClockType::RosTime,
+            ClockType::SystemTime,
+            ClockType::SteadyTime,
+        ] {
+            let clock = Clock::new(clock_type)?;
+            assert_eq!(clock.clock_type(), clock_type);
+            let now = clock.now();
+            assert_eq!(now.clock_type(), clock_type);
+            assert!(now.nanoseconds() > 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_system_clock_matches_system_time() -> Result<(), RclrsError> {
+        let clock = Clock::new(ClockType::SystemTime)?;
+        let before = std::time::SystemTime::now();
+        let now = clock.now();
+        let after = std::time::SystemTime::now();
+        assert!(Time::from(before) <= now);
+        assert!(now <= Time::from(after));
+        Ok(())
+    }
+
+    #[test]
+    fn test_jump_callbacks() -> Result<(), RclrsError> {
+        let clock = Clock::new(ClockType::RosTime)?;
+        let jumps = Arc::new(Mutex::new(Vec::new()));
+        let pre_jumps = Arc::clone(&jumps);
+        let post_jumps = Arc::clone(&jumps);
+        let threshold = JumpThreshold {
+            on_clock_change: true,
+            min_forward: Duration::from_nanoseconds(0),
+            min_backward: Duration::from_nanoseconds(-1),
+        };
+        let handler = clock.create_jump_callback(
+            threshold,
+            move || pre_jumps.lock().unwrap().push(None),
+            move |time_jump| post_jumps.lock().unwrap().push(Some(*time_jump)),
+        )?;
+
+        clock.set_ros_time_override(Time::new(10, 0, ClockType::RosTime))?;
+        clock.set_ros_time_override_enabled(true)?;
+        assert_eq!(jumps.lock().unwrap().len(), 2);
+        assert_eq!(
+            jumps.lock().unwrap()[1].unwrap().clock_change,
+            ClockChange::RosTimeActivated
+        );
+        jumps.lock().unwrap().clear();
+
+        // Forward jumps are not reported, since min_forward is zero
+        clock.set_ros_time_override(Time::new(20, 0, ClockType::RosTime))?;
+        assert!(jumps.lock().unwrap().is_empty());
+
+        clock.set_ros_time_override(Time::new(5, 0, ClockType::RosTime))?;
+        let expected_jump = TimeJump {
+            clock_change: ClockChange::RosTimeNoChange,
+            delta: Duration::new(-15, 0),
+        };
+        assert_eq!(*jumps.lock().unwrap(), [None, Some(expected_jump)]);
+        jumps.lock().unwrap().clear();
+
+        // After dropping the handler, the callbacks are no longer run
+        drop(handler);
+        clock.set_ros_time_override(Time::new(1, 0, ClockType::RosTime))?;
+        assert!(jumps.lock().unwrap().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sleep_until_is_interrupted_by_shutdown() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let clock = Clock::new(ClockType::RosTime)?;
+        clock.set_ros_time_override_enabled(true)?;
+        std::thread::scope(|scope| {
+            // The time never advances, so only the shutdown can end the sleep
+            let sleeping =
+                scope.spawn(|| clock.sleep_until(Time::new(1, 0, ClockType::RosTime), &context));
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            context.shutdown("test")?;
+            assert_eq!(sleeping.join().unwrap(), Err(RclrsError::ContextShutDown));
+            // Sleeping after the shutdown fails right away
+            assert_eq!(
+                clock.sleep_until(Time::new(1, 0, ClockType::RosTime), &context),
+                Err(RclrsError::ContextShutDown)
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_jump_callbacks_can_use_clock() -> Result<(), RclrsError> {
+        let clock = Clock::new(ClockType::RosTime)?;
+        clock.set_ros_time_override_enabled(true)?;
+        let threshold = JumpThreshold {
+            on_clock_change: false,
+            min_forward: Duration::from_nanoseconds(1),
+            min_backward: Duration::from_nanoseconds(0),
+        };
+        let times = Arc::new(Mutex::new(Vec::new()));
+        let handler_slot = Arc::new(Mutex::new(None));
+        let (pre_clock, pre_times) = (clock.clone(), Arc::clone(&times));
+        let (post_clock, post_times, slot_for_callback) =
+            (clock.clone(), Arc::clone(&times), Arc::clone(&handler_slot));
+        let handler = clock.create_jump_callback(
+            threshold,
+            move || pre_times.lock().unwrap().push(pre_clock.now()),
+            move |_| {
+                post_times.lock().unwrap().push(post_clock.now());
+                // The handler removes itself on the first jump
+                drop(slot_for_callback.lock().unwrap().take());
+            },
+        )?;
+        *handler_slot.lock().unwrap() = Some(handler);
+
+        clock.set_ros_time_override(Time::new(3, 0, ClockType::RosTime))?;
+        clock.set_ros_time_override(Time::new(4, 0, ClockType::RosTime))?;
+        // The pre-jump callback still sees the time before the jump
+        assert_eq!(
+            *times.lock().unwrap(),
+            [
+                Time::new(0, 0, ClockType::RosTime),
+                Time::new(3, 0, ClockType::RosTime)
+            ]
+        );
+        assert!(handler_slot.lock().unwrap().is_none());
+
+        // A handler that is dropped by its pre-jump callback doesn't run its post-jump callback
+        let post_jumps = Arc::new(Mutex::new(0));
+        let post_jumps_for_callback = Arc::clone(&post_jumps);
+        let slot_for_callback = Arc::clone(&handler_slot);
+        let handler = clock.create_jump_callback(
+            threshold,
+            move || drop(slot_for_callback.lock().unwrap().take()),
+            move |_| *post_jumps_for_callback.lock().unwrap() += 1,
+        )?;
+        *handler_slot.lock().unwrap() = Some(handler);
+        clock.set_ros_time_override(Time::new(5, 0, ClockType::RosTime))?;
+        clock.set_ros_time_override(Time::new(6, 0, ClockType::RosTime))?;
+        assert!(handler_slot.lock().unwrap().is_none());
+        assert_eq!(*post_jumps.lock().unwrap(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_panicking_jump_callback() -> Result<(), RclrsError> {
+        let clock = Clock::new(ClockType::RosTime)?;
+        let threshold = JumpThreshold {
+            on_clock_change: true,
+            ..Default::default()
+        };
+        let _panicking_handler =
+            clock.create_jump_callback(threshold, || panic!("pre callback"), |_| {})?;
+        let post_jumps = Arc::new(Mutex::new(0));
+        let post_jumps_for_callback = Arc::clone(&post_jumps);
+        let _handler = clock.create_jump_callback(
+            threshold,
+            || {},
+            move |_| {
+                *post_jumps_for_callback.lock().unwrap() += 1;
+            },
+        )?;
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            clock.set_ros_time_override_enabled(true)
+        }));
+        assert!(result.is_err());
+        // The other callbacks have still run, and the clock is usable
+        assert_eq!(*post_jumps.lock().unwrap(), 1);
+        assert!(clock.ros_time_is_active());
+        Ok(())
+    }
+}
@@ -1,23 +1,28 @@
-use std::ffi::CString;
-use std::os::raw::c_char;
 use std::string::String;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 use std::vec::Vec;
 
+mod builder;
+mod signal_handler;
+pub use builder::*;
+pub use signal_handler::*;
+
 use crate::rcl_bindings::*;
-use crate::{RclrsError, ToResult};
+use crate::{GuardCondition, RclrsError, SleepWaker, ToResult};
 
 impl Drop for rcl_context_t {
     fn drop(&mut self) {
         unsafe {
-            // The context may be invalid when rcl_init failed, e.g. because of invalid command
-            // line arguments.
+            // The context is already invalid when it has been shut down explicitly.
             // SAFETY: No preconditions for this function.
             if rcl_context_is_valid(self) {
-                // SAFETY: These functions have no preconditions besides a valid rcl_context
+                // SAFETY: No preconditions for this function besides a valid rcl_context
                 rcl_shutdown(self);
-                rcl_context_fini(self);
             }
+            // The context may be zero-initialized when rcl_init failed, e.g. because of invalid
+            // command line arguments. rcl_context_fini() does nothing in that case.
+            // SAFETY: The context is not valid anymore, as required by this function.
+            rcl_context_fini(self);
         }
     }
 }
@@ -38,8 +43,22 @@ unsafe impl Send for rcl_context_t {}
 /// - middleware-specific data, e.g. the domain participant in DDS
 /// - the allocator used (left as the default by `rclrs`)
 ///
+/// Cloning a `Context` does not create a new context, but another reference to the same one.
+#[derive(Clone)]
 pub struct Context {
     pub(crate) rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+    pub(crate) shutdown_state: Arc<ShutdownState>,
+}
+
+// What is needed for shutting down the context, besides the rcl_context.
+#[derive(Default)]
+pub(crate) struct ShutdownState {
+    reason: Mutex<Option<String>>,
+    callbacks: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+    // Guard conditions of wait sets that need to wake up when the context is shut down.
+    guard_conditions: Mutex<Vec<Weak<GuardCondition>>>,
+    // Threads sleeping in Clock::sleep_until(), which also need to wake up.
+    sleep_wakers: Mutex<Vec<Weak<SleepWaker>>>,
 }
 
 impl Context {
@@ -58,62 +77,141 @@ impl Context {
     /// assert!(Context::new(invalid_remapping).is_err());
     /// ```
     pub fn new(args: impl IntoIterator<Item = String>) -> Result<Self, RclrsError> {
-        // SAFETY: Getting a zero-initialized value is always safe
-        let mut rcl_context = unsafe { rcl_get_zero_initialized_context() };
-        let cstring_args: Vec<CString> = args
-            .into_iter()
-            .map(|arg| {
-                CString::new(arg.as_str()).map_err(|err| RclrsError::StringContainsNul {
-                    err,
-                    s: arg.clone(),
-                })
-            })
-            .collect::<Result<_, _>>()?;
-        // Vector of pointers into cstring_args
-        let c_args: Vec<*const c_char> = cstring_args.iter().map(|arg| arg.as_ptr()).collect();
-        unsafe {
-            // SAFETY: No preconditions for this function.
-            let allocator = rcutils_get_default_allocator();
-            // SAFETY: Getting a zero-initialized value is always safe.
-            let mut rcl_init_options = rcl_get_zero_initialized_init_options();
-            // SAFETY: Passing in a zero-initialized value is expected.
-            // In the case where this returns not ok, there's nothing to clean up.
-            rcl_init_options_init(&mut rcl_init_options, allocator).ok()?;
-            // SAFETY: This function does not store the ephemeral init_options and c_args
-            // pointers. Passing in a zero-initialized rcl_context is expected.
-            let ret = rcl_init(
-                c_args.len() as i32,
-                if c_args.is_empty() {
-                    std::ptr::null()
-                } else {
-                    c_args.as_ptr()
-                },
-                &rcl_init_options,
-                &mut rcl_context,
-            )
-            .ok();
-            // SAFETY: It's safe to pass in an initialized object.
-            // Early return will not leak memory, because this is the last fini function.
-            rcl_init_options_fini(&mut rcl_init_options).ok()?;
-            // Move the check after the last fini()
-            ret?;
-        }
-        Ok(Self {
-            rcl_context_mtx: Arc::new(Mutex::new(rcl_context)),
-        })
+        Self::builder(args).build()
+    }
+
+    /// Creates a [`ContextBuilder`][1] with the given command line arguments.
+    ///
+    /// Convenience function equivalent to [`ContextBuilder::new()`][2].
+    ///
+    /// [1]: crate::ContextBuilder
+    /// [2]: crate::ContextBuilder::new
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::builder([]).localhost_only(true).build()?;
+    /// assert!(context.ok());
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn builder(args: impl IntoIterator<Item = String>) -> ContextBuilder {
+        ContextBuilder::new(args)
     }
 
     /// Checks if the context is still valid.
     ///
-    /// This will return `false` when a signal has caused the context to shut down (currently
-    /// unimplemented).
+    /// This will return `false` after the context has been shut down, either with
+    /// [`Context::shutdown`] or by a signal, if [`install_signal_handler`] has been called.
     pub fn ok(&self) -> bool {
-        // This will currently always return true, but once we have a signal handler, the signal
-        // handler could call `rcl_shutdown()`, hence making the context invalid.
         let rcl_context = &mut *self.rcl_context_mtx.lock().unwrap();
         // SAFETY: No preconditions for this function.
         unsafe { rcl_context_is_valid(rcl_context) }
     }
+
+    /// Shuts down the context.
+    ///
+    /// Afterwards, [`Context::ok`] returns `false`, and the spin functions and executors that
+    /// spin nodes of this context return, even if they are currently waiting. The callbacks
+    /// registered with [`Context::on_shutdown`] are called before this function returns.
+    ///
+    /// Shutting down a context that has already been shut down has no effect.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// context.on_shutdown(|| println!("Goodbye"));
+    /// context.shutdown("done")?;
+    /// assert!(!context.ok());
+    /// assert_eq!(context.shutdown_reason().as_deref(), Some("done"));
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn shutdown(&self, reason: &str) -> Result<(), RclrsError> {
+        {
+            let rcl_context = &mut *self.rcl_context_mtx.lock().unwrap();
+            // SAFETY: No preconditions for this function.
+            if !unsafe { rcl_context_is_valid(rcl_context) } {
+                return Ok(());
+            }
+            // SAFETY: The context is valid.
+            unsafe { rcl_shutdown(rcl_context) }.ok()?;
+            *self.shutdown_state.reason.lock().unwrap() = Some(reason.to_string());
+        }
+        let guard_conditions = self.shutdown_state.guard_conditions.lock().unwrap();
+        for guard_condition in guard_conditions.iter().filter_map(Weak::upgrade) {
+            // Triggering a valid guard condition does not fail
+            let _ = guard_condition.trigger();
+        }
+        drop(guard_conditions);
+        let sleep_wakers = self.shutdown_state.sleep_wakers.lock().unwrap();
+        for sleep_waker in sleep_wakers.iter().filter_map(Weak::upgrade) {
+            sleep_waker.wake();
+        }
+        drop(sleep_wakers);
+        // The callbacks are called without holding any lock, so that they may use the context
+        let callbacks = std::mem::take(&mut *self.shutdown_state.callbacks.lock().unwrap());
+        for callback in callbacks {
+            callback();
+        }
+        Ok(())
+    }
+
+    /// Registers a callback that is called when the context is shut down.
+    ///
+    /// The callback is called by the thread that shuts down the context. If the context has
+    /// already been shut down, the callback is called immediately.
+    pub fn on_shutdown<F>(&self, callback: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut callbacks = self.shutdown_state.callbacks.lock().unwrap();
+        // The reason is set before the callbacks are taken in shutdown(), so checking it while
+        // holding the lock ensures that the callback is called exactly once.
+        if self.shutdown_state.reason.lock().unwrap().is_none() {
+            callbacks.push(Box::new(callback));
+        } else {
+            drop(callbacks);
+            callback();
+        }
+    }
+
+    /// Returns the reason that was given to [`Context::shutdown`], if the context has been shut
+    /// down.
+    pub fn shutdown_reason(&self) -> Option<String> {
+        self.shutdown_state.reason.lock().unwrap().clone()
+    }
+
+    /// Makes the guard condition trigger when the context is shut down, so that wait sets
+    /// containing it wake up.
+    pub(crate) fn add_shutdown_guard_condition(&self, guard_condition: &Arc<GuardCondition>) {
+        {
+            let mut guard_conditions = self.shutdown_state.guard_conditions.lock().unwrap();
+            guard_conditions.retain(|weak| weak.strong_count() > 0);
+            if guard_conditions
+                .iter()
+                .any(|weak| weak.as_ptr() == Arc::as_ptr(guard_condition))
+            {
+                return;
+            }
+            guard_conditions.push(Arc::downgrade(guard_condition));
+        }
+        // The context may have been shut down before the guard condition was added
+        if !self.ok() {
+            let _ = guard_condition.trigger();
+        }
+    }
+
+    /// Wakes up a thread sleeping in [`Clock::sleep_until`][1] when the context is shut down.
+    ///
+    /// The sleeping thread has to check [`Context::ok`] after adding the waker, since the
+    /// context may have been shut down before.
+    ///
+    /// [1]: crate::Clock::sleep_until
+    pub(crate) fn add_shutdown_sleep_waker(&self, sleep_waker: &Arc<SleepWaker>) {
+        let mut sleep_wakers = self.shutdown_state.sleep_wakers.lock().unwrap();
+        sleep_wakers.retain(|weak| weak.strong_count() > 0);
+        sleep_wakers.push(Arc::downgrade(sleep_waker));
+    }
 }
 
 #[cfg(test)]
@@ -144,4 +242,45 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_shutdown_calls_callbacks_once() -> Result<(), RclrsError> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let context = Context::new([])?;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_in_callback = Arc::clone(&calls);
+        context.on_shutdown(move || {
+            calls_in_callback.fetch_add(1, Ordering::Relaxed);
+        });
+        assert!(context.shutdown_reason().is_none());
+
+        context.shutdown("first")?;
+        assert!(!context.ok());
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        // Shutting down again does nothing
+        context.shutdown("second")?;
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(context.shutdown_reason().as_deref(), Some("first"));
+
+        // Callbacks registered after shutdown are called right away
+        let calls_in_callback = Arc::clone(&calls);
+        context.on_shutdown(move || {
+            calls_in_callback.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shutdown_wakes_up_spin() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = crate::create_node(&context, "test_shutdown_wakes_up_spin")?;
+        std::thread::scope(|scope| {
+            let spinning = scope.spawn(|| crate::spin(&node));
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            context.shutdown("test")?;
+            spinning.join().unwrap()
+        })
+    }
 }
@@ -0,0 +1,316 @@
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::sync::{Arc, Mutex};
+
+use super::{register_context, ShutdownState};
+use crate::rcl_bindings::*;
+use crate::{configure_logging, Context, RclrsError, ToResult};
+
+/// A builder for creating a [`Context`][1].
+///
+/// The builder pattern allows selectively setting some options, and leaving all others at their
+/// default values. Options that are not set are taken from the environment variables, e.g.
+/// `ROS_DOMAIN_ID` and `ROS_LOCALHOST_ONLY`, like with [`Context::new()`][2].
+/// This struct instance can be created via [`Context::builder()`][3].
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, ContextBuilder, RclrsError};
+/// let context = Context::builder([])
+///     .domain_id(42)
+///     .localhost_only(true)
+///     .build()?;
+/// let node = rclrs::create_node(&context, "my_node")?;
+/// assert_eq!(node.domain_id(), 42);
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Context
+/// [2]: crate::Context::new
+/// [3]: crate::Context::builder
+pub struct ContextBuilder {
+    args: Vec<String>,
+    #[cfg(not(ros_distro = "foxy"))]
+    domain_id: Option<usize>,
+    localhost_only: Option<bool>,
+    enclave: Option<String>,
+    allocator: Option<Allocator>,
+}
+
+impl ContextBuilder {
+    /// Creates a builder for a context with the given command line arguments.
+    ///
+    /// See [`Context::new()`][1] for the meaning of the arguments.
+    ///
+    /// [1]: crate::Context::new
+    pub fn new(args: impl IntoIterator<Item = String>) -> ContextBuilder {
+        ContextBuilder {
+            args: args.into_iter().collect(),
+            #[cfg(not(ros_distro = "foxy"))]
+            domain_id: None,
+            localhost_only: None,
+            enclave: None,
+            allocator: None,
+        }
+    }
+
+    /// Sets the ROS domain ID, instead of taking it from the `ROS_DOMAIN_ID` environment
+    /// variable.
+    ///
+    /// Nodes in different domains do not communicate with each other, which can e.g. be used to
+    /// isolate tests that run in parallel.
+    ///
+    /// This is not available on Foxy, where the domain ID is a node option.
+    #[cfg(not(ros_distro = "foxy"))]
+    pub fn domain_id(mut self, domain_id: usize) -> Self {
+        self.domain_id = Some(domain_id);
+        self
+    }
+
+    /// Sets whether communication is restricted to the local host, instead of taking it from the
+    /// `ROS_LOCALHOST_ONLY` environment variable.
+    pub fn localhost_only(mut self, localhost_only: bool) -> Self {
+        self.localhost_only = Some(localhost_only);
+        self
+    }
+
+    /// Sets the security enclave.
+    ///
+    /// This is equivalent to passing `--ros-args --enclave <enclave>` after the other command
+    /// line arguments. The enclave name is validated in [`ContextBuilder::build()`][1].
+    ///
+    /// [1]: ContextBuilder::build
+    pub fn enclave(mut self, enclave: &str) -> Self {
+        self.enclave = Some(enclave.to_string());
+        self
+    }
+
+    /// Sets the allocator that the `rcl` layer uses for the context.
+    pub fn allocator(mut self, allocator: Allocator) -> Self {
+        self.allocator = Some(allocator);
+        self
+    }
+
+    /// Builds the context instance.
+    ///
+    /// This fails in case the args contain invalid ROS arguments, or one of the options is
+    /// invalid.
+    ///
+    /// For example usage, see the [`ContextBuilder`][1] docs.
+    ///
+    /// [1]: crate::ContextBuilder
+    pub fn build(&self) -> Result<Context, RclrsError> {
+        let enclave_args = self
+            .enclave
+            .iter()
+            .flat_map(|enclave| ["--ros-args", "--enclave", enclave.as_str()]);
+        let cstring_args: Vec<CString> = self
+            .args
+            .iter()
+            .map(String::as_str)
+            .chain(enclave_args)
+            .map(|arg| {
+                CString::new(arg).map_err(|err| RclrsError::StringContainsNul {
+                    err,
+                    s: arg.to_string(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        // Vector of pointers into cstring_args
+        let c_args: Vec<*const c_char> = cstring_args.iter().map(|arg| arg.as_ptr()).collect();
+        // SAFETY: Getting a zero-initialized value is always safe
+        let mut rcl_context = unsafe { rcl_get_zero_initialized_context() };
+        unsafe {
+            let allocator = match &self.allocator {
+                Some(allocator) => allocator.to_rcl_allocator(),
+                // SAFETY: No preconditions for this function.
+                None => rcutils_get_default_allocator(),
+            };
+            // SAFETY: Getting a zero-initialized value is always safe.
+            let mut rcl_init_options = rcl_get_zero_initialized_init_options();
+            // SAFETY: Passing in a zero-initialized value is expected.
+            // In the case where this returns not ok, there's nothing to clean up.
+            rcl_init_options_init(&mut rcl_init_options, allocator).ok()?;
+            // SAFETY: This function does not store the ephemeral init_options and c_args
+            // pointers. Passing in a zero-initialized rcl_context is expected.
+            let ret = self
+                .configure_init_options(&mut rcl_init_options)
+                .and_then(|()| {
+                    rcl_init(
+                        c_args.len() as i32,
+                        if c_args.is_empty() {
+                            std::ptr::null()
+                        } else {
+                            c_args.as_ptr()
+                        },
+                        &rcl_init_options,
+                        &mut rcl_context,
+                    )
+                    .ok()
+                });
+            // SAFETY: It's safe to pass in an initialized object.
+            // Early return will not leak memory, because this is the last fini function.
+            rcl_init_options_fini(&mut rcl_init_options).ok()?;
+            // Move the check after the last fini()
+            ret?;
+        }
+        // Logging has to be configured before creating nodes, so that rcl_node_init() creates
+        // their /rosout publishers.
+        configure_logging(&rcl_context.global_arguments)?;
+        let context = Context {
+            rcl_context_mtx: Arc::new(Mutex::new(rcl_context)),
+            shutdown_state: Arc::new(ShutdownState::default()),
+        };
+        register_context(&context);
+        Ok(context)
+    }
+
+    /// Applies the options of this builder to initialized init options.
+    ///
+    /// Options that are left at their default are resolved from the environment by `rcl_init()`.
+    fn configure_init_options(
+        &self,
+        rcl_init_options: &mut rcl_init_options_t,
+    ) -> Result<(), RclrsError> {
+        #[cfg(not(ros_distro = "foxy"))]
+        if let Some(domain_id) = self.domain_id {
+            // SAFETY: The init options are initialized.
+            unsafe { rcl_init_options_set_domain_id(rcl_init_options, domain_id) }.ok()?;
+        }
+        if let Some(localhost_only) = self.localhost_only {
+            // SAFETY: The init options are initialized, so this returns a valid pointer.
+            let rmw_init_options =
+                unsafe { &mut *rcl_init_options_get_rmw_init_options(rcl_init_options) };
+            rmw_init_options.localhost_only = if localhost_only {
+                rmw_localhost_only_t::RMW_LOCALHOST_ONLY_ENABLED
+            } else {
+                rmw_localhost_only_t::RMW_LOCALHOST_ONLY_DISABLED
+            };
+        }
+        Ok(())
+    }
+}
+
+/// The function signature of [`Allocator::from_raw_parts()`]'s `allocate` function.
+pub type AllocateFn = unsafe extern "C" fn(size: usize, state: *mut c_void) -> *mut c_void;
+/// The function signature of [`Allocator::from_raw_parts()`]'s `deallocate` function.
+pub type DeallocateFn = unsafe extern "C" fn(pointer: *mut c_void, state: *mut c_void);
+/// The function signature of [`Allocator::from_raw_parts()`]'s `reallocate` function.
+pub type ReallocateFn =
+    unsafe extern "C" fn(pointer: *mut c_void, size: usize, state: *mut c_void) -> *mut c_void;
+/// The function signature of [`Allocator::from_raw_parts()`]'s `zero_allocate` function.
+pub type ZeroAllocateFn = unsafe extern "C" fn(
+    number_of_elements: usize,
+    size_of_element: usize,
+    state: *mut c_void,
+) -> *mut c_void;
+
+/// A memory allocator for the `rcl` layer, used with [`ContextBuilder::allocator()`].
+///
+/// The default allocator uses the C standard library functions `malloc()`, `free()`, etc.
+pub struct Allocator {
+    allocate: AllocateFn,
+    deallocate: DeallocateFn,
+    reallocate: ReallocateFn,
+    zero_allocate: ZeroAllocateFn,
+    state: *mut c_void,
+}
+
+// SAFETY: The allocator functions are required to be thread-safe by from_raw_parts(), and the
+// state is only ever passed to them.
+unsafe impl Send for Allocator {}
+unsafe impl Sync for Allocator {}
+
+impl Default for Allocator {
+    fn default() -> Self {
+        // SAFETY: No preconditions for this function.
+        let rcl_allocator = unsafe { rcutils_get_default_allocator() };
+        // The default allocator always has all functions set
+        Self {
+            allocate: rcl_allocator.allocate.unwrap(),
+            deallocate: rcl_allocator.deallocate.unwrap(),
+            reallocate: rcl_allocator.reallocate.unwrap(),
+            zero_allocate: rcl_allocator.zero_allocate.unwrap(),
+            state: rcl_allocator.state,
+        }
+    }
+}
+
+impl Allocator {
+    /// Creates an allocator from its functions and the state that is passed to each of them.
+    ///
+    /// # Safety
+    /// The functions must behave like `malloc()`, `free()`, `realloc()` and `calloc()` from the
+    /// C standard library respectively, and be callable from any thread. The `state` must stay
+    /// valid as long as a context that uses this allocator exists.
+    pub unsafe fn from_raw_parts(
+        allocate: AllocateFn,
+        deallocate: DeallocateFn,
+        reallocate: ReallocateFn,
+        zero_allocate: ZeroAllocateFn,
+        state: *mut c_void,
+    ) -> Self {
+        Self {
+            allocate,
+            deallocate,
+            reallocate,
+            zero_allocate,
+            state,
+        }
+    }
+
+    fn to_rcl_allocator(&self) -> rcl_allocator_t {
+        rcl_allocator_t {
+            allocate: Some(self.allocate),
+            deallocate: Some(self.deallocate),
+            reallocate: Some(self.reallocate),
+            zero_allocate: Some(self.zero_allocate),
+            state: self.state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn context_builder_is_send_and_sync() {
+        assert_send::<ContextBuilder>();
+        assert_sync::<ContextBuilder>();
+    }
+
+    #[test]
+    #[cfg(not(ros_distro = "foxy"))]
+    fn test_contexts_with_different_domain_ids() -> Result<(), RclrsError> {
+        for domain_id in [3, 7] {
+            let context = Context::builder([]).domain_id(domain_id).build()?;
+            let node = crate::create_node(&context, "test_contexts_with_different_domain_ids")?;
+            assert_eq!(node.domain_id(), domain_id);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_enclave() -> Result<(), RclrsError> {
+        let _ = Context::builder([]).enclave("/my_enclave").build()?;
+        assert!(Context::builder([])
+            .enclave("not an enclave")
+            .build()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_localhost_only_and_default_allocator() -> Result<(), RclrsError> {
+        let context = Context::builder([])
+            .localhost_only(true)
+            .allocator(Allocator::default())
+            .build()?;
+        assert!(context.ok());
+        Ok(())
+    }
+}
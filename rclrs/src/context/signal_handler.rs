@@ -0,0 +1,116 @@
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use super::ShutdownState;
+use crate::rcl_bindings::rcl_context_t;
+use crate::Context;
+
+// These values are the same on all supported platforms.
+const SIGINT: c_int = 2;
+const SIGTERM: c_int = 15;
+const SIG_DFL: usize = 0;
+
+// How often the watcher thread checks whether a signal has been received.
+const WATCHER_PERIOD: Duration = Duration::from_millis(20);
+
+extern "C" {
+    // From the C standard library, which is available on all platforms.
+    fn signal(signum: c_int, handler: usize) -> usize;
+}
+
+// The contexts that are shut down when a signal is received.
+static CONTEXTS: Mutex<Vec<WeakContext>> = Mutex::new(Vec::new());
+// The signal that has been received, or 0.
+static RECEIVED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+
+struct WeakContext {
+    rcl_context_mtx: Weak<Mutex<rcl_context_t>>,
+    shutdown_state: Weak<ShutdownState>,
+}
+
+impl WeakContext {
+    fn upgrade(&self) -> Option<Context> {
+        Some(Context {
+            rcl_context_mtx: self.rcl_context_mtx.upgrade()?,
+            shutdown_state: self.shutdown_state.upgrade()?,
+        })
+    }
+}
+
+pub(super) fn register_context(context: &Context) {
+    let mut contexts = CONTEXTS.lock().unwrap();
+    contexts.retain(|weak| weak.rcl_context_mtx.strong_count() > 0);
+    contexts.push(WeakContext {
+        rcl_context_mtx: Arc::downgrade(&context.rcl_context_mtx),
+        shutdown_state: Arc::downgrade(&context.shutdown_state),
+    });
+}
+
+/// Installs handlers for `SIGINT` and `SIGTERM` that shut down all contexts.
+///
+/// This is opt-in, since installing signal handlers is a process-wide decision that should be
+/// left to the application. After a signal has been received, all contexts that exist at that
+/// point are shut down with [`Context::shutdown`], so that [`spin`][1] and the executors return
+/// and the nodes can be dropped cleanly.
+///
+/// The default signal handlers are restored after the first signal, so that a second `Ctrl-C`
+/// terminates the process in case shutting down gets stuck. Calling this function again
+/// installs the handlers again.
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::{Context, RclrsError};
+/// rclrs::install_signal_handler();
+/// let context = Context::new(std::env::args())?;
+/// let node = rclrs::create_node(&context, "my_node")?;
+/// // Returns when Ctrl-C is pressed
+/// rclrs::spin(&node)?;
+/// assert_eq!(context.shutdown_reason().as_deref(), Some("SIGINT"));
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::spin
+pub fn install_signal_handler() {
+    if !WATCHER_STARTED.swap(true, Ordering::AcqRel) {
+        // Signal handlers may only do very little, so the contexts are shut down on this thread
+        std::thread::spawn(|| loop {
+            std::thread::sleep(WATCHER_PERIOD);
+            match RECEIVED_SIGNAL.swap(0, Ordering::AcqRel) {
+                0 => (),
+                signum => shutdown_all_contexts(if signum == SIGINT {
+                    "SIGINT"
+                } else {
+                    "SIGTERM"
+                }),
+            }
+        });
+    }
+    for signum in [SIGINT, SIGTERM] {
+        // SAFETY: The handler only does things that are allowed in signal handlers.
+        unsafe { signal(signum, handle_signal as extern "C" fn(c_int) as usize) };
+    }
+}
+
+extern "C" fn handle_signal(signum: c_int) {
+    RECEIVED_SIGNAL.store(signum, Ordering::Release);
+    for signum in [SIGINT, SIGTERM] {
+        // SAFETY: Restoring the default handler is allowed in signal handlers.
+        unsafe { signal(signum, SIG_DFL) };
+    }
+}
+
+fn shutdown_all_contexts(reason: &str) {
+    let contexts: Vec<Context> = CONTEXTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(WeakContext::upgrade)
+        .collect();
+    for context in contexts {
+        // There is nobody to report the error to, and the other contexts should still be shut down
+        let _ = context.shutdown(reason);
+    }
+}
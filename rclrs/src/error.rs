@@ -3,6 +3,7 @@ use std::ffi::{CStr, NulError};
 use std::fmt::{self, Display};
 
 use crate::rcl_bindings::*;
+use crate::ParameterKind;
 
 /// The main error type.
 #[derive(Debug, PartialEq, Eq)]
@@ -30,6 +31,12 @@ pub enum RclrsError {
     },
     /// It was attempted to add a waitable to a wait set twice.
     AlreadyAddedToWaitSet,
+    /// The context has been shut down while waiting.
+    ContextShutDown,
+    /// An entity was created in a callback group that does not belong to the node.
+    CallbackGroupOfOtherNode,
+    /// A parameter could not be declared, read or changed.
+    ParameterError(ParameterError),
 }
 
 impl Display for RclrsError {
@@ -46,10 +53,21 @@ impl Display for RclrsError {
                     "Could not add entity to wait set because it was already added to a wait set"
                 )
             }
+            RclrsError::ContextShutDown => write!(f, "The context has been shut down"),
+            RclrsError::CallbackGroupOfOtherNode => {
+                write!(f, "The callback group was not created by this node")
+            }
+            RclrsError::ParameterError(err) => write!(f, "{}", err),
         }
     }
 }
 
+impl From<ParameterError> for RclrsError {
+    fn from(err: ParameterError) -> Self {
+        RclrsError::ParameterError(err)
+    }
+}
+
 /// Struct encapsulating an error message from the rcl layer or below.
 ///
 /// This struct is intended to be returned by the `source` method in the implementation of the
@@ -78,10 +96,103 @@ impl Error for RclrsError {
             RclrsError::UnknownRclError { msg, .. } => msg.as_ref().map(|e| e as &dyn Error),
             RclrsError::StringContainsNul { err, .. } => Some(err).map(|e| e as &dyn Error),
             RclrsError::AlreadyAddedToWaitSet => None,
+            RclrsError::ContextShutDown => None,
+            RclrsError::CallbackGroupOfOtherNode => None,
+            RclrsError::ParameterError(_) => None,
+        }
+    }
+}
+
+/// The reason why a parameter could not be declared, read or changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParameterError {
+    /// The parameter has been declared already.
+    AlreadyDeclared {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// The parameter has not been declared.
+    NotDeclared {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// A value of the wrong type was given for the parameter, or the parameter was read as the
+    /// wrong type.
+    TypeMismatch {
+        /// The name of the parameter.
+        name: String,
+        /// The type that was required.
+        expected: ParameterKind,
+        /// The type that was found instead.
+        actual: ParameterKind,
+    },
+    /// The parameter is read-only, and can't be changed or undeclared.
+    ReadOnly {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// The value is outside of the range in the parameter's descriptor.
+    OutOfRange {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// The range in the parameter's descriptor is empty, or does not fit the type of the
+    /// parameter.
+    InvalidRange {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// The same parameter is changed more than once in an atomic set of changes.
+    ChangedTwice {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// A parameter callback rejected the change.
+    Rejected {
+        /// The reason given by the callback.
+        reason: String,
+    },
+}
+
+impl Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParameterError::AlreadyDeclared { name } => {
+                write!(f, "Parameter '{}' has already been declared", name)
+            }
+            ParameterError::NotDeclared { name } => {
+                write!(f, "Parameter '{}' has not been declared", name)
+            }
+            ParameterError::TypeMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Type mismatch for parameter '{}': expected {:?}, found {:?}",
+                name, expected, actual
+            ),
+            ParameterError::ReadOnly { name } => {
+                write!(f, "Parameter '{}' is read-only", name)
+            }
+            ParameterError::OutOfRange { name } => {
+                write!(f, "Value is out of range for parameter '{}'", name)
+            }
+            ParameterError::InvalidRange { name } => {
+                write!(f, "Invalid range in the descriptor of parameter '{}'", name)
+            }
+            ParameterError::ChangedTwice { name } => {
+                write!(f, "Parameter '{}' is changed more than once", name)
+            }
+            ParameterError::Rejected { reason } => {
+                write!(f, "Parameter change rejected: {}", reason)
+            }
         }
     }
 }
 
+impl Error for ParameterError {}
+
 /// Return codes of `rcl` functions.
 ///
 /// This type corresponds to `rcl_ret_t`.
@@ -0,0 +1,1045 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context as TaskContext, Poll};
+use std::time::{Duration, Instant};
+
+use crate::{
+    CallbackGroup, CallbackGroupType, ClientBase, Context, GuardCondition, Node, RclReturnCode,
+    RclrsError, ReadyEntities, ServiceBase, SubscriptionBase, Timer, WaitSet, WaitSetCache,
+    Waitable, WaitableCount,
+};
+
+/// An executor that spins multiple nodes on the thread that calls its spin functions.
+///
+/// All nodes that are added to the executor are waited on with a single wait set, and the
+/// callbacks of their ready entities are run one after another.
+///
+/// The executor only holds weak references to the nodes, so dropping a node removes it from the
+/// executor. All nodes should belong to the same [`Context`].
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, RclrsError, SingleThreadedExecutor};
+/// # use std::sync::Arc;
+/// let context = Context::new([])?;
+/// let node_1 = Arc::new(rclrs::create_node(&context, "node_1")?);
+/// let node_2 = Arc::new(rclrs::create_node(&context, "node_2")?);
+///
+/// let executor = SingleThreadedExecutor::new();
+/// executor.add_node(&node_1);
+/// executor.add_node(&node_2);
+/// // Processes everything that is ready in both nodes, without blocking
+/// executor.spin_some()?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+pub struct SingleThreadedExecutor {
+    nodes: NodeList,
+    // Reused by spin_once(), so that the wait set is not created anew on every call.
+    wait_set_cache: Arc<Mutex<WaitSetCache>>,
+}
+
+impl Default for SingleThreadedExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SingleThreadedExecutor {
+    /// Creates a new executor without any nodes.
+    pub fn new() -> Self {
+        Self {
+            nodes: NodeList::default(),
+            wait_set_cache: WaitSetCache::new_releasable(),
+        }
+    }
+
+    /// Adds a node to the executor.
+    ///
+    /// Adding a node that has already been added has no effect.
+    pub fn add_node(&self, node: &Arc<Node>) {
+        self.nodes.add(node)
+    }
+
+    /// Removes a node from the executor.
+    ///
+    /// Removing a node that was not added has no effect.
+    pub fn remove_node(&self, node: &Arc<Node>) {
+        self.nodes.remove(node)
+    }
+
+    /// Waits for any entity in any of the nodes to become ready, and executes the callbacks of
+    /// all ready entities.
+    ///
+    /// See [`WaitSet::wait`] for the meaning of the `timeout` parameter. If the executor has no
+    /// nodes, this returns immediately.
+    ///
+    /// Like [`spin_once`][1], this may under some circumstances return a `*TakeFailed` error
+    /// when the wait set spuriously wakes up, which can usually be ignored.
+    ///
+    /// [1]: crate::spin_once
+    pub fn spin_once(&self, timeout: Option<Duration>) -> Result<(), RclrsError> {
+        let nodes = self.nodes.live();
+        if nodes.is_empty() {
+            return Ok(());
+        }
+        let ready_entities = WaitSetCache::wait_once(&self.wait_set_cache, &nodes, timeout)?;
+        execute_ready_entities(ready_entities)
+    }
+
+    // Like spin_once(), but reuses the wait set of previous iterations if the nodes haven't changed.
+    fn spin_once_with_cache(
+        &self,
+        wait_set_cache: &mut WaitSetCache,
+        timeout: Option<Duration>,
+    ) -> Result<(), RclrsError> {
+        let nodes = self.nodes.live();
+        if nodes.is_empty() {
+            return Ok(());
+        }
+        let ready_entities = wait_set_cache.wait(&nodes, timeout)?;
+        execute_ready_entities(ready_entities)
+    }
+
+    /// Executes the callbacks of all entities that are ready right now, without blocking.
+    pub fn spin_some(&self) -> Result<(), RclrsError> {
+        match self.spin_once(Some(Duration::ZERO)) {
+            Err(RclrsError::RclError {
+                code: RclReturnCode::Timeout,
+                ..
+            }) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Calls [`spin_once`][1] in a loop, until all nodes have been dropped or their context
+    /// has become invalid.
+    ///
+    /// [1]: SingleThreadedExecutor::spin_once
+    pub fn spin(&self) -> Result<(), RclrsError> {
+        let mut wait_set_cache = WaitSetCache::default();
+        while !self.nodes.live().is_empty() {
+            match self.spin_once_with_cache(&mut wait_set_cache, None) {
+                Ok(_)
+                | Err(RclrsError::RclError {
+                    code: RclReturnCode::Timeout,
+                    ..
+                }) => (),
+                error => return error,
+            }
+        }
+        Ok(())
+    }
+
+    /// Spins the executor until the future is complete, and returns its output.
+    ///
+    /// See [`spin_until_future_complete`][1].
+    ///
+    /// [1]: crate::spin_until_future_complete
+    pub fn spin_until_future_complete<F: Future>(
+        &self,
+        future: F,
+        timeout: Option<Duration>,
+    ) -> Result<F::Output, RclrsError> {
+        let mut wait_set_cache = WaitSetCache::default();
+        spin_until_future_complete_with(future, timeout, |timeout| {
+            self.spin_once_with_cache(&mut wait_set_cache, timeout)
+        })
+    }
+}
+
+/// A node or executor that can be spun by [`spin_until_future_complete`][1].
+///
+/// [1]: crate::spin_until_future_complete
+pub trait SpinUntilFutureComplete {
+    /// Spins until the future is complete, and returns its output.
+    ///
+    /// See [`spin_until_future_complete`][1].
+    ///
+    /// [1]: crate::spin_until_future_complete
+    fn spin_until_future_complete<F: Future>(
+        &self,
+        future: F,
+        timeout: Option<Duration>,
+    ) -> Result<F::Output, RclrsError>;
+}
+
+impl SpinUntilFutureComplete for Node {
+    fn spin_until_future_complete<F: Future>(
+        &self,
+        future: F,
+        timeout: Option<Duration>,
+    ) -> Result<F::Output, RclrsError> {
+        let mut wait_set_cache = WaitSetCache::default();
+        spin_until_future_complete_with(future, timeout, |timeout| {
+            let ready_entities = wait_set_cache.wait(&[self], timeout)?;
+            execute_ready_entities(ready_entities)
+        })
+    }
+}
+
+impl SpinUntilFutureComplete for SingleThreadedExecutor {
+    fn spin_until_future_complete<F: Future>(
+        &self,
+        future: F,
+        timeout: Option<Duration>,
+    ) -> Result<F::Output, RclrsError> {
+        SingleThreadedExecutor::spin_until_future_complete(self, future, timeout)
+    }
+}
+
+impl<T: SpinUntilFutureComplete + ?Sized> SpinUntilFutureComplete for Arc<T> {
+    fn spin_until_future_complete<F: Future>(
+        &self,
+        future: F,
+        timeout: Option<Duration>,
+    ) -> Result<F::Output, RclrsError> {
+        (**self).spin_until_future_complete(future, timeout)
+    }
+}
+
+// Polls the future, and calls spin_once with the remaining time whenever it is not complete yet.
+fn spin_until_future_complete_with<F, S>(
+    future: F,
+    timeout: Option<Duration>,
+    mut spin_once: S,
+) -> Result<F::Output, RclrsError>
+where
+    F: Future,
+    S: FnMut(Option<Duration>) -> Result<(), RclrsError>,
+{
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut future = Box::pin(future);
+    let mut task_context = TaskContext::from_waker(futures::task::noop_waker_ref());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut task_context) {
+            return Ok(output);
+        }
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if remaining == Some(Duration::ZERO) {
+            return Err(RclrsError::RclError {
+                code: RclReturnCode::Timeout,
+                msg: None,
+            });
+        }
+        match spin_once(remaining) {
+            Ok(_)
+            | Err(RclrsError::RclError {
+                code: RclReturnCode::Timeout,
+                ..
+            }) => (),
+            Err(err) if is_take_failed(&err) => (),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// An executor that runs the callbacks of multiple nodes on a pool of threads.
+///
+/// One of the threads waits for entities to become ready, while the others run callbacks.
+/// Which callbacks may run in parallel is determined by their [`CallbackGroup`]s: callbacks from
+/// different groups can always run concurrently, and callbacks from the same group only if it is
+/// [`Reentrant`][1]. Since all entities are in the mutually exclusive default callback group of
+/// their node unless specified otherwise, a node's callbacks are not run in parallel by default.
+///
+/// Like the [`SingleThreadedExecutor`], this only holds weak references to the nodes, and all
+/// nodes should belong to the same [`Context`].
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::{CallbackGroupType, Context, MultiThreadedExecutor, RclrsError};
+/// # use std::sync::Arc;
+/// # use std::time::Duration;
+/// let context = Context::new([])?;
+/// let mut node = rclrs::create_node(&context, "my_node")?;
+/// let callback_group = node.create_callback_group(CallbackGroupType::Reentrant);
+/// // These timers may run at the same time
+/// let _fast_timer =
+///     node.create_timer_with_callback_group(&callback_group, Duration::from_millis(10), || {})?;
+/// let _slow_timer =
+///     node.create_timer_with_callback_group(&callback_group, Duration::from_secs(1), || {
+///         std::thread::sleep(Duration::from_millis(500));
+///     })?;
+///
+/// let node = Arc::new(node);
+/// let executor = MultiThreadedExecutor::new();
+/// executor.add_node(&node);
+/// executor.spin()?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::CallbackGroupType::Reentrant
+pub struct MultiThreadedExecutor {
+    nodes: NodeList,
+    number_of_threads: usize,
+    canceled: AtomicBool,
+    // Wakes up the thread that is waiting, while the executor is spinning.
+    interrupt_mtx: Mutex<Option<Arc<GuardCondition>>>,
+}
+
+impl Default for MultiThreadedExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultiThreadedExecutor {
+    /// Creates a new executor without any nodes, which uses one thread per CPU core.
+    pub fn new() -> Self {
+        let number_of_threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self::with_number_of_threads(number_of_threads)
+    }
+
+    /// Creates a new executor without any nodes, which uses the given number of threads.
+    ///
+    /// A number of zero is treated like one.
+    pub fn with_number_of_threads(number_of_threads: usize) -> Self {
+        Self {
+            nodes: NodeList::default(),
+            number_of_threads: number_of_threads.max(1),
+            canceled: AtomicBool::new(false),
+            interrupt_mtx: Mutex::new(None),
+        }
+    }
+
+    /// Returns the number of threads that [`spin`][1] uses.
+    ///
+    /// [1]: MultiThreadedExecutor::spin
+    pub fn number_of_threads(&self) -> usize {
+        self.number_of_threads
+    }
+
+    /// Adds a node to the executor.
+    ///
+    /// Adding a node that has already been added has no effect.
+    pub fn add_node(&self, node: &Arc<Node>) {
+        self.nodes.add(node)
+    }
+
+    /// Removes a node from the executor.
+    ///
+    /// Removing a node that was not added has no effect. Callbacks of the node that are already
+    /// running are not interrupted.
+    pub fn remove_node(&self, node: &Arc<Node>) {
+        self.nodes.remove(node)
+    }
+
+    /// Runs callbacks on the executor's threads until the executor is [canceled][1], all nodes
+    /// have been dropped, their context has become invalid, or an error occurs.
+    ///
+    /// The first error that occurs in any thread is returned, after the callbacks that are
+    /// currently running have finished. Spurious `*TakeFailed` errors are ignored.
+    ///
+    /// [1]: MultiThreadedExecutor::cancel
+    pub fn spin(&self) -> Result<(), RclrsError> {
+        let context = match self.nodes.live().first() {
+            Some(node) => node.context.clone(),
+            None => return Ok(()),
+        };
+        let state = SpinState::new(&context);
+        // All nodes belong to the same context, whose shutdown has to end the wait
+        context.add_shutdown_guard_condition(&state.interrupt);
+        *self.interrupt_mtx.lock().unwrap() = Some(Arc::clone(&state.interrupt));
+        std::thread::scope(|scope| {
+            for _ in 0..self.number_of_threads {
+                scope.spawn(|| self.run_worker(&state));
+            }
+        });
+        *self.interrupt_mtx.lock().unwrap() = None;
+        self.canceled.store(false, Ordering::Release);
+        match state.error_mtx.into_inner().unwrap() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Makes [`spin`][1] return as soon as the callbacks that are currently running have
+    /// finished.
+    ///
+    /// If the executor is not spinning, the next call to `spin` returns immediately.
+    ///
+    /// [1]: MultiThreadedExecutor::spin
+    pub fn cancel(&self) {
+        self.canceled.store(true, Ordering::Release);
+        if let Some(interrupt) = &*self.interrupt_mtx.lock().unwrap() {
+            // Triggering a valid guard condition does not fail
+            let _ = interrupt.trigger();
+        }
+    }
+
+    fn run_worker(&self, state: &SpinState) {
+        while let Some(job) = self.next_job(state) {
+            let result = job.executable.execute();
+            let mutually_exclusive = job.group.group_type() == CallbackGroupType::MutuallyExclusive;
+            if mutually_exclusive {
+                job.group.in_use.store(false, Ordering::Release);
+            }
+            let left_out = state
+                .in_flight_mtx
+                .lock()
+                .unwrap()
+                .finish(job.executable.id());
+            if let Err(error) = result {
+                if !is_take_failed(&error) {
+                    state.fail(error);
+                }
+            }
+            // Lets the waiting thread pick up the group or the entity again. This is only needed
+            // if they are missing from its wait set, since the wait set is rebuilt then.
+            if mutually_exclusive || left_out {
+                let _ = state.interrupt.trigger();
+            }
+        }
+    }
+
+    // Returns a job whose callback group is available, waiting for entities to become ready if
+    // there is none. Returns None when the workers should stop.
+    fn next_job(&self, state: &SpinState) -> Option<Job> {
+        // Holding this lock while waiting ensures that only one thread waits at a time
+        let mut wait_state = state.wait_state_mtx.lock().unwrap();
+        loop {
+            if state.done.load(Ordering::Acquire) || self.canceled.load(Ordering::Acquire) {
+                state.done.store(true, Ordering::Release);
+                return None;
+            }
+            if let Some(index) = wait_state
+                .pending_jobs
+                .iter()
+                .position(|job| job.try_acquire_group())
+            {
+                return Some(wait_state.pending_jobs.remove(index));
+            }
+            if let Err(error) = self.wait_for_jobs(state, &mut wait_state) {
+                state.fail(error);
+                return None;
+            }
+        }
+    }
+
+    // Waits on all entities that are not pending or running already, and whose callback group is
+    // not busy, and adds jobs for the ones that became ready.
+    fn wait_for_jobs(
+        &self,
+        state: &SpinState,
+        wait_state: &mut WaitState,
+    ) -> Result<(), RclrsError> {
+        let nodes = self.nodes.live();
+        if nodes.is_empty() {
+            state.done.store(true, Ordering::Release);
+            return Ok(());
+        }
+        if wait_state.is_stale(&nodes, state) {
+            wait_state.rebuild(&nodes, state)?;
+        }
+        // Drop the nodes, so that they can be removed from the executor while waiting
+        drop(nodes);
+
+        let ready_entities = match &mut wait_state.wait_set {
+            Some(wait_set) => wait_set.wait(None)?,
+            None => unreachable!(),
+        };
+        let ready_executables = ready_entities
+            .subscriptions
+            .into_iter()
+            .map(Executable::Subscription)
+            .chain(ready_entities.clients.into_iter().map(Executable::Client))
+            .chain(ready_entities.services.into_iter().map(Executable::Service))
+            .chain(ready_entities.timers.into_iter().map(Executable::Timer))
+            .chain(
+                ready_entities
+                    .guard_conditions
+                    .into_iter()
+                    .map(Executable::GuardCondition),
+            )
+            .chain(
+                ready_entities
+                    .waitables
+                    .into_iter()
+                    .map(Executable::Waitable),
+            );
+        let mut in_flight = state.in_flight_mtx.lock().unwrap();
+        for executable in ready_executables {
+            let id = executable.id();
+            // The interrupt guard condition has no group, so it is skipped here
+            let group = match wait_state.groups.get(&id) {
+                Some(group) => Arc::clone(group),
+                None => continue,
+            };
+            // An entity of a reentrant group that is still running became ready again. It is left
+            // out of the wait set until it is done, so that it doesn't end every wait right away.
+            if !in_flight.ids.insert(id) {
+                wait_state.needs_rebuild = true;
+                continue;
+            }
+            wait_state.pending_jobs.push(Job { executable, group });
+        }
+        Ok(())
+    }
+}
+
+// The pending jobs and the wait set of MultiThreadedExecutor::spin. The wait set is reused until
+// the nodes' entities or the callback groups whose entities are waited on change.
+#[derive(Default)]
+struct WaitState {
+    pending_jobs: Vec<Job>,
+    wait_set: Option<WaitSet>,
+    // The address and entity generation of each node that the wait set was built for.
+    node_generations: Vec<(usize, usize)>,
+    // The addresses of the callback groups whose entities are in the wait set.
+    available_groups: Vec<usize>,
+    // The callback group of each entity in the wait set, by the ID of the entity.
+    groups: HashMap<usize, Arc<CallbackGroup>>,
+    needs_rebuild: bool,
+}
+
+impl WaitState {
+    fn is_stale(&self, nodes: &[Arc<Node>], state: &SpinState) -> bool {
+        let wait_set = match &self.wait_set {
+            Some(wait_set) => wait_set,
+            None => return true,
+        };
+        if self.needs_rebuild
+            || self.node_generations.len() != nodes.len()
+            || self
+                .node_generations
+                .iter()
+                .zip(nodes)
+                .any(|(&(address, generation), node)| {
+                    address != Arc::as_ptr(node) as usize
+                        || generation != node.entity_generation.load(Ordering::Acquire)
+                })
+            || wait_set.has_dropped_entities()
+        {
+            return true;
+        }
+        let mut available_groups = self.available_groups.iter();
+        for group in nodes
+            .iter()
+            .flat_map(|node| node.callback_groups.iter())
+            .filter(|group| is_available(group))
+        {
+            if available_groups.next() != Some(&(Arc::as_ptr(group) as usize)) {
+                return true;
+            }
+        }
+        // The entities that were left out because they were running are done now
+        available_groups.next().is_some() || state.in_flight_mtx.lock().unwrap().left_out_done()
+    }
+
+    // Adds the entities of the available callback groups that are not in flight, and the
+    // interrupt guard condition, to the wait set.
+    fn rebuild(&mut self, nodes: &[Arc<Node>], state: &SpinState) -> Result<(), RclrsError> {
+        // If adding an entity fails, the wait set is rebuilt on the next wait
+        self.needs_rebuild = true;
+        self.groups.clear();
+        self.available_groups.clear();
+        let wait_set = match &mut self.wait_set {
+            Some(wait_set) => {
+                wait_set.clear();
+                wait_set
+            }
+            None => self
+                .wait_set
+                .insert(WaitSet::new(0, 0, 0, 0, 0, 0, &nodes[0].context)?),
+        };
+        let mut candidates = Vec::new();
+        {
+            let mut in_flight = state.in_flight_mtx.lock().unwrap();
+            in_flight.left_out.clear();
+            for node in nodes {
+                for (index, group) in node.callback_groups.iter().enumerate() {
+                    if !is_available(group) {
+                        continue;
+                    }
+                    self.available_groups.push(Arc::as_ptr(group) as usize);
+                    // Guard conditions belong to the default callback group
+                    let guard_conditions = if index == 0 {
+                        node.live_guard_conditions()
+                    } else {
+                        Vec::new()
+                    };
+                    let executables = group
+                        .live_subscriptions()
+                        .into_iter()
+                        .map(Executable::Subscription)
+                        .chain(group.live_clients().into_iter().map(Executable::Client))
+                        .chain(group.live_services().into_iter().map(Executable::Service))
+                        .chain(group.live_timers().into_iter().map(Executable::Timer))
+                        .chain(group.live_waitables().into_iter().map(Executable::Waitable))
+                        .chain(guard_conditions.into_iter().map(Executable::GuardCondition));
+                    for executable in executables {
+                        let id = executable.id();
+                        if in_flight.ids.contains(&id) {
+                            in_flight.left_out.insert(id);
+                        } else {
+                            candidates.push((executable, Arc::clone(group)));
+                        }
+                    }
+                }
+            }
+        }
+
+        // One additional guard condition is needed for the interrupt guard condition
+        let mut count = WaitableCount {
+            guard_conditions: 1,
+            ..Default::default()
+        };
+        for (executable, _) in &candidates {
+            count.add(executable.count_entities());
+        }
+        wait_set.resize(&count)?;
+        for (executable, group) in candidates {
+            self.groups.insert(executable.id(), group);
+            match executable {
+                Executable::Subscription(subscription) => {
+                    wait_set.add_subscription(subscription)?
+                }
+                Executable::Client(client) => wait_set.add_client(client)?,
+                Executable::Service(service) => wait_set.add_service(service)?,
+                Executable::Timer(timer) => wait_set.add_timer(timer)?,
+                Executable::GuardCondition(guard_condition) => {
+                    wait_set.add_guard_condition(guard_condition)?
+                }
+                Executable::Waitable(waitable) => wait_set.add_waitable(waitable)?,
+            }
+        }
+        wait_set.add_guard_condition(Arc::clone(&state.interrupt))?;
+        self.node_generations.clear();
+        self.node_generations.extend(nodes.iter().map(|node| {
+            (
+                Arc::as_ptr(node) as usize,
+                node.entity_generation.load(Ordering::Acquire),
+            )
+        }));
+        self.needs_rebuild = false;
+        Ok(())
+    }
+}
+
+// Whether the entities of the group may be executed, i.e. whether it is not a mutually exclusive
+// group with a running callback.
+fn is_available(group: &CallbackGroup) -> bool {
+    group.group_type() == CallbackGroupType::Reentrant || !group.in_use.load(Ordering::Acquire)
+}
+
+// The entities that are either pending or running.
+#[derive(Default)]
+struct InFlight {
+    ids: HashSet<usize>,
+    // The entities that are in flight and have been left out of the wait set, which has to be
+    // rebuilt once they are done.
+    left_out: HashSet<usize>,
+}
+
+impl InFlight {
+    // Removes the entity, and returns true if it has been left out of the wait set.
+    fn finish(&mut self, id: usize) -> bool {
+        self.ids.remove(&id);
+        self.left_out.contains(&id)
+    }
+
+    fn left_out_done(&self) -> bool {
+        self.left_out.iter().any(|id| !self.ids.contains(id))
+    }
+}
+
+// The state that is shared by the threads of MultiThreadedExecutor::spin.
+struct SpinState {
+    interrupt: Arc<GuardCondition>,
+    wait_state_mtx: Mutex<WaitState>,
+    in_flight_mtx: Mutex<InFlight>,
+    error_mtx: Mutex<Option<RclrsError>>,
+    done: AtomicBool,
+}
+
+impl SpinState {
+    fn new(context: &Context) -> Self {
+        Self {
+            interrupt: Arc::new(GuardCondition::new(context)),
+            wait_state_mtx: Mutex::new(WaitState::default()),
+            in_flight_mtx: Mutex::new(InFlight::default()),
+            error_mtx: Mutex::new(None),
+            done: AtomicBool::new(false),
+        }
+    }
+
+    // Stores the first error and makes all threads stop.
+    fn fail(&self, error: RclrsError) {
+        self.error_mtx.lock().unwrap().get_or_insert(error);
+        self.done.store(true, Ordering::Release);
+        let _ = self.interrupt.trigger();
+    }
+}
+
+enum Executable {
+    Subscription(Arc<dyn SubscriptionBase>),
+    Client(Arc<dyn ClientBase>),
+    Service(Arc<dyn ServiceBase>),
+    Timer(Arc<Timer>),
+    GuardCondition(Arc<GuardCondition>),
+    Waitable(Arc<dyn Waitable>),
+}
+
+impl Executable {
+    // Identifies the entity by the address of its allocation.
+    fn id(&self) -> usize {
+        match self {
+            Executable::Subscription(subscription) => {
+                Arc::as_ptr(subscription) as *const () as usize
+            }
+            Executable::Client(client) => Arc::as_ptr(client) as *const () as usize,
+            Executable::Service(service) => Arc::as_ptr(service) as *const () as usize,
+            Executable::Timer(timer) => Arc::as_ptr(timer) as usize,
+            Executable::GuardCondition(guard_condition) => Arc::as_ptr(guard_condition) as usize,
+            Executable::Waitable(waitable) => Arc::as_ptr(waitable) as *const () as usize,
+        }
+    }
+
+    // The number of rcl entities that the entity adds to a wait set.
+    fn count_entities(&self) -> WaitableCount {
+        let zero = WaitableCount::default();
+        match self {
+            Executable::Subscription(_) => WaitableCount {
+                subscriptions: 1,
+                ..zero
+            },
+            Executable::Client(_) => WaitableCount { clients: 1, ..zero },
+            Executable::Service(_) => WaitableCount {
+                services: 1,
+                ..zero
+            },
+            Executable::Timer(_) => WaitableCount { timers: 1, ..zero },
+            Executable::GuardCondition(_) => WaitableCount {
+                guard_conditions: 1,
+                ..zero
+            },
+            Executable::Waitable(waitable) => waitable.count_entities(),
+        }
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        match self {
+            Executable::Subscription(subscription) => subscription.execute(),
+            Executable::Client(client) => client.execute(),
+            Executable::Service(service) => service.execute(),
+            Executable::Timer(timer) => timer.execute(),
+            Executable::GuardCondition(guard_condition) => {
+                guard_condition.execute();
+                Ok(())
+            }
+            Executable::Waitable(waitable) => waitable.execute(),
+        }
+    }
+}
+
+struct Job {
+    executable: Executable,
+    group: Arc<CallbackGroup>,
+}
+
+impl Job {
+    // Marks a mutually exclusive group as busy, if it isn't already.
+    fn try_acquire_group(&self) -> bool {
+        match self.group.group_type() {
+            CallbackGroupType::Reentrant => true,
+            CallbackGroupType::MutuallyExclusive => self
+                .group
+                .in_use
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok(),
+        }
+    }
+}
+
+pub(crate) fn is_take_failed(error: &RclrsError) -> bool {
+    matches!(
+        error,
+        RclrsError::RclError {
+            code: RclReturnCode::SubscriptionTakeFailed
+                | RclReturnCode::ClientTakeFailed
+                | RclReturnCode::ServiceTakeFailed,
+            ..
+        }
+    )
+}
+
+// The nodes of an executor.
+#[derive(Default)]
+struct NodeList {
+    nodes_mtx: Mutex<Vec<Weak<Node>>>,
+}
+
+impl NodeList {
+    fn add(&self, node: &Arc<Node>) {
+        let mut nodes = self.nodes_mtx.lock().unwrap();
+        if !nodes.iter().any(|weak| weak.as_ptr() == Arc::as_ptr(node)) {
+            nodes.push(Arc::downgrade(node));
+        }
+    }
+
+    fn remove(&self, node: &Arc<Node>) {
+        self.nodes_mtx
+            .lock()
+            .unwrap()
+            .retain(|weak| weak.as_ptr() != Arc::as_ptr(node));
+    }
+
+    // Returns the nodes that have not been dropped yet and whose context is still valid.
+    fn live(&self) -> Vec<Arc<Node>> {
+        let mut nodes = self.nodes_mtx.lock().unwrap();
+        nodes.retain(|weak| weak.strong_count() > 0);
+        nodes
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|node| node.context.ok())
+            .collect()
+    }
+}
+
+/// Executes the callbacks of the ready entities returned by [`WaitSet::wait`].
+pub(crate) fn execute_ready_entities(ready_entities: ReadyEntities) -> Result<(), RclrsError> {
+    for ready_subscription in ready_entities.subscriptions {
+        ready_subscription.execute()?;
+    }
+
+    for ready_client in ready_entities.clients {
+        ready_client.execute()?;
+    }
+
+    for ready_service in ready_entities.services {
+        ready_service.execute()?;
+    }
+
+    for ready_timer in ready_entities.timers {
+        ready_timer.execute()?;
+    }
+
+    for ready_guard_condition in ready_entities.guard_conditions {
+        ready_guard_condition.execute();
+    }
+
+    for ready_waitable in ready_entities.waitables {
+        ready_waitable.execute()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn executor_is_send_and_sync() {
+        assert_send::<SingleThreadedExecutor>();
+        assert_sync::<SingleThreadedExecutor>();
+        assert_send::<MultiThreadedExecutor>();
+        assert_sync::<MultiThreadedExecutor>();
+    }
+
+    #[test]
+    fn test_executor_spins_all_nodes() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let counter = Arc::new(AtomicUsize::new(0));
+        let executor = SingleThreadedExecutor::new();
+        let mut nodes = Vec::new();
+        let mut timers = Vec::new();
+        for node_name in ["test_executor_node_1", "test_executor_node_2"] {
+            let mut node = Node::new(&context, node_name)?;
+            let counter_for_callback = Arc::clone(&counter);
+            timers.push(node.create_timer(Duration::from_millis(1), move || {
+                counter_for_callback.fetch_add(1, Ordering::Relaxed);
+            })?);
+            let node = Arc::new(node);
+            executor.add_node(&node);
+            nodes.push(node);
+        }
+        // Adding a node twice has no effect
+        executor.add_node(&nodes[0]);
+
+        std::thread::sleep(Duration::from_millis(2));
+        executor.spin_once(Some(Duration::from_secs(1)))?;
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+
+        // Only the remaining node is spun
+        executor.remove_node(&nodes[1]);
+        std::thread::sleep(Duration::from_millis(2));
+        executor.spin_once(Some(Duration::from_secs(1)))?;
+        assert_eq!(counter.load(Ordering::Relaxed), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_spin_returns_without_nodes() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let executor = SingleThreadedExecutor::new();
+        let node = Arc::new(Node::new(&context, "test_spin_returns_without_nodes")?);
+        executor.add_node(&node);
+        drop(node);
+        executor.spin()?;
+        executor.spin_some()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_spin_until_future_complete_times_out() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let executor = SingleThreadedExecutor::new();
+        let mut node = Node::new(&context, "test_spin_until_future_complete_times_out")?;
+        let _timer = node.create_timer(Duration::from_millis(1), || {})?;
+        let node = Arc::new(node);
+        executor.add_node(&node);
+
+        let output = executor.spin_until_future_complete(async { 42 }, None)?;
+        assert_eq!(output, 42);
+
+        let result = executor.spin_until_future_complete(
+            futures::future::pending::<()>(),
+            Some(Duration::from_millis(10)),
+        );
+        assert!(matches!(
+            result,
+            Err(RclrsError::RclError {
+                code: RclReturnCode::Timeout,
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    // Spins two slow timers of the same callback group for a while, and returns how many of their
+    // callbacks ran at the same time at most.
+    fn max_concurrent_callbacks(
+        node_name: &str,
+        group_type: CallbackGroupType,
+    ) -> Result<usize, RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(&context, node_name)?;
+        let callback_group = node.create_callback_group(group_type);
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let mut timers = Vec::new();
+        for _ in 0..2 {
+            let running = Arc::clone(&running);
+            let max_running = Arc::clone(&max_running);
+            timers.push(node.create_timer_with_callback_group(
+                &callback_group,
+                Duration::from_millis(5),
+                move || {
+                    let now_running = running.fetch_add(1, Ordering::AcqRel) + 1;
+                    max_running.fetch_max(now_running, Ordering::AcqRel);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::AcqRel);
+                },
+            )?);
+        }
+        let node = Arc::new(node);
+        let executor = MultiThreadedExecutor::with_number_of_threads(4);
+        executor.add_node(&node);
+        std::thread::scope(|scope| {
+            let handle = scope.spawn(|| executor.spin());
+            std::thread::sleep(Duration::from_millis(200));
+            executor.cancel();
+            handle.join().unwrap()
+        })?;
+        Ok(max_running.load(Ordering::Acquire))
+    }
+
+    #[test]
+    fn test_mutually_exclusive_callbacks_do_not_overlap() -> Result<(), RclrsError> {
+        let max_running = max_concurrent_callbacks(
+            "test_mutually_exclusive_callbacks_do_not_overlap",
+            CallbackGroupType::MutuallyExclusive,
+        )?;
+        assert_eq!(max_running, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reentrant_callbacks_run_concurrently() -> Result<(), RclrsError> {
+        let max_running = max_concurrent_callbacks(
+            "test_reentrant_callbacks_run_concurrently",
+            CallbackGroupType::Reentrant,
+        )?;
+        assert_eq!(max_running, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_threaded_wait_set_is_only_rebuilt_when_needed() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(
+            &context,
+            "test_multi_threaded_wait_set_is_only_rebuilt_when_needed",
+        )?;
+        let reentrant_group = node.create_callback_group(CallbackGroupType::Reentrant);
+        let timer = node.create_timer_with_callback_group(
+            &reentrant_group,
+            Duration::from_secs(10),
+            || {},
+        )?;
+        let timer_id = Executable::Timer(Arc::clone(&timer)).id();
+        let nodes = [Arc::new(node)];
+        let state = SpinState::new(&context);
+        let mut wait_state = WaitState::default();
+        wait_state.rebuild(&nodes, &state)?;
+        assert!(!wait_state.is_stale(&nodes, &state));
+
+        // A running callback of the mutually exclusive default group makes it unavailable
+        let default_group = &nodes[0].callback_groups[0];
+        default_group.in_use.store(true, Ordering::Release);
+        assert!(wait_state.is_stale(&nodes, &state));
+        wait_state.rebuild(&nodes, &state)?;
+        assert!(!wait_state.is_stale(&nodes, &state));
+        assert_eq!(wait_state.groups.len(), 1);
+        default_group.in_use.store(false, Ordering::Release);
+        assert!(wait_state.is_stale(&nodes, &state));
+        wait_state.rebuild(&nodes, &state)?;
+
+        // A running callback of a reentrant group doesn't change the wait set, unless its entity
+        // has to be left out
+        state.in_flight_mtx.lock().unwrap().ids.insert(timer_id);
+        assert!(!wait_state.is_stale(&nodes, &state));
+        wait_state.needs_rebuild = true;
+        wait_state.rebuild(&nodes, &state)?;
+        assert!(!wait_state.groups.contains_key(&timer_id));
+        assert!(state.in_flight_mtx.lock().unwrap().finish(timer_id));
+        assert!(wait_state.is_stale(&nodes, &state));
+        wait_state.rebuild(&nodes, &state)?;
+        assert!(wait_state.groups.contains_key(&timer_id));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_threaded_spin_returns_without_nodes() -> Result<(), RclrsError> {
+        let executor = MultiThreadedExecutor::with_number_of_threads(0);
+        assert_eq!(executor.number_of_threads(), 1);
+        executor.spin()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_threaded_spin_returns_after_shutdown() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Arc::new(Node::new(
+            &context,
+            "test_multi_threaded_spin_returns_after_shutdown",
+        )?);
+        let executor = MultiThreadedExecutor::with_number_of_threads(2);
+        executor.add_node(&node);
+        std::thread::scope(|scope| {
+            let handle = scope.spawn(|| executor.spin());
+            std::thread::sleep(Duration::from_millis(100));
+            context.shutdown("test")?;
+            handle.join().unwrap()
+        })
+    }
+}
@@ -6,37 +6,61 @@
 //! [1]: https://github.com/ros2-rust/ros2_rust/blob/main/README.md
 
 mod arguments;
+mod callback_group;
 mod client;
+mod clock;
 mod context;
 mod error;
+mod executor;
+mod logging;
 mod node;
 mod parameter;
 mod publisher;
 mod qos;
+mod rate;
 mod service;
 mod subscription;
+mod time;
+mod time_source;
+mod timer;
 mod vendor;
 mod wait;
 
 mod rcl_bindings;
 
+#[cfg(feature = "async")]
+mod async_runtime;
 #[cfg(feature = "dyn_msg")]
 pub mod dynamic_message;
-
-use std::time::Duration;
+#[cfg(test)]
+mod test_helpers;
 
 pub use arguments::*;
+#[cfg(feature = "async")]
+pub use async_runtime::*;
+pub use callback_group::*;
 pub use client::*;
+pub use clock::*;
 pub use context::*;
 pub use error::*;
+pub use executor::*;
+pub use logging::*;
 pub use node::*;
 pub use parameter::*;
 pub use publisher::*;
 pub use qos::*;
+pub use rate::*;
 use rcl_bindings::rcl_context_is_valid;
 pub use rcl_bindings::rmw_request_id_t;
 pub use service::*;
 pub use subscription::*;
+pub use time::*;
+use time_source::*;
+pub use timer::*;
+/// The `builtin_interfaces/Duration` message, which [`Duration`] can be converted to and from.
+pub use vendor::builtin_interfaces::msg::Duration as DurationMsg;
+/// The `builtin_interfaces/Time` message, which [`Time`] can be converted to and from.
+pub use vendor::builtin_interfaces::msg::Time as TimeMsg;
 pub use wait::*;
 
 /// Polls the node for new messages and executes the corresponding callbacks.
@@ -48,42 +72,41 @@ pub use wait::*;
 /// set spuriously wakes up.
 /// This can usually be ignored.
 ///
+/// The wait also ends when the node's context is [shut down][2].
+///
+/// The node's entities stay in its wait set between calls, so that the wait set only needs to be
+/// rebuilt when entities are added to or removed from the node, or when another wait set needs
+/// them in the meantime. Therefore, an entity that is dropped after this call is only finalized
+/// by the next call, or when the node is dropped.
+///
 /// [1]: crate::RclReturnCode
-pub fn spin_once(node: &Node, timeout: Option<Duration>) -> Result<(), RclrsError> {
-    let wait_set = WaitSet::new_for_node(node)?;
-    let ready_entities = wait_set.wait(timeout)?;
-
-    for ready_subscription in ready_entities.subscriptions {
-        ready_subscription.execute()?;
-    }
-
-    for ready_client in ready_entities.clients {
-        ready_client.execute()?;
-    }
-
-    for ready_service in ready_entities.services {
-        ready_service.execute()?;
-    }
-
-    Ok(())
+/// [2]: crate::Context::shutdown
+pub fn spin_once(node: &Node, timeout: Option<std::time::Duration>) -> Result<(), RclrsError> {
+    let ready_entities = WaitSetCache::wait_once(&node.wait_set_cache, &[node], timeout)?;
+    execute_ready_entities(ready_entities)
 }
 
 /// Convenience function for calling [`spin_once`] in a loop.
 ///
-/// This function additionally checks that the context is still valid.
+/// This function additionally checks that the context is still valid, and returns as soon as
+/// the context is shut down.
 pub fn spin(node: &Node) -> Result<(), RclrsError> {
     // The context_is_valid functions exists only to abstract away ROS distro differences
     #[cfg(ros_distro = "foxy")]
     // SAFETY: No preconditions for this function.
     let context_is_valid =
-        || unsafe { rcl_context_is_valid(&mut *node.rcl_context_mtx.lock().unwrap()) };
+        || unsafe { rcl_context_is_valid(&mut *node.context.rcl_context_mtx.lock().unwrap()) };
     #[cfg(not(ros_distro = "foxy"))]
     // SAFETY: No preconditions for this function.
     let context_is_valid =
-        || unsafe { rcl_context_is_valid(&*node.rcl_context_mtx.lock().unwrap()) };
+        || unsafe { rcl_context_is_valid(&*node.context.rcl_context_mtx.lock().unwrap()) };
 
+    let mut wait_set_cache = WaitSetCache::default();
     while context_is_valid() {
-        match spin_once(node, None) {
+        match wait_set_cache
+            .wait(&[node], None)
+            .and_then(execute_ready_entities)
+        {
             Ok(_)
             | Err(RclrsError::RclError {
                 code: RclReturnCode::Timeout,
@@ -95,6 +118,44 @@ pub fn spin(node: &Node) -> Result<(), RclrsError> {
     Ok(())
 }
 
+/// Spins the node or executor until the future is complete, and returns its output.
+///
+/// This is mainly useful for waiting on the futures returned by [`Client::call_async`][1], which
+/// are only completed when the client's node is spun. Waiting on the wait set is interleaved
+/// with polling the future, so the future should only depend on the entities of the node or
+/// executor for making progress.
+///
+/// If the future is not complete after the timeout, a [`Timeout`][2] error is returned.
+/// A timeout of `None` means waiting indefinitely.
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::{Client, Node, RclrsError};
+/// # use std::time::Duration;
+/// fn call<T: rosidl_runtime_rs::Service>(
+///     node: &Node,
+///     client: &Client<T>,
+///     request: T::Request,
+/// ) -> Result<T::Response, RclrsError> {
+///     let future = client.call_async(&request);
+///     rclrs::spin_until_future_complete(node, future, Some(Duration::from_secs(5)))?
+/// }
+/// ```
+///
+/// [1]: crate::Client::call_async
+/// [2]: crate::RclReturnCode::Timeout
+pub fn spin_until_future_complete<S, F>(
+    node_or_executor: &S,
+    future: F,
+    timeout: Option<std::time::Duration>,
+) -> Result<F::Output, RclrsError>
+where
+    S: SpinUntilFutureComplete + ?Sized,
+    F: std::future::Future,
+{
+    node_or_executor.spin_until_future_complete(future, timeout)
+}
+
 /// Creates a new node in the empty namespace.
 ///
 /// Convenience function equivalent to [`Node::new`][1].
@@ -0,0 +1,424 @@
+use std::cell::Cell;
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::rcl_bindings::*;
+use crate::{RclrsError, ToResult};
+
+// Whether logging has been configured by a context. The lock is also held while calling into
+// rcutils logging, which is not thread-safe, and while creating or destroying nodes, which
+// changes the rosout publishers that rcl uses to publish log messages.
+static LOGGING_MTX: Mutex<bool> = Mutex::new(false);
+
+thread_local! {
+    // Whether the current thread holds LOGGING_MTX.
+    static HOLDS_LOGGING_MTX: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Holds the logging lock.
+///
+/// Code that is called by rcl while the lock is held, e.g. the output handler when rcl logs
+/// during node creation, can check [`LoggingGuard::is_held()`] to not lock it again.
+pub(crate) struct LoggingGuard {
+    configured: MutexGuard<'static, bool>,
+}
+
+impl LoggingGuard {
+    /// Takes the logging lock, which must not already be held by the current thread.
+    pub(crate) fn lock() -> Self {
+        // Logging should keep working after a panic, and the flag is always valid
+        let configured = LOGGING_MTX.lock().unwrap_or_else(PoisonError::into_inner);
+        HOLDS_LOGGING_MTX.with(|held| held.set(true));
+        Self { configured }
+    }
+
+    /// Returns true if the current thread holds the logging lock.
+    fn is_held() -> bool {
+        // The thread-local can only be missing while the thread exits
+        HOLDS_LOGGING_MTX.try_with(Cell::get).unwrap_or(false)
+    }
+}
+
+impl Drop for LoggingGuard {
+    fn drop(&mut self) {
+        let _ = HOLDS_LOGGING_MTX.try_with(|held| held.set(false));
+    }
+}
+
+/// Configures logging with the global arguments of a context, e.g. the log levels and whether
+/// logs are published on `/rosout`.
+///
+/// Like in rclcpp, only the first context configures logging. Logging stays configured until
+/// the process exits, since log messages can be written at any time.
+pub(crate) fn configure_logging(global_arguments: &rcl_arguments_t) -> Result<(), RclrsError> {
+    let mut logging = LoggingGuard::lock();
+    if !*logging.configured {
+        // SAFETY: No preconditions for this function.
+        let allocator = unsafe { rcutils_get_default_allocator() };
+        // SAFETY: The arguments have been initialized by rcl_init(), and the allocator is copied.
+        unsafe {
+            rcl_logging_configure_with_output_handler(
+                global_arguments,
+                &allocator,
+                Some(output_handler),
+            )
+        }
+        .ok()?;
+        *logging.configured = true;
+    }
+    Ok(())
+}
+
+// Writes log messages with the output handlers of rcl, which include the rosout publishers.
+// Messages can also be logged by rcl and other libraries directly through rcutils, so the
+// logging lock is taken here, unless the current thread already holds it.
+unsafe extern "C" fn output_handler(
+    location: *const rcutils_log_location_t,
+    severity: c_int,
+    name: *const c_char,
+    timestamp: rcutils_time_point_value_t,
+    format: *const c_char,
+    args: *mut va_list,
+) {
+    let _logging = if LoggingGuard::is_held() {
+        None
+    } else {
+        Some(LoggingGuard::lock())
+    };
+    // SAFETY: The arguments are passed on unchanged from rcutils.
+    rcl_logging_multiple_output_handler(location, severity, name, timestamp, format, args);
+}
+
+// Takes the logging lock, and initializes rcutils logging with its defaults if logging has not
+// been configured by a context yet.
+fn lock_logging() -> LoggingGuard {
+    let logging = LoggingGuard::lock();
+    if !*logging.configured {
+        // SAFETY: No preconditions for this function. It does nothing if logging has already
+        // been initialized, and can only fail to allocate memory, in which case rcutils_log()
+        // prints the message to stderr.
+        unsafe { rcutils_logging_initialize() };
+    }
+    logging
+}
+
+/// The severity of a log message.
+///
+/// A logger only outputs messages whose severity is at least its level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogSeverity {
+    /// As the level of a logger, uses the level of its parent logger.
+    Unset,
+    /// Detailed information that is useful when debugging issues.
+    Debug,
+    /// Information about the expected operation of the program.
+    Info,
+    /// Something unexpected that the program can handle.
+    Warn,
+    /// An error that the program cannot handle by itself.
+    Error,
+    /// An error after which the program can't continue.
+    Fatal,
+}
+
+impl LogSeverity {
+    fn as_rcutils_severity(self) -> i32 {
+        let severity = match self {
+            LogSeverity::Unset => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_UNSET,
+            LogSeverity::Debug => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_DEBUG,
+            LogSeverity::Info => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_INFO,
+            LogSeverity::Warn => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_WARN,
+            LogSeverity::Error => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_ERROR,
+            LogSeverity::Fatal => RCUTILS_LOG_SEVERITY::RCUTILS_LOG_SEVERITY_FATAL,
+        };
+        severity as i32
+    }
+}
+
+/// Writes log messages under a name.
+///
+/// Log messages are written through `rcutils`, so they are formatted and filtered by severity
+/// like those of other ROS 2 client libraries. The messages of a node's logger are also
+/// published on the `/rosout` topic, unless this is disabled with
+/// [`NodeBuilder::enable_rosout()`][1] or the `--disable-rosout-logs` argument.
+///
+/// Messages are usually written with the [`ros_debug!`][2], [`ros_info!`][3],
+/// [`ros_warn!`][4], [`ros_error!`][5] and [`ros_fatal!`][6] macros.
+///
+/// Logger names are hierarchical, with parts separated by dots. A logger whose level is
+/// [`LogSeverity::Unset`] uses the level of its parent, e.g. `my_node.planner` uses the level of
+/// `my_node`. The levels can also be set with the `--log-level` argument.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, LogSeverity, RclrsError};
+/// let context = Context::new([])?;
+/// let node = rclrs::create_node(&context, "my_node")?;
+/// rclrs::ros_info!(node.logger(), "Starting {} workers", 4);
+///
+/// let planner_logger = node.logger().get_child("planner")?;
+/// assert_eq!(planner_logger.name(), "my_node.planner");
+/// planner_logger.set_level(LogSeverity::Warn)?;
+/// // This message is not written
+/// rclrs::ros_info!(planner_logger, "Planning");
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::NodeBuilder::enable_rosout
+/// [2]: crate::ros_debug
+/// [3]: crate::ros_info
+/// [4]: crate::ros_warn
+/// [5]: crate::ros_error
+/// [6]: crate::ros_fatal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Logger {
+    name: CString,
+}
+
+impl Logger {
+    /// Creates a logger with the given name.
+    ///
+    /// Loggers with the same name share their level.
+    pub fn new(name: &str) -> Result<Self, RclrsError> {
+        let name = CString::new(name).map_err(|err| RclrsError::StringContainsNul {
+            err,
+            s: name.to_string(),
+        })?;
+        Ok(Self { name })
+    }
+
+    /// Returns the name of the logger.
+    pub fn name(&self) -> &str {
+        // The name has been created from a &str
+        self.name.to_str().unwrap()
+    }
+
+    /// Creates a logger whose name is this logger's name, a dot and the given name.
+    pub fn get_child(&self, name: &str) -> Result<Logger, RclrsError> {
+        Logger::new(&format!("{}.{}", self.name(), name))
+    }
+
+    /// Sets the level of the logger, i.e. the lowest severity of the messages that it writes.
+    pub fn set_level(&self, level: LogSeverity) -> Result<(), RclrsError> {
+        let _lock = lock_logging();
+        // SAFETY: The name is a valid C string.
+        unsafe { rcutils_logging_set_logger_level(self.name.as_ptr(), level.as_rcutils_severity()) }
+            .ok()
+    }
+
+    /// Returns true if the logger writes messages with the given severity.
+    pub fn is_enabled_for(&self, severity: LogSeverity) -> bool {
+        let _lock = lock_logging();
+        // SAFETY: The name is a valid C string.
+        unsafe {
+            rcutils_logging_logger_is_enabled_for(
+                self.name.as_ptr(),
+                severity.as_rcutils_severity(),
+            )
+        }
+    }
+
+    /// Writes a message, which is only formatted if the logger is enabled for the severity.
+    ///
+    /// This is used by the logging macros, which pass the location of their call.
+    #[doc(hidden)]
+    pub fn log_at(
+        &self,
+        severity: LogSeverity,
+        file: &str,
+        function: &str,
+        line: u32,
+        args: fmt::Arguments,
+    ) {
+        if !self.is_enabled_for(severity) {
+            return;
+        }
+        // The message is formatted without holding the lock, since formatting can call code
+        // that logs as well.
+        let message = to_cstring_lossy(fmt::format(args));
+        let file = to_cstring_lossy(file.to_string());
+        let function = to_cstring_lossy(function.to_string());
+        let location = rcutils_log_location_t {
+            function_name: function.as_ptr(),
+            file_name: file.as_ptr(),
+            line_number: line as usize,
+        };
+        let _lock = lock_logging();
+        // SAFETY: All pointers are valid C strings that outlive the call, and the message is
+        // passed as an argument so that it is not interpreted as a format string.
+        unsafe {
+            rcutils_log(
+                &location,
+                severity.as_rcutils_severity(),
+                self.name.as_ptr(),
+                b"%s\0".as_ptr() as *const c_char,
+                message.as_ptr(),
+            );
+        }
+    }
+}
+
+// Log messages should not be lost because of a nul byte, so it is escaped instead.
+fn to_cstring_lossy(s: String) -> CString {
+    CString::new(s).unwrap_or_else(|err| {
+        let s = String::from_utf8_lossy(&err.into_vec()).replace('\0', "\\0");
+        // The nul bytes have been replaced
+        CString::new(s).unwrap()
+    })
+}
+
+/// Returns the name of the function that contains the given function item.
+///
+/// This is used by the logging macros, since Rust has no macro for the function name.
+#[doc(hidden)]
+pub fn __enclosing_function_name<T>(_: T) -> &'static str {
+    let mut name = std::any::type_name::<T>();
+    name = name.strip_suffix("::__rclrs_function").unwrap_or(name);
+    // Closures are part of the function that they are defined in
+    while let Some(stripped) = name.strip_suffix("::{{closure}}") {
+        name = stripped;
+    }
+    name
+}
+
+/// Writes a log message with the given [`LogSeverity`].
+///
+/// The first argument is the [`Logger`], the second one the severity, and the remaining ones
+/// are a format string and its arguments, like for [`format!`]. The file, function and line of
+/// the call are passed along with the message.
+///
+/// Usually, one of the macros for a specific severity is used instead, e.g. [`ros_info!`].
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, LogSeverity, RclrsError};
+/// let context = Context::new([])?;
+/// let node = rclrs::create_node(&context, "my_node")?;
+/// let severity = LogSeverity::Warn;
+/// rclrs::ros_log!(node.logger(), severity, "The battery is at {}%", 15);
+/// # Ok::<(), RclrsError>(())
+/// ```
+#[macro_export]
+macro_rules! ros_log {
+    ($logger:expr, $severity:expr, $($arg:tt)+) => {{
+        fn __rclrs_function() {}
+        $logger.log_at(
+            $severity,
+            ::core::file!(),
+            $crate::__enclosing_function_name(__rclrs_function),
+            ::core::line!(),
+            ::core::format_args!($($arg)+),
+        )
+    }};
+}
+
+/// Writes a log message with [`LogSeverity::Debug`], see [`ros_log!`].
+#[macro_export]
+macro_rules! ros_debug {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::ros_log!($logger, $crate::LogSeverity::Debug, $($arg)+)
+    };
+}
+
+/// Writes a log message with [`LogSeverity::Info`], see [`ros_log!`].
+#[macro_export]
+macro_rules! ros_info {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::ros_log!($logger, $crate::LogSeverity::Info, $($arg)+)
+    };
+}
+
+/// Writes a log message with [`LogSeverity::Warn`], see [`ros_log!`].
+#[macro_export]
+macro_rules! ros_warn {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::ros_log!($logger, $crate::LogSeverity::Warn, $($arg)+)
+    };
+}
+
+/// Writes a log message with [`LogSeverity::Error`], see [`ros_log!`].
+#[macro_export]
+macro_rules! ros_error {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::ros_log!($logger, $crate::LogSeverity::Error, $($arg)+)
+    };
+}
+
+/// Writes a log message with [`LogSeverity::Fatal`], see [`ros_log!`].
+#[macro_export]
+macro_rules! ros_fatal {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::ros_log!($logger, $crate::LogSeverity::Fatal, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_helpers::{spin_until, wait_for_subscription};
+    use crate::vendor::rcl_interfaces::msg::Log;
+    use crate::{Context, Node, QOS_PROFILE_DEFAULT};
+
+    #[test]
+    fn test_logger_levels() -> Result<(), RclrsError> {
+        let logger = Logger::new("logger_levels_test")?;
+        let child = logger.get_child("child")?;
+        assert_eq!(child.name(), "logger_levels_test.child");
+        logger.set_level(LogSeverity::Error)?;
+        assert!(!child.is_enabled_for(LogSeverity::Warn));
+        assert!(child.is_enabled_for(LogSeverity::Error));
+        child.set_level(LogSeverity::Debug)?;
+        assert!(child.is_enabled_for(LogSeverity::Debug));
+        assert!(Logger::new("nul\0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_enclosing_function_name() {
+        fn __rclrs_function() {}
+        assert_eq!(
+            __enclosing_function_name(__rclrs_function),
+            "rclrs::logging::tests::test_enclosing_function_name"
+        );
+        let in_closure = || {
+            fn __rclrs_function() {}
+            __enclosing_function_name(__rclrs_function)
+        };
+        assert_eq!(
+            in_closure(),
+            "rclrs::logging::tests::test_enclosing_function_name"
+        );
+    }
+
+    #[test]
+    fn test_rosout() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Node::new(&context, "rosout_node")?;
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let messages_clone = Arc::clone(&messages);
+        let mut listener = Node::new(&context, "rosout_listener")?;
+        let _subscription =
+            listener.create_subscription("/rosout", QOS_PROFILE_DEFAULT, move |msg: Log| {
+                if msg.name == "rosout_node" {
+                    messages_clone.lock().unwrap().push(msg);
+                }
+            })?;
+        wait_for_subscription(&node, "/rosout", &listener);
+
+        crate::ros_warn!(node.logger(), "Low battery: {}%", 15);
+        spin_until(&listener, || !messages.lock().unwrap().is_empty());
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].level, Log::WARN);
+        assert_eq!(messages[0].msg, "Low battery: 15%");
+        assert_eq!(messages[0].file, file!());
+        assert_eq!(messages[0].function, "rclrs::logging::tests::test_rosout");
+        assert!(messages[0].line > 0);
+        Ok(())
+    }
+}
@@ -4,7 +4,10 @@ use std::cmp::PartialEq;
 use std::ffi::CStr;
 use std::fmt;
 use std::os::raw::c_char;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
 use std::vec::Vec;
 
 use rosidl_runtime_rs::Message;
@@ -13,13 +16,19 @@ pub use self::builder::*;
 pub use self::graph::*;
 use crate::rcl_bindings::*;
 use crate::{
-    Client, ClientBase, Context, GuardCondition, ParameterOverrideMap, Publisher, QoSProfile,
-    RclrsError, Service, ServiceBase, Subscription, SubscriptionBase, SubscriptionCallback,
-    ToResult,
+    parameters_from_yaml_file, parameters_to_yaml, CallbackGroup, CallbackGroupType, Client,
+    ClientBase, Clock, ClockType, Context, GuardCondition, Logger, LoggingGuard, Parameter,
+    ParameterCallbackHandle, ParameterDescriptor, ParameterError, ParameterEventHandler,
+    ParameterInterface, ParameterService, ParameterStruct, ParameterValue, ParameterVariant,
+    Parameters, ParametersClient, Publisher, QoSProfile, Rate, RclrsError, Service, ServiceBase,
+    Subscription, SubscriptionBase, SubscriptionCallback, Time, TimeSource, Timer, ToResult,
+    WaitSetCache, Waitable,
 };
 
 impl Drop for rcl_node_t {
     fn drop(&mut self) {
+        // Destroying the rosout publisher of the node must not race with logging
+        let _logging = LoggingGuard::lock();
         // SAFETY: No preconditions for this function
         unsafe { rcl_node_fini(self).ok().unwrap() };
     }
@@ -66,12 +75,19 @@ unsafe impl Send for rcl_node_t {}
 /// [4]: crate::NodeBuilder::namespace
 pub struct Node {
     pub(crate) rcl_node_mtx: Arc<Mutex<rcl_node_t>>,
-    pub(crate) rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
-    pub(crate) clients: Vec<Weak<dyn ClientBase>>,
+    pub(crate) context: Context,
+    // The first callback group is the default callback group.
+    pub(crate) callback_groups: Vec<Arc<CallbackGroup>>,
     pub(crate) guard_conditions: Vec<Weak<GuardCondition>>,
-    pub(crate) services: Vec<Weak<dyn ServiceBase>>,
-    pub(crate) subscriptions: Vec<Weak<dyn SubscriptionBase>>,
-    _parameter_map: ParameterOverrideMap,
+    // Incremented whenever an entity is added, so that wait sets can be rebuilt. This is shared
+    // with the time source, which adds the /clock subscription when use_sim_time is enabled.
+    pub(crate) entity_generation: Arc<AtomicUsize>,
+    pub(crate) time_source: TimeSource,
+    pub(crate) parameters: Arc<ParameterInterface>,
+    pub(crate) _parameter_service: Option<ParameterService>,
+    pub(crate) logger: Logger,
+    // Reused by spin_once(), so that the wait set is not created anew on every call.
+    pub(crate) wait_set_cache: Arc<Mutex<WaitSetCache>>,
 }
 
 impl Eq for Node {}
@@ -168,6 +184,17 @@ impl Node {
         self.call_string_getter(rcl_node_get_fully_qualified_name)
     }
 
+    /// Returns the logger of the node.
+    ///
+    /// Its name is the fully qualified name of the node, with dots instead of slashes and without
+    /// the leading slash, e.g. `my.namespace.my_node`. Its messages are published on `/rosout`,
+    /// unless this has been disabled with [`NodeBuilder::enable_rosout()`].
+    ///
+    /// See [`Logger`] for an example.
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
     // Helper for name(), namespace(), fully_qualified_name()
     fn call_string_getter(
         &self,
@@ -176,7 +203,7 @@ impl Node {
         unsafe { call_string_getter_with_handle(&*self.rcl_node_mtx.lock().unwrap(), getter) }
     }
 
-    /// Creates a [`Client`][1].
+    /// Creates a [`Client`][1] in the node's default [`CallbackGroup`].
     ///
     /// [1]: crate::Client
     // TODO: make client's lifetime depend on node's lifetime
@@ -184,9 +211,53 @@ impl Node {
     where
         T: rosidl_runtime_rs::Service,
     {
+        let callback_group = self.default_callback_group();
+        self.create_client_with_callback_group(&callback_group, topic)
+    }
+
+    /// Creates a [`ParametersClient`][1] for the parameter services of another node.
+    ///
+    /// The name of the other node can be fully qualified, e.g. `/robot/camera_driver`, or
+    /// relative to the namespace of this node. The clients are created in the node's default
+    /// [`CallbackGroup`].
+    ///
+    /// [1]: crate::ParametersClient
+    pub fn create_parameters_client(
+        &mut self,
+        remote_node_name: &str,
+    ) -> Result<ParametersClient, RclrsError> {
+        ParametersClient::new(self, remote_node_name)
+    }
+
+    /// Creates a [`ParameterEventHandler`][1] to react to the parameter changes of other nodes.
+    ///
+    /// The handler subscribes to `/parameter_events` in the node's default [`CallbackGroup`].
+    ///
+    /// [1]: crate::ParameterEventHandler
+    pub fn create_parameter_event_handler(&mut self) -> Result<ParameterEventHandler, RclrsError> {
+        ParameterEventHandler::new(self)
+    }
+
+    /// Creates a [`Client`][1] in the given [`CallbackGroup`] of this node.
+    ///
+    /// [1]: crate::Client
+    // TODO: make client's lifetime depend on node's lifetime
+    pub fn create_client_with_callback_group<T>(
+        &mut self,
+        callback_group: &Arc<CallbackGroup>,
+        topic: &str,
+    ) -> Result<Arc<Client<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        self.check_callback_group(callback_group)?;
         let client = Arc::new(Client::<T>::new(Arc::clone(&self.rcl_node_mtx), topic)?);
-        self.clients
+        callback_group
+            .clients
+            .lock()
+            .unwrap()
             .push(Arc::downgrade(&client) as Weak<dyn ClientBase>);
+        self.entity_generation.fetch_add(1, Ordering::Release);
         Ok(client)
     }
 
@@ -201,11 +272,12 @@ impl Node {
     /// [2]: crate::spin_once
     pub fn create_guard_condition(&mut self) -> Arc<GuardCondition> {
         let guard_condition = Arc::new(GuardCondition::new_with_rcl_context(
-            &mut self.rcl_context_mtx.lock().unwrap(),
+            &mut self.context.rcl_context_mtx.lock().unwrap(),
             None,
         ));
         self.guard_conditions
             .push(Arc::downgrade(&guard_condition) as Weak<GuardCondition>);
+        self.entity_generation.fetch_add(1, Ordering::Release);
         guard_condition
     }
 
@@ -215,6 +287,8 @@ impl Node {
     /// When this node is added to a wait set (e.g. when calling `spin_once`[2]
     /// with this node as an argument), the guard condition can be used to
     /// interrupt the wait.
+    /// The callback is run by the spin functions and executors after the guard condition has
+    /// been triggered, on the thread that spins the node.
     ///
     /// [1]: crate::GuardCondition
     /// [2]: crate::spin_once
@@ -223,11 +297,12 @@ impl Node {
         F: Fn() + Send + Sync + 'static,
     {
         let guard_condition = Arc::new(GuardCondition::new_with_rcl_context(
-            &mut self.rcl_context_mtx.lock().unwrap(),
+            &mut self.context.rcl_context_mtx.lock().unwrap(),
             Some(Box::new(callback) as Box<dyn Fn() + Send + Sync>),
         ));
         self.guard_conditions
             .push(Arc::downgrade(&guard_condition) as Weak<GuardCondition>);
+        self.entity_generation.fetch_add(1, Ordering::Release);
         guard_condition
     }
 
@@ -246,7 +321,7 @@ impl Node {
         Publisher::<T>::new(Arc::clone(&self.rcl_node_mtx), topic, qos)
     }
 
-    /// Creates a [`Service`][1].
+    /// Creates a [`Service`][1] in the node's default [`CallbackGroup`].
     ///
     /// [1]: crate::Service
     // TODO: make service's lifetime depend on node's lifetime
@@ -259,17 +334,40 @@ impl Node {
         T: rosidl_runtime_rs::Service,
         F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
     {
+        let callback_group = self.default_callback_group();
+        self.create_service_with_callback_group(&callback_group, topic, callback)
+    }
+
+    /// Creates a [`Service`][1] in the given [`CallbackGroup`] of this node.
+    ///
+    /// [1]: crate::Service
+    // TODO: make service's lifetime depend on node's lifetime
+    pub fn create_service_with_callback_group<T, F>(
+        &mut self,
+        callback_group: &Arc<CallbackGroup>,
+        topic: &str,
+        callback: F,
+    ) -> Result<Arc<Service<T>>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
+    {
+        self.check_callback_group(callback_group)?;
         let service = Arc::new(Service::<T>::new(
             Arc::clone(&self.rcl_node_mtx),
             topic,
             callback,
         )?);
-        self.services
+        callback_group
+            .services
+            .lock()
+            .unwrap()
             .push(Arc::downgrade(&service) as Weak<dyn ServiceBase>);
+        self.entity_generation.fetch_add(1, Ordering::Release);
         Ok(service)
     }
 
-    /// Creates a [`Subscription`][1].
+    /// Creates a [`Subscription`][1] in the node's default [`CallbackGroup`].
     ///
     /// [1]: crate::Subscription
     // TODO: make subscription's lifetime depend on node's lifetime
@@ -282,27 +380,237 @@ impl Node {
     where
         T: Message,
     {
+        let callback_group = self.default_callback_group();
+        self.create_subscription_with_callback_group(&callback_group, topic, qos, callback)
+    }
+
+    /// Creates a [`Subscription`][1] in the given [`CallbackGroup`] of this node.
+    ///
+    /// [1]: crate::Subscription
+    // TODO: make subscription's lifetime depend on node's lifetime
+    pub fn create_subscription_with_callback_group<T, Args>(
+        &mut self,
+        callback_group: &Arc<CallbackGroup>,
+        topic: &str,
+        qos: QoSProfile,
+        callback: impl SubscriptionCallback<T, Args>,
+    ) -> Result<Arc<Subscription<T>>, RclrsError>
+    where
+        T: Message,
+    {
+        self.check_callback_group(callback_group)?;
         let subscription = Arc::new(Subscription::<T>::new(
             Arc::clone(&self.rcl_node_mtx),
             topic,
             qos,
             callback,
         )?);
-        self.subscriptions
+        callback_group
+            .subscriptions
+            .lock()
+            .unwrap()
             .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        self.entity_generation.fetch_add(1, Ordering::Release);
         Ok(subscription)
     }
 
+    /// Creates a [`Timer`][1] that runs the callback every `period`.
+    ///
+    /// The period is measured by the node's [clock][2], which uses ROS time.
+    /// The callback is run when the node is spun, e.g. by [`spin_once`][3], after the period has
+    /// elapsed. The timer is in the node's default [`CallbackGroup`].
+    ///
+    /// [1]: crate::Timer
+    /// [2]: Node::get_clock
+    /// [3]: crate::spin_once
+    // TODO: make timer's lifetime depend on node's lifetime
+    pub fn create_timer<F>(
+        &mut self,
+        period: Duration,
+        callback: F,
+    ) -> Result<Arc<Timer>, RclrsError>
+    where
+        F: FnMut() + 'static + Send,
+    {
+        let callback_group = self.default_callback_group();
+        self.create_timer_with_callback_group(&callback_group, period, callback)
+    }
+
+    /// Creates a [`Timer`][1] in the given [`CallbackGroup`] of this node.
+    ///
+    /// See [`Node::create_timer`].
+    ///
+    /// [1]: crate::Timer
+    // TODO: make timer's lifetime depend on node's lifetime
+    pub fn create_timer_with_callback_group<F>(
+        &mut self,
+        callback_group: &Arc<CallbackGroup>,
+        period: Duration,
+        callback: F,
+    ) -> Result<Arc<Timer>, RclrsError>
+    where
+        F: FnMut() + 'static + Send,
+    {
+        self.check_callback_group(callback_group)?;
+        let timer = Arc::new(Timer::new(
+            self.get_clock(),
+            Arc::clone(&self.context.rcl_context_mtx),
+            period,
+            callback,
+        )?);
+        callback_group
+            .timers
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&timer) as Weak<Timer>);
+        self.entity_generation.fetch_add(1, Ordering::Release);
+        Ok(timer)
+    }
+
+    /// Creates a [`Timer`][1] that runs the callback every `period`, measured by a steady clock.
+    ///
+    /// Unlike [`Node::create_timer`], the period of this timer is unaffected by changes to ROS
+    /// time. The timer is in the node's default [`CallbackGroup`].
+    ///
+    /// [1]: crate::Timer
+    // TODO: make timer's lifetime depend on node's lifetime
+    pub fn create_wall_timer<F>(
+        &mut self,
+        period: Duration,
+        callback: F,
+    ) -> Result<Arc<Timer>, RclrsError>
+    where
+        F: FnMut() + 'static + Send,
+    {
+        let timer = Arc::new(Timer::new(
+            Clock::new(ClockType::SteadyTime)?,
+            Arc::clone(&self.context.rcl_context_mtx),
+            period,
+            callback,
+        )?);
+        self.default_callback_group()
+            .timers
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&timer) as Weak<Timer>);
+        self.entity_generation.fetch_add(1, Ordering::Release);
+        Ok(timer)
+    }
+
+    /// Adds a [`Waitable`] to the node's default [`CallbackGroup`].
+    ///
+    /// The node only stores a weak pointer to the waitable, which is removed from the node when it
+    /// is dropped. It is executed when the node is spun and the waitable is ready.
+    pub fn add_waitable(&mut self, waitable: Arc<dyn Waitable>) {
+        let callback_group = self.default_callback_group();
+        // The default callback group always belongs to the node
+        let _ = self.add_waitable_to_callback_group(&callback_group, waitable);
+    }
+
+    /// Adds a [`Waitable`] to the given [`CallbackGroup`] of this node.
+    ///
+    /// See [`Node::add_waitable`].
+    pub fn add_waitable_to_callback_group(
+        &mut self,
+        callback_group: &Arc<CallbackGroup>,
+        waitable: Arc<dyn Waitable>,
+    ) -> Result<(), RclrsError> {
+        self.check_callback_group(callback_group)?;
+        callback_group
+            .waitables
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&waitable));
+        self.entity_generation.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Creates a new [`CallbackGroup`] in this node.
+    ///
+    /// Entities can be added to the group when they are created, e.g. with
+    /// [`Node::create_subscription_with_callback_group`]. The node keeps the group alive.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{CallbackGroupType, Context, RclrsError};
+    /// # use std::time::Duration;
+    /// let context = Context::new([])?;
+    /// let mut node = rclrs::create_node(&context, "my_node")?;
+    /// let callback_group = node.create_callback_group(CallbackGroupType::Reentrant);
+    /// let _timer =
+    ///     node.create_timer_with_callback_group(&callback_group, Duration::from_secs(1), || {})?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn create_callback_group(&mut self, group_type: CallbackGroupType) -> Arc<CallbackGroup> {
+        let callback_group = Arc::new(CallbackGroup::new(group_type));
+        self.callback_groups.push(Arc::clone(&callback_group));
+        callback_group
+    }
+
+    /// Returns the default [`CallbackGroup`] of this node, which is mutually exclusive.
+    pub fn default_callback_group(&self) -> Arc<CallbackGroup> {
+        Arc::clone(&self.callback_groups[0])
+    }
+
+    // Checks that the callback group was created by this node.
+    fn check_callback_group(&self, callback_group: &Arc<CallbackGroup>) -> Result<(), RclrsError> {
+        if self
+            .callback_groups
+            .iter()
+            .any(|group| Arc::ptr_eq(group, callback_group))
+        {
+            Ok(())
+        } else {
+            Err(RclrsError::CallbackGroupOfOtherNode)
+        }
+    }
+
+    /// Returns the clock of the node, which uses [ROS time][1].
+    ///
+    /// While the `use_sim_time` parameter is set to `true`, the clock follows the simulated time
+    /// published on the `/clock` topic, which is received when spinning the node.
+    ///
+    /// [1]: crate::ClockType::RosTime
+    pub fn get_clock(&self) -> Clock {
+        self.time_source.clock().clone()
+    }
+
+    /// Creates a [`Rate`] with the given period, measured by the node's clock.
+    ///
+    /// See the [`Rate`] documentation for an example.
+    pub fn create_rate(&self, period: Duration) -> Rate {
+        Rate::new(&self.context, self.get_clock(), period)
+    }
+
+    /// Returns the current time of the node's clock.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{ClockType, Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = rclrs::create_node(&context, "my_node")?;
+    /// let now = node.now();
+    /// assert_eq!(now.clock_type(), ClockType::RosTime);
+    /// assert!(now <= node.get_clock().now());
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn now(&self) -> Time {
+        self.time_source.clock().now()
+    }
+
     /// Returns the subscriptions that have not been dropped yet.
     pub(crate) fn live_subscriptions(&self) -> Vec<Arc<dyn SubscriptionBase>> {
-        self.subscriptions
+        self.callback_groups
             .iter()
-            .filter_map(Weak::upgrade)
+            .flat_map(|group| group.live_subscriptions())
             .collect()
     }
 
     pub(crate) fn live_clients(&self) -> Vec<Arc<dyn ClientBase>> {
-        self.clients.iter().filter_map(Weak::upgrade).collect()
+        self.callback_groups
+            .iter()
+            .flat_map(|group| group.live_clients())
+            .collect()
     }
 
     pub(crate) fn live_guard_conditions(&self) -> Vec<Arc<GuardCondition>> {
@@ -313,15 +621,238 @@ impl Node {
     }
 
     pub(crate) fn live_services(&self) -> Vec<Arc<dyn ServiceBase>> {
-        self.services.iter().filter_map(Weak::upgrade).collect()
+        self.callback_groups
+            .iter()
+            .flat_map(|group| group.live_services())
+            .collect()
+    }
+
+    pub(crate) fn live_timers(&self) -> Vec<Arc<Timer>> {
+        self.callback_groups
+            .iter()
+            .flat_map(|group| group.live_timers())
+            .collect()
+    }
+
+    pub(crate) fn live_waitables(&self) -> Vec<Arc<dyn Waitable>> {
+        self.callback_groups
+            .iter()
+            .flat_map(|group| group.live_waitables())
+            .collect()
+    }
+
+    /// Declares a parameter, and returns its value.
+    ///
+    /// If a value for the parameter was given on the command line or in a parameter file, e.g.
+    /// by a launch file, that value is used instead of the default value. It must have the same
+    /// type as the default value.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, ParameterValue, RclrsError};
+    /// let context = Context::new(["--ros-args", "-p", "speed:=2.0"].map(String::from))?;
+    /// let node = rclrs::create_node(&context, "my_node")?;
+    /// assert_eq!(node.declare_parameter("speed", 1.0)?, ParameterValue::Double(2.0));
+    /// assert_eq!(node.declare_parameter("name", "robot")?, "robot".into());
+    /// assert_eq!(node.get_parameter::<String>("name")?, "robot");
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn declare_parameter(
+        &self,
+        name: &str,
+        default_value: impl Into<ParameterValue>,
+    ) -> Result<ParameterValue, RclrsError> {
+        self.declare_parameter_with_descriptor(name, default_value, ParameterDescriptor::default())
+    }
+
+    /// Declares a parameter with a descriptor, and returns its value.
+    ///
+    /// This is like [`Node::declare_parameter()`], but the descriptor can document the
+    /// parameter, make it read-only, allow changing its type, or restrict its values to a
+    /// range. Declaring fails if the value is outside of the range, and later changes are
+    /// checked against the descriptor as well.
+    ///
+    /// See [`ParameterDescriptor`] for an example.
+    pub fn declare_parameter_with_descriptor(
+        &self,
+        name: &str,
+        default_value: impl Into<ParameterValue>,
+        descriptor: ParameterDescriptor,
+    ) -> Result<ParameterValue, RclrsError> {
+        Ok(self
+            .parameters
+            .declare(name, default_value.into(), descriptor)?)
+    }
+
+    /// Declares the parameters of a [`Parameters`] struct, and keeps the struct in sync with
+    /// them.
+    ///
+    /// The parameter names are prefixed with the given prefix and a dot, unless it is empty.
+    /// Like with [`Node::declare_parameter()`], overrides from the command line or parameter
+    /// files take precedence over the defaults. The struct stops being updated when the
+    /// returned [`ParameterStruct`] is dropped, but the parameters stay declared.
+    ///
+    /// See [`Parameters`] for an example.
+    pub fn declare_parameters<T: Parameters>(
+        &self,
+        prefix: &str,
+    ) -> Result<ParameterStruct<T>, RclrsError> {
+        ParameterStruct::new(self, prefix)
+    }
+
+    /// Returns the descriptor that a parameter has been declared with.
+    pub fn describe_parameter(&self, name: &str) -> Result<ParameterDescriptor, RclrsError> {
+        Ok(self.parameters.describe(name)?)
+    }
+
+    /// Returns the value of a declared parameter, converted to the given type.
+    ///
+    /// Use `get_parameter::<ParameterValue>()` to get the value regardless of its type.
+    pub fn get_parameter<T: ParameterVariant>(&self, name: &str) -> Result<T, RclrsError> {
+        let value = self.parameters.get(name)?;
+        let actual = value.kind();
+        T::from_parameter_value(value).ok_or_else(|| {
+            ParameterError::TypeMismatch {
+                name: name.to_string(),
+                // Types whose kind() is None accept any value
+                expected: T::kind().unwrap_or(actual),
+                actual,
+            }
+            .into()
+        })
+    }
+
+    /// Changes the value of a declared parameter.
+    ///
+    /// The new value must satisfy the parameter's descriptor, which means for instance that the
+    /// type of a parameter can not be changed, unless it allows dynamic typing. If the node
+    /// has been built with [`NodeBuilder::allow_undeclared_parameters()`], an undeclared
+    /// parameter is declared instead.
+    pub fn set_parameter(
+        &self,
+        name: &str,
+        value: impl Into<ParameterValue>,
+    ) -> Result<(), RclrsError> {
+        Ok(self.parameters.set(name, value.into())?)
+    }
+
+    /// Returns true if the parameter has been declared.
+    pub fn has_parameter(&self, name: &str) -> bool {
+        self.parameters.has(name)
+    }
+
+    /// Removes a declared parameter, unless it is read-only.
+    pub fn undeclare_parameter(&self, name: &str) -> Result<(), RclrsError> {
+        Ok(self.parameters.undeclare(name)?)
+    }
+
+    /// Sets parameters to the values in a parameter file.
+    ///
+    /// The file has the same layout as the files given with the `--params-file` argument, and
+    /// the values for `/**` and for this node are used. All of the parameters must have been
+    /// declared, unless the node allows undeclared parameters, and either all of them are
+    /// changed, or none if one of them can't be.
+    /// Parameters that already have the value from the file are not changed, so that a file from
+    /// [`Node::dump_parameters()`] can be loaded even if the node has read-only parameters.
+    pub fn load_parameters_from_file(&self, path: impl AsRef<Path>) -> Result<(), RclrsError> {
+        let values = parameters_from_yaml_file(path.as_ref(), &self.fully_qualified_name())?;
+        Ok(self.parameters.load(values)?)
+    }
+
+    /// Returns the values of all declared parameters in the layout of a parameter file.
+    ///
+    /// The result can be written to a file, and loaded with
+    /// [`Node::load_parameters_from_file()`] or the `--params-file` argument.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = rclrs::create_node(&context, "calibration_node")?;
+    /// node.declare_parameter("offset", 0.5)?;
+    /// let path = std::env::temp_dir().join("calibration_node.yaml");
+    /// std::fs::write(&path, node.dump_parameters()).unwrap();
+    ///
+    /// node.set_parameter("offset", 0.7)?;
+    /// node.load_parameters_from_file(&path)?;
+    /// assert_eq!(node.get_parameter::<f64>("offset")?, 0.5);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn dump_parameters(&self) -> String {
+        parameters_to_yaml(&self.fully_qualified_name(), &self.parameters.values())
+    }
+
+    /// Adds a callback that is called with each batch of parameter changes before it is
+    /// validated.
+    ///
+    /// The callback may modify the batch, e.g. to change further parameters that depend on the
+    /// changed ones. A batch that the callback empties is rejected.
+    ///
+    /// The callback is removed when the returned handle is dropped. Like the other parameter
+    /// callbacks, it may read parameters, but must not change them.
+    pub fn add_pre_set_parameters_callback<F>(&self, callback: F) -> ParameterCallbackHandle
+    where
+        F: Fn(&mut Vec<Parameter>) + 'static + Send + Sync,
+    {
+        self.parameters.add_pre_set_callback(Arc::new(callback))
+    }
+
+    /// Adds a callback that can reject a batch of parameter changes.
+    ///
+    /// The callback is called after the changes have been validated against the parameter
+    /// descriptors, and before they are applied. If it returns an error, none of the changes in
+    /// the batch are applied, and the error is returned as the reason in the
+    /// `SetParametersResult` of the parameter services, or as [`ParameterError::Rejected`] from
+    /// [`Node::set_parameter()`].
+    ///
+    /// The callback is removed when the returned handle is dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, RclrsError};
+    /// let context = Context::new([])?;
+    /// let node = rclrs::create_node(&context, "my_node")?;
+    /// node.declare_parameter("port", 8080i64)?;
+    /// let _handle = node.add_on_set_parameters_callback(|changes| {
+    ///     for change in changes {
+    ///         if change.name == "port" && change.value == Some(0i64.into()) {
+    ///             return Err(String::from("Port 0 is not allowed"));
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// });
+    /// assert!(node.set_parameter("port", 0i64).is_err());
+    /// assert_eq!(node.get_parameter::<i64>("port")?, 8080);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn add_on_set_parameters_callback<F>(&self, callback: F) -> ParameterCallbackHandle
+    where
+        F: Fn(&[Parameter]) -> Result<(), String> + 'static + Send + Sync,
+    {
+        self.parameters.add_on_set_callback(Arc::new(callback))
+    }
+
+    /// Adds a callback that is called with each batch of parameter changes after it has been
+    /// applied.
+    ///
+    /// Unlike the other parameter callbacks, this callback may change parameters.
+    ///
+    /// The callback is removed when the returned handle is dropped.
+    pub fn add_post_set_parameters_callback<F>(&self, callback: F) -> ParameterCallbackHandle
+    where
+        F: Fn(&[Parameter]) + 'static + Send + Sync,
+    {
+        self.parameters.add_post_set_callback(Arc::new(callback))
     }
 
     /// Returns the ROS domain ID that the node is using.
     ///    
     /// The domain ID controls which nodes can send messages to each other, see the [ROS 2 concept article][1].
-    /// It can be set through the `ROS_DOMAIN_ID` environment variable.
+    /// It can be set through the `ROS_DOMAIN_ID` environment variable, or for a single context
+    /// with [`ContextBuilder::domain_id()`][2].
     ///
     /// [1]: https://docs.ros.org/en/rolling/Concepts/About-Domain-ID.html
+    /// [2]: crate::ContextBuilder::domain_id
     ///
     /// # Example
     /// ```
@@ -398,4 +929,26 @@ mod tests {
         assert_send::<Node>();
         assert_sync::<Node>();
     }
+
+    #[test]
+    fn test_callback_group_of_other_node_is_rejected() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node_1 = Node::new(&context, "test_callback_group_node_1")?;
+        let mut node_2 = Node::new(&context, "test_callback_group_node_2")?;
+        let callback_group = node_1.create_callback_group(CallbackGroupType::Reentrant);
+        assert_eq!(callback_group.group_type(), CallbackGroupType::Reentrant);
+        assert_eq!(
+            node_1.default_callback_group().group_type(),
+            CallbackGroupType::MutuallyExclusive
+        );
+        let period = Duration::from_secs(1);
+        assert!(node_1
+            .create_timer_with_callback_group(&callback_group, period, || {})
+            .is_ok());
+        assert!(matches!(
+            node_2.create_timer_with_callback_group(&callback_group, period, || {}),
+            Err(RclrsError::CallbackGroupOfOtherNode)
+        ));
+        Ok(())
+    }
 }
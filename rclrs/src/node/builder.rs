@@ -1,10 +1,13 @@
 use std::ffi::CString;
+use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex};
 
 use crate::rcl_bindings::*;
 use crate::{
-    node::call_string_getter_with_handle, resolve_parameter_overrides, Context, Node, RclrsError,
-    ToResult,
+    node::call_string_getter_with_handle, resolve_parameter_overrides, CallbackGroup,
+    CallbackGroupType, Clock, ClockType, Context, Logger, LoggingGuard, Node,
+    ParameterEventPublisher, ParameterInterface, ParameterService, RclrsError, TimeSource,
+    ToResult, WaitSetCache,
 };
 
 /// A builder for creating a [`Node`][1].
@@ -17,6 +20,10 @@ use crate::{
 /// - `use_global_arguments: true`
 /// - `arguments: []`
 /// - `enable_rosout: true`
+/// - `start_parameter_services: true`
+/// - `start_parameter_event_publisher: true`
+/// - `allow_undeclared_parameters: false`
+/// - `automatically_declare_parameters_from_overrides: false`
 ///
 /// # Example
 /// ```
@@ -40,12 +47,16 @@ use crate::{
 /// [1]: crate::Node
 /// [2]: crate::Node::builder
 pub struct NodeBuilder {
-    context: Arc<Mutex<rcl_context_t>>,
+    context: Context,
     name: String,
     namespace: String,
     use_global_arguments: bool,
     arguments: Vec<String>,
     enable_rosout: bool,
+    start_parameter_services: bool,
+    start_parameter_event_publisher: bool,
+    allow_undeclared_parameters: bool,
+    automatically_declare_parameters_from_overrides: bool,
 }
 
 impl NodeBuilder {
@@ -86,12 +97,16 @@ impl NodeBuilder {
     /// [3]: NodeBuilder::build
     pub fn new(context: &Context, name: &str) -> NodeBuilder {
         NodeBuilder {
-            context: context.rcl_context_mtx.clone(),
+            context: context.clone(),
             name: name.to_string(),
             namespace: "/".to_string(),
             use_global_arguments: true,
             arguments: vec![],
             enable_rosout: true,
+            start_parameter_services: true,
+            start_parameter_event_publisher: true,
+            allow_undeclared_parameters: false,
+            automatically_declare_parameters_from_overrides: false,
         }
     }
 
@@ -215,15 +230,68 @@ impl NodeBuilder {
 
     /// Enables or disables logging to rosout.
     ///
-    /// When enabled, log messages are published to the `/rosout` topic in addition to
-    /// standard output.
+    /// When enabled, the messages of the [node's logger][1] are published to the `/rosout`
+    /// topic in addition to standard output.
     ///
-    /// This option is currently unused in `rclrs`.
+    /// [1]: crate::Node::logger
     pub fn enable_rosout(mut self, enable: bool) -> Self {
         self.enable_rosout = enable;
         self
     }
 
+    /// Enables or disables the parameter services of the node.
+    ///
+    /// These services make the node's parameters accessible to other nodes, e.g. to the
+    /// `ros2 param` command line tool.
+    pub fn start_parameter_services(mut self, start: bool) -> Self {
+        self.start_parameter_services = start;
+        self
+    }
+
+    /// Enables or disables publishing the node's parameter changes.
+    ///
+    /// When enabled, a `rcl_interfaces/msg/ParameterEvent` is published on the
+    /// `/parameter_events` topic whenever a parameter is declared, changed or undeclared.
+    pub fn start_parameter_event_publisher(mut self, start: bool) -> Self {
+        self.start_parameter_event_publisher = start;
+        self
+    }
+
+    /// Enables or disables setting parameters that have not been declared.
+    ///
+    /// When enabled, setting an undeclared parameter, e.g. with [`Node::set_parameter()`][1] or
+    /// through the parameter services, declares it. Such parameters can change their type.
+    ///
+    /// [1]: crate::Node::set_parameter
+    pub fn allow_undeclared_parameters(mut self, allow: bool) -> Self {
+        self.allow_undeclared_parameters = allow;
+        self
+    }
+
+    /// Enables or disables declaring all parameters that have an override.
+    ///
+    /// When enabled, every parameter that is given a value on the command line or in a
+    /// parameter file for this node, or for all nodes with `/**`, is declared when the node is
+    /// built. Declaring one of these parameters again with [`Node::declare_parameter()`][1] is
+    /// then an error.
+    ///
+    /// # Example
+    /// ```
+    /// # use rclrs::{Context, Node, RclrsError};
+    /// let context = Context::new(["--ros-args", "-p", "speed:=2.0"].map(String::from))?;
+    /// let node = Node::builder(&context, "my_node")
+    ///     .automatically_declare_parameters_from_overrides(true)
+    ///     .build()?;
+    /// assert_eq!(node.get_parameter::<f64>("speed")?, 2.0);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    ///
+    /// [1]: crate::Node::declare_parameter
+    pub fn automatically_declare_parameters_from_overrides(mut self, enable: bool) -> Self {
+        self.automatically_declare_parameters_from_overrides = enable;
+        self
+    }
+
     /// Builds the node instance.
     ///
     /// Node name and namespace validation is performed in this method.
@@ -243,27 +311,37 @@ impl NodeBuilder {
                 s: self.namespace.clone(),
             })?;
         let rcl_node_options = self.create_rcl_node_options()?;
-        let rcl_context = &mut *self.context.lock().unwrap();
+        let rcl_context = &mut *self.context.rcl_context_mtx.lock().unwrap();
 
         // SAFETY: Getting a zero-initialized value is always safe.
         let mut rcl_node = unsafe { rcl_get_zero_initialized_node() };
-        unsafe {
-            // SAFETY: The rcl_node is zero-initialized as expected by this function.
-            // The strings and node options are copied by this function, so we don't need
-            // to keep them alive.
-            // The rcl_context has to be kept alive because it is co-owned by the node.
-            rcl_node_init(
-                &mut rcl_node,
-                node_name.as_ptr(),
-                node_namespace.as_ptr(),
-                rcl_context,
-                &rcl_node_options,
-            )
-            .ok()?;
-        };
+        {
+            // Creating the rosout publisher of the node must not race with logging
+            let _logging = LoggingGuard::lock();
+            unsafe {
+                // SAFETY: The rcl_node is zero-initialized as expected by this function.
+                // The strings and node options are copied by this function, so we don't need
+                // to keep them alive.
+                // The rcl_context has to be kept alive because it is co-owned by the node.
+                rcl_node_init(
+                    &mut rcl_node,
+                    node_name.as_ptr(),
+                    node_namespace.as_ptr(),
+                    rcl_context,
+                    &rcl_node_options,
+                )
+                .ok()?;
+            }
+        }
 
-        let _parameter_map = unsafe {
-            let fqn = call_string_getter_with_handle(&rcl_node, rcl_node_get_fully_qualified_name);
+        // SAFETY: The node has been initialized.
+        let fqn =
+            unsafe { call_string_getter_with_handle(&rcl_node, rcl_node_get_fully_qualified_name) };
+        // SAFETY: The node has been initialized.
+        let logger_name =
+            unsafe { call_string_getter_with_handle(&rcl_node, rcl_node_get_logger_name) };
+        let logger = Logger::new(&logger_name)?;
+        let parameter_overrides = unsafe {
             resolve_parameter_overrides(
                 &fqn,
                 &rcl_node_options.arguments,
@@ -271,16 +349,53 @@ impl NodeBuilder {
             )?
         };
         let rcl_node_mtx = Arc::new(Mutex::new(rcl_node));
+        let default_callback_group =
+            Arc::new(CallbackGroup::new(CallbackGroupType::MutuallyExclusive));
+        let clock = Clock::new(ClockType::RosTime)?;
+        // The publisher is created before any parameter is declared, so that no event is missed
+        let parameter_events = if self.start_parameter_event_publisher {
+            Some(ParameterEventPublisher::new(
+                &rcl_node_mtx,
+                fqn,
+                clock.clone(),
+            )?)
+        } else {
+            None
+        };
+        let parameters = Arc::new(ParameterInterface::new(
+            parameter_overrides,
+            self.allow_undeclared_parameters,
+            parameter_events,
+        ));
+        let entity_generation = Arc::new(AtomicUsize::new(0));
+        let time_source = TimeSource::new(
+            clock,
+            &rcl_node_mtx,
+            &default_callback_group,
+            &entity_generation,
+            &parameters,
+            &logger,
+        )?;
+        if self.automatically_declare_parameters_from_overrides {
+            parameters.declare_overrides()?;
+        }
 
-        Ok(Node {
+        let mut node = Node {
             rcl_node_mtx,
-            rcl_context_mtx: self.context.clone(),
-            clients: vec![],
+            context: self.context.clone(),
+            callback_groups: vec![default_callback_group],
             guard_conditions: vec![],
-            services: vec![],
-            subscriptions: vec![],
-            _parameter_map,
-        })
+            entity_generation,
+            time_source,
+            parameters,
+            _parameter_service: None,
+            logger,
+            wait_set_cache: WaitSetCache::new_releasable(),
+        };
+        if self.start_parameter_services {
+            node._parameter_service = Some(ParameterService::new(&mut node)?);
+        }
+        Ok(node)
     }
 
     /// Creates a rcl_node_options_t struct from this builder.
@@ -1,5 +1,637 @@
+mod callbacks;
+mod client;
+mod descriptor;
+mod event_handler;
+mod events;
 mod override_map;
+mod service;
+mod structs;
 mod value;
+mod yaml;
 
+pub use callbacks::*;
+pub use client::*;
+pub use descriptor::*;
+pub use event_handler::*;
+pub(crate) use events::*;
 pub(crate) use override_map::*;
+pub(crate) use service::*;
+pub use structs::*;
 pub use value::*;
+pub(crate) use yaml::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use crate::ParameterError;
+
+/// The parameters of a node.
+///
+/// The node and, through shared ownership, the parameter services access the parameters
+/// concurrently, which is why they are behind a mutex.
+pub(crate) struct ParameterInterface {
+    parameters_mtx: Mutex<BTreeMap<String, DeclaredParameter>>,
+    overrides: ParameterOverrideMap,
+    // If true, setting a parameter that has not been declared declares it
+    allow_undeclared: bool,
+    pre_set_callbacks: Arc<Mutex<CallbackRegistry<PreSetParametersCallback>>>,
+    on_set_callbacks: Arc<Mutex<CallbackRegistry<OnSetParametersCallback>>>,
+    post_set_callbacks: Arc<Mutex<CallbackRegistry<PostSetParametersCallback>>>,
+    // Called with the applied changes before the set lock is released, e.g. to keep parameter
+    // structs up to date in the order of the changes. They must not change parameters.
+    applied_callbacks: Arc<Mutex<CallbackRegistry<PostSetParametersCallback>>>,
+    // Held while a batch of changes is validated and applied, but not while reading parameters.
+    // This makes batches atomic while the callbacks are still able to read parameters.
+    set_mtx: Mutex<()>,
+    events: Option<ParameterEventPublisher>,
+}
+
+struct DeclaredParameter {
+    value: ParameterValue,
+    descriptor: ParameterDescriptor,
+}
+
+impl ParameterInterface {
+    pub(crate) fn new(
+        overrides: ParameterOverrideMap,
+        allow_undeclared: bool,
+        events: Option<ParameterEventPublisher>,
+    ) -> Self {
+        Self {
+            parameters_mtx: Mutex::new(BTreeMap::new()),
+            overrides,
+            allow_undeclared,
+            pre_set_callbacks: Arc::default(),
+            on_set_callbacks: Arc::default(),
+            post_set_callbacks: Arc::default(),
+            applied_callbacks: Arc::default(),
+            set_mtx: Mutex::new(()),
+            events,
+        }
+    }
+
+    /// Declares the parameter, using the override for it if there is one.
+    ///
+    /// Returns the value that the parameter has been declared with.
+    pub(crate) fn declare(
+        &self,
+        name: &str,
+        default_value: ParameterValue,
+        descriptor: ParameterDescriptor,
+    ) -> Result<ParameterValue, ParameterError> {
+        let mut parameters = self.parameters_mtx.lock().unwrap();
+        if parameters.contains_key(name) {
+            return Err(ParameterError::AlreadyDeclared {
+                name: name.to_string(),
+            });
+        }
+        let value = match self
+            .overrides
+            .get(name)
+            .map(|value| value.clone().into_kind_from_yaml(default_value.kind()))
+        {
+            Some(value) if !descriptor.dynamic_typing && value.kind() != default_value.kind() => {
+                return Err(ParameterError::TypeMismatch {
+                    name: name.to_string(),
+                    expected: default_value.kind(),
+                    actual: value.kind(),
+                })
+            }
+            Some(value) => value,
+            None => default_value,
+        };
+        descriptor.validate_declaration(name, &value)?;
+        parameters.insert(
+            name.to_string(),
+            DeclaredParameter {
+                value: value.clone(),
+                descriptor,
+            },
+        );
+        if let Some(events) = &self.events {
+            events.publish_declared(name, &value);
+        }
+        Ok(value)
+    }
+
+    /// Declares every parameter that has an override, unless it has already been declared.
+    ///
+    /// The parameters are declared with the default descriptor, so their type is fixed.
+    pub(crate) fn declare_overrides(&self) -> Result<(), ParameterError> {
+        for (name, value) in &self.overrides {
+            if !self.has(name) {
+                self.declare(name, value.clone(), ParameterDescriptor::default())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Result<ParameterValue, ParameterError> {
+        self.with_parameter(name, |parameter| parameter.value.clone())
+    }
+
+    pub(crate) fn describe(&self, name: &str) -> Result<ParameterDescriptor, ParameterError> {
+        self.with_parameter(name, |parameter| parameter.descriptor.clone())
+    }
+
+    fn with_parameter<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&DeclaredParameter) -> T,
+    ) -> Result<T, ParameterError> {
+        self.parameters_mtx
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(f)
+            .ok_or_else(|| ParameterError::NotDeclared {
+                name: name.to_string(),
+            })
+    }
+
+    /// Changes the value of a declared parameter, which must satisfy its descriptor.
+    ///
+    /// If undeclared parameters are allowed, an undeclared parameter is declared instead.
+    pub(crate) fn set(&self, name: &str, value: ParameterValue) -> Result<(), ParameterError> {
+        self.set_atomically(vec![Parameter {
+            name: name.to_string(),
+            value: Some(value),
+        }])
+    }
+
+    /// Changes the values of several parameters at once, or undeclares those whose value is
+    /// `None`.
+    ///
+    /// Either all changes are applied, or none of them if one is invalid or rejected by an
+    /// on-set callback, or if it changes the same parameter more than once. The pre-set callbacks
+    /// are called before validating the changes, and the post-set callbacks after applying them.
+    pub(crate) fn set_atomically(&self, mut changes: Vec<Parameter>) -> Result<(), ParameterError> {
+        let set_guard = self.set_mtx.lock().unwrap();
+        let pre_set = self.pre_set_callbacks.lock().unwrap().callbacks();
+        let on_set = self.on_set_callbacks.lock().unwrap().callbacks();
+        let post_set = self.post_set_callbacks.lock().unwrap().callbacks();
+        let applied = self.applied_callbacks.lock().unwrap().callbacks();
+
+        let was_empty = changes.is_empty();
+        for callback in pre_set {
+            callback(&mut changes);
+        }
+        if changes.is_empty() && !was_empty {
+            return Err(ParameterError::Rejected {
+                reason: String::from("A pre-set callback removed all parameter changes"),
+            });
+        }
+        self.validate(&changes)?;
+        for callback in on_set {
+            callback(&changes).map_err(|reason| ParameterError::Rejected { reason })?;
+        }
+
+        let mut parameters = self.parameters_mtx.lock().unwrap();
+        let mut declared = Vec::new();
+        for Parameter { name, value } in changes.iter().cloned() {
+            match value {
+                Some(value) => match parameters.get_mut(&name) {
+                    Some(parameter) => parameter.value = value,
+                    None if self.allow_undeclared => {
+                        // Like in rclcpp, the type of such a parameter is not fixed
+                        let descriptor = ParameterDescriptor {
+                            dynamic_typing: true,
+                            ..Default::default()
+                        };
+                        parameters.insert(name.clone(), DeclaredParameter { value, descriptor });
+                        declared.push(name);
+                    }
+                    // Ruled out by validate()
+                    None => unreachable!(),
+                },
+                None => {
+                    parameters.remove(&name);
+                }
+            }
+        }
+        drop(parameters);
+        // Publishing before releasing the set lock keeps the events in the order of the changes
+        if let Some(events) = &self.events {
+            events.publish_changes(&changes, &declared);
+        }
+        for callback in applied {
+            callback(&changes);
+        }
+        drop(set_guard);
+
+        for callback in post_set {
+            callback(&changes);
+        }
+        Ok(())
+    }
+
+    /// Checks that the changes satisfy the descriptors of the parameters.
+    ///
+    /// Each parameter may only be changed once, since the changes are validated against the
+    /// current values and not against the results of the earlier changes in the batch.
+    fn validate(&self, changes: &[Parameter]) -> Result<(), ParameterError> {
+        let parameters = self.parameters_mtx.lock().unwrap();
+        let mut names = BTreeSet::new();
+        for Parameter { name, value } in changes {
+            if !names.insert(name) {
+                return Err(ParameterError::ChangedTwice { name: name.clone() });
+            }
+            let parameter = match parameters.get(name) {
+                Some(parameter) => parameter,
+                // The parameter will be declared when the change is applied
+                None if self.allow_undeclared && value.is_some() => continue,
+                None => return Err(ParameterError::NotDeclared { name: name.clone() }),
+            };
+            let descriptor = &parameter.descriptor;
+            if descriptor.read_only {
+                return Err(ParameterError::ReadOnly { name: name.clone() });
+            }
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            if !descriptor.dynamic_typing && value.kind() != parameter.value.kind() {
+                return Err(ParameterError::TypeMismatch {
+                    name: name.clone(),
+                    expected: parameter.value.kind(),
+                    actual: value.kind(),
+                });
+            }
+            descriptor.validate_value(name, value)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn add_pre_set_callback(
+        &self,
+        callback: Arc<PreSetParametersCallback>,
+    ) -> ParameterCallbackHandle {
+        CallbackRegistry::add(&self.pre_set_callbacks, callback)
+    }
+
+    pub(crate) fn add_on_set_callback(
+        &self,
+        callback: Arc<OnSetParametersCallback>,
+    ) -> ParameterCallbackHandle {
+        CallbackRegistry::add(&self.on_set_callbacks, callback)
+    }
+
+    pub(crate) fn add_post_set_callback(
+        &self,
+        callback: Arc<PostSetParametersCallback>,
+    ) -> ParameterCallbackHandle {
+        CallbackRegistry::add(&self.post_set_callbacks, callback)
+    }
+
+    pub(crate) fn add_applied_callback(
+        &self,
+        callback: Arc<PostSetParametersCallback>,
+    ) -> ParameterCallbackHandle {
+        CallbackRegistry::add(&self.applied_callbacks, callback)
+    }
+
+    pub(crate) fn has(&self, name: &str) -> bool {
+        self.parameters_mtx.lock().unwrap().contains_key(name)
+    }
+
+    pub(crate) fn undeclare(&self, name: &str) -> Result<(), ParameterError> {
+        self.set_atomically(vec![Parameter {
+            name: name.to_string(),
+            value: None,
+        }])
+    }
+
+    /// Sets the parameters to the values that have been read from a parameter file.
+    ///
+    /// Parameters that already have the value from the file are left alone, so that the
+    /// values of read-only parameters can be loaded back after dumping them.
+    pub(crate) fn load(&self, values: ParameterOverrideMap) -> Result<(), ParameterError> {
+        let parameters = self.parameters_mtx.lock().unwrap();
+        let mut changes = Vec::new();
+        for (name, value) in values {
+            let value = match parameters.get(&name) {
+                Some(parameter) => {
+                    let value = value.into_kind_from_yaml(parameter.value.kind());
+                    if parameter.value == value {
+                        continue;
+                    }
+                    value
+                }
+                // Validating the change fails, unless undeclared parameters are allowed
+                None => value,
+            };
+            changes.push(Parameter {
+                name,
+                value: Some(value),
+            });
+        }
+        drop(parameters);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        self.set_atomically(changes)
+    }
+
+    /// Returns the values of all declared parameters.
+    pub(crate) fn values(&self) -> BTreeMap<String, ParameterValue> {
+        self.parameters_mtx
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, parameter)| (name.clone(), parameter.value.clone()))
+            .collect()
+    }
+
+    /// Returns the names of all declared parameters, in alphabetical order.
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.parameters_mtx
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, Node, RclrsError};
+
+    fn node_with_overrides(overrides: &[&str]) -> Result<Node, RclrsError> {
+        let args = std::iter::once("--ros-args")
+            .chain(overrides.iter().flat_map(|&p| ["-p", p]))
+            .map(String::from);
+        let context = Context::new(args)?;
+        Node::new(&context, "parameter_test_node")
+    }
+
+    #[test]
+    fn test_declared_parameters() -> Result<(), RclrsError> {
+        let node = node_with_overrides(&[])?;
+        assert!(!node.has_parameter("speed"));
+        assert_eq!(
+            node.declare_parameter("speed", 1.5)?,
+            ParameterValue::Double(1.5)
+        );
+        assert!(node.has_parameter("speed"));
+        assert_eq!(node.get_parameter::<f64>("speed")?, 1.5);
+        assert_eq!(
+            node.get_parameter::<ParameterValue>("speed")?,
+            ParameterValue::Double(1.5)
+        );
+
+        node.set_parameter("speed", 2.5)?;
+        assert_eq!(node.get_parameter::<f64>("speed")?, 2.5);
+
+        node.undeclare_parameter("speed")?;
+        assert!(!node.has_parameter("speed"));
+        assert_eq!(
+            node.get_parameter::<f64>("speed"),
+            Err(RclrsError::ParameterError(ParameterError::NotDeclared {
+                name: String::from("speed")
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_overrides_win_over_defaults() -> Result<(), RclrsError> {
+        let node = node_with_overrides(&["speed:=3.0", "mode:='fast'", "count:=1.0"])?;
+        assert_eq!(
+            node.declare_parameter("speed", 1.5)?,
+            ParameterValue::Double(3.0)
+        );
+        assert_eq!(node.declare_parameter("mode", "slow")?, "fast".into());
+        // An override of another type is an error
+        assert_eq!(
+            node.declare_parameter("count", 1i64),
+            Err(RclrsError::ParameterError(ParameterError::TypeMismatch {
+                name: String::from("count"),
+                expected: ParameterKind::Integer,
+                actual: ParameterKind::Double,
+            }))
+        );
+        // Overrides for parameters that are not declared are not visible
+        assert!(!node.has_parameter("count"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_undeclared_parameters() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Node::builder(&context, "undeclared_parameters_node")
+            .allow_undeclared_parameters(true)
+            .build()?;
+        node.set_parameter("speed", 1.5)?;
+        assert_eq!(node.get_parameter::<f64>("speed")?, 1.5);
+        assert!(node.describe_parameter("speed")?.dynamic_typing);
+        node.set_parameter("speed", "fast")?;
+        assert_eq!(node.get_parameter::<String>("speed")?, "fast");
+        // Undeclaring still requires the parameter to be declared
+        node.undeclare_parameter("speed")?;
+        assert!(node.undeclare_parameter("speed").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_declare_parameters_from_overrides() -> Result<(), RclrsError> {
+        let args = [
+            "--ros-args",
+            "-p",
+            "speed:=3.0",
+            "-p",
+            "camera.name:='front'",
+        ];
+        let context = Context::new(args.map(String::from))?;
+        let node = Node::builder(&context, "declare_from_overrides_node")
+            .automatically_declare_parameters_from_overrides(true)
+            .build()?;
+        assert_eq!(node.get_parameter::<f64>("speed")?, 3.0);
+        assert_eq!(node.get_parameter::<String>("camera.name")?, "front");
+        // The parameters are declared with a fixed type
+        assert!(node.set_parameter("speed", 1i64).is_err());
+        assert_eq!(
+            node.declare_parameter("speed", 1.0),
+            Err(RclrsError::ParameterError(
+                ParameterError::AlreadyDeclared {
+                    name: String::from("speed")
+                }
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameter_errors() -> Result<(), RclrsError> {
+        let node = node_with_overrides(&[])?;
+        node.declare_parameter("flag", true)?;
+        assert_eq!(
+            node.declare_parameter("flag", false),
+            Err(RclrsError::ParameterError(
+                ParameterError::AlreadyDeclared {
+                    name: String::from("flag")
+                }
+            ))
+        );
+        assert_eq!(
+            node.get_parameter::<String>("flag"),
+            Err(RclrsError::ParameterError(ParameterError::TypeMismatch {
+                name: String::from("flag"),
+                expected: ParameterKind::String,
+                actual: ParameterKind::Bool,
+            }))
+        );
+        assert_eq!(
+            node.set_parameter("flag", 1i64),
+            Err(RclrsError::ParameterError(ParameterError::TypeMismatch {
+                name: String::from("flag"),
+                expected: ParameterKind::Bool,
+                actual: ParameterKind::Integer,
+            }))
+        );
+        assert!(node.get_parameter::<bool>("flag")?);
+        assert_eq!(
+            node.set_parameter("missing", 1i64),
+            Err(RclrsError::ParameterError(ParameterError::NotDeclared {
+                name: String::from("missing")
+            }))
+        );
+        // A batch that changes the same parameter twice is not applied at all
+        assert_eq!(
+            node.parameters.set_atomically(vec![
+                Parameter {
+                    name: String::from("flag"),
+                    value: None,
+                },
+                Parameter {
+                    name: String::from("flag"),
+                    value: Some(ParameterValue::Bool(false)),
+                },
+            ]),
+            Err(ParameterError::ChangedTwice {
+                name: String::from("flag")
+            })
+        );
+        assert!(node.get_parameter::<bool>("flag")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameter_descriptors() -> Result<(), RclrsError> {
+        let node = node_with_overrides(&["ratio:=2.0", "mode:=3"])?;
+        let range = ParameterRange::FloatingPoint(FloatingPointRange {
+            from_value: 0.0,
+            to_value: 1.0,
+            step: 0.25,
+        });
+        let descriptor = ParameterDescriptor {
+            range: Some(range.clone()),
+            ..Default::default()
+        };
+        // The override is outside of the range
+        assert_eq!(
+            node.declare_parameter_with_descriptor("ratio", 0.5, descriptor.clone()),
+            Err(RclrsError::ParameterError(ParameterError::OutOfRange {
+                name: String::from("ratio")
+            }))
+        );
+        node.declare_parameter_with_descriptor("other_ratio", 0.5, descriptor.clone())?;
+        assert_eq!(node.describe_parameter("other_ratio")?, descriptor);
+        node.set_parameter("other_ratio", 0.75)?;
+        assert_eq!(
+            node.set_parameter("other_ratio", 0.8),
+            Err(RclrsError::ParameterError(ParameterError::OutOfRange {
+                name: String::from("other_ratio")
+            }))
+        );
+        assert_eq!(node.get_parameter::<f64>("other_ratio")?, 0.75);
+
+        let read_only = ParameterDescriptor {
+            read_only: true,
+            ..Default::default()
+        };
+        node.declare_parameter_with_descriptor("fixed", 1i64, read_only)?;
+        let read_only_error = Err(RclrsError::ParameterError(ParameterError::ReadOnly {
+            name: String::from("fixed"),
+        }));
+        assert_eq!(node.set_parameter("fixed", 2i64), read_only_error);
+        assert_eq!(node.undeclare_parameter("fixed"), read_only_error);
+
+        // With dynamic typing, the override may have another type than the default
+        let dynamic_typing = ParameterDescriptor {
+            dynamic_typing: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            node.declare_parameter_with_descriptor("mode", "slow", dynamic_typing)?,
+            ParameterValue::Integer(3)
+        );
+        node.set_parameter("mode", "fast")?;
+        assert_eq!(node.get_parameter::<String>("mode")?, "fast");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameter_callbacks() -> Result<(), RclrsError> {
+        let node = node_with_overrides(&[])?;
+        node.declare_parameter("speed", 1.0)?;
+        node.declare_parameter("limit", 5.0)?;
+        let post_set_changes = Arc::new(Mutex::new(Vec::new()));
+
+        // Changing the limit also lowers the speed if necessary
+        let _pre_set = node.add_pre_set_parameters_callback(|changes| {
+            if let Some(Parameter {
+                value: Some(ParameterValue::Double(limit)),
+                ..
+            }) = changes
+                .iter()
+                .find(|change| change.name == "limit")
+                .cloned()
+            {
+                changes.push(Parameter {
+                    name: String::from("speed"),
+                    value: Some(ParameterValue::Double(limit)),
+                });
+            }
+        });
+        let _on_set = node.add_on_set_parameters_callback(|changes| {
+            if changes
+                .iter()
+                .any(|change| change.value == Some(0.0.into()))
+            {
+                Err(String::from("Zero is not allowed"))
+            } else {
+                Ok(())
+            }
+        });
+        let post_set_changes_clone = Arc::clone(&post_set_changes);
+        let post_set = node.add_post_set_parameters_callback(move |changes| {
+            post_set_changes_clone
+                .lock()
+                .unwrap()
+                .extend(changes.iter().cloned());
+        });
+
+        node.set_parameter("limit", 2.0)?;
+        assert_eq!(node.get_parameter::<f64>("speed")?, 2.0);
+        assert_eq!(post_set_changes.lock().unwrap().len(), 2);
+
+        // A rejected batch is not applied at all
+        assert_eq!(
+            node.set_parameter("limit", 0.0),
+            Err(RclrsError::ParameterError(ParameterError::Rejected {
+                reason: String::from("Zero is not allowed")
+            }))
+        );
+        assert_eq!(node.get_parameter::<f64>("limit")?, 2.0);
+        assert_eq!(node.get_parameter::<f64>("speed")?, 2.0);
+        assert_eq!(post_set_changes.lock().unwrap().len(), 2);
+
+        // Dropping the handle removes the callback
+        drop(post_set);
+        node.set_parameter("speed", 1.5)?;
+        assert_eq!(post_set_changes.lock().unwrap().len(), 2);
+        Ok(())
+    }
+}
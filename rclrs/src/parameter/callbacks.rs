@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::ParameterValue;
+
+/// A change of a parameter, as passed to parameter callbacks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    /// The name of the parameter.
+    pub name: String,
+    /// The new value of the parameter, or `None` if the parameter is being undeclared.
+    pub value: Option<ParameterValue>,
+}
+
+pub(crate) type PreSetParametersCallback = dyn Fn(&mut Vec<Parameter>) + Send + Sync;
+pub(crate) type OnSetParametersCallback = dyn Fn(&[Parameter]) -> Result<(), String> + Send + Sync;
+pub(crate) type PostSetParametersCallback = dyn Fn(&[Parameter]) + Send + Sync;
+
+/// Registered callbacks of one type, by registration ID.
+pub(crate) struct CallbackRegistry<F: ?Sized> {
+    next_id: u64,
+    callbacks: Vec<(u64, Arc<F>)>,
+}
+
+impl<F: ?Sized> Default for CallbackRegistry<F> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            callbacks: Vec::new(),
+        }
+    }
+}
+
+impl<F: ?Sized + Send + Sync + 'static> CallbackRegistry<F> {
+    /// Adds a callback, which is removed again when the returned handle is dropped.
+    pub(crate) fn add(registry: &Arc<Mutex<Self>>, callback: Arc<F>) -> ParameterCallbackHandle {
+        let mut guard = registry.lock().unwrap();
+        guard.next_id += 1;
+        let id = guard.next_id;
+        guard.callbacks.push((id, callback));
+        let registry: Weak<Mutex<Self>> = Arc::downgrade(registry);
+        ParameterCallbackHandle { id, registry }
+    }
+
+    /// Returns the registered callbacks in the order they were added.
+    ///
+    /// The callbacks are cloned out of the registry, so that calling them after releasing the
+    /// lock allows them to add or remove callbacks without deadlocking.
+    pub(crate) fn callbacks(&self) -> Vec<Arc<F>> {
+        self.callbacks
+            .iter()
+            .map(|(_, cb)| Arc::clone(cb))
+            .collect()
+    }
+}
+
+// Lets a handle remove its callback without knowing the type of the callback.
+trait RemoveCallback: Send + Sync {
+    fn remove(&self, id: u64);
+}
+
+impl<F: ?Sized + Send + Sync> RemoveCallback for Mutex<CallbackRegistry<F>> {
+    fn remove(&self, id: u64) {
+        self.lock().unwrap().callbacks.retain(|(i, _)| *i != id);
+    }
+}
+
+/// Keeps a callback registered with the parameters of a node, or with a
+/// [`ParameterEventHandler`][1].
+///
+/// The callback is removed when the handle is dropped.
+///
+/// [1]: crate::ParameterEventHandler
+#[must_use = "the callback is removed when the handle is dropped"]
+pub struct ParameterCallbackHandle {
+    id: u64,
+    registry: Weak<dyn RemoveCallback>,
+}
+
+impl Drop for ParameterCallbackHandle {
+    fn drop(&mut self) {
+        if let Some(registry) = self.registry.upgrade() {
+            registry.remove(self.id);
+        }
+    }
+}
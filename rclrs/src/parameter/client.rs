@@ -0,0 +1,375 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::vendor::rcl_interfaces::msg::{Parameter as ParameterMsg, SetParametersResult};
+use crate::vendor::rcl_interfaces::srv::*;
+use crate::{
+    Client, Node, Parameter, ParameterDescriptor, ParameterError, ParameterKind, ParameterValue,
+    RclrsError, SpinUntilFutureComplete,
+};
+
+// How often wait_for_services() checks whether the services are available.
+const SERVICE_POLL_PERIOD: Duration = Duration::from_millis(10);
+
+/// A client for the parameter services of another node.
+///
+/// This makes it possible to read and change the parameters of other nodes at runtime, like the
+/// `ros2 param` command line tool does.
+///
+/// Each operation is available as an `async` method, whose future completes when the client's
+/// node is spun, and as a blocking method that spins a node or executor until the response
+/// arrives, see [`spin_until_future_complete`][1].
+///
+/// Create a parameters client with [`Node::create_parameters_client()`][2].
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::{Context, RclrsError};
+/// # use std::time::Duration;
+/// let context = Context::new([])?;
+/// let mut node = rclrs::create_node(&context, "orchestrator")?;
+/// let client = node.create_parameters_client("/camera_driver")?;
+/// let timeout = Some(Duration::from_secs(5));
+/// client.wait_for_services(timeout)?;
+/// let values = client.get_parameters(&node, &["exposure"], timeout)?;
+/// println!("Exposure: {:?}", values[0]);
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::spin_until_future_complete
+/// [2]: crate::Node::create_parameters_client
+pub struct ParametersClient {
+    remote_node_name: String,
+    describe_parameters_client: Arc<Client<DescribeParameters>>,
+    get_parameter_types_client: Arc<Client<GetParameterTypes>>,
+    get_parameters_client: Arc<Client<GetParameters>>,
+    list_parameters_client: Arc<Client<ListParameters>>,
+    set_parameters_client: Arc<Client<SetParameters>>,
+    set_parameters_atomically_client: Arc<Client<SetParametersAtomically>>,
+}
+
+impl ParametersClient {
+    pub(crate) fn new(node: &mut Node, remote_node_name: &str) -> Result<Self, RclrsError> {
+        let service_name = |service: &str| format!("{}/{}", remote_node_name, service);
+        Ok(Self {
+            remote_node_name: remote_node_name.to_string(),
+            describe_parameters_client: node.create_client(&service_name("describe_parameters"))?,
+            get_parameter_types_client: node.create_client(&service_name("get_parameter_types"))?,
+            get_parameters_client: node.create_client(&service_name("get_parameters"))?,
+            list_parameters_client: node.create_client(&service_name("list_parameters"))?,
+            set_parameters_client: node.create_client(&service_name("set_parameters"))?,
+            set_parameters_atomically_client: node
+                .create_client(&service_name("set_parameters_atomically"))?,
+        })
+    }
+
+    /// Returns the name of the node whose parameters this client accesses.
+    pub fn remote_node_name(&self) -> &str {
+        &self.remote_node_name
+    }
+
+    /// Checks whether all parameter services of the remote node are available.
+    pub fn services_are_ready(&self) -> Result<bool, RclrsError> {
+        Ok(self.describe_parameters_client.service_is_ready()?
+            && self.get_parameter_types_client.service_is_ready()?
+            && self.get_parameters_client.service_is_ready()?
+            && self.list_parameters_client.service_is_ready()?
+            && self.set_parameters_client.service_is_ready()?
+            && self.set_parameters_atomically_client.service_is_ready()?)
+    }
+
+    /// Waits until all parameter services of the remote node are available.
+    ///
+    /// Returns false if they are still not available after the timeout. A timeout of `None`
+    /// means waiting indefinitely.
+    pub fn wait_for_services(&self, timeout: Option<Duration>) -> Result<bool, RclrsError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if self.services_are_ready()? {
+                return Ok(true);
+            }
+            if let Some(deadline) = deadline {
+                if Instant::now() >= deadline {
+                    return Ok(false);
+                }
+            }
+            std::thread::sleep(SERVICE_POLL_PERIOD);
+        }
+    }
+
+    /// Gets the values of parameters, or `None` for parameters that are not declared.
+    pub async fn get_parameters_async(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<Option<ParameterValue>>, RclrsError> {
+        let request = GetParameters_Request {
+            names: names.iter().map(|name| name.to_string()).collect(),
+        };
+        let response = self.get_parameters_client.call_async(request).await?;
+        Ok(response
+            .values
+            .into_iter()
+            .map(ParameterValue::from_msg)
+            .collect())
+    }
+
+    /// Gets the types of parameters, or `None` for parameters that are not declared.
+    pub async fn get_parameter_types_async(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<Option<ParameterKind>>, RclrsError> {
+        let request = GetParameterTypes_Request {
+            names: names.iter().map(|name| name.to_string()).collect(),
+        };
+        let response = self.get_parameter_types_client.call_async(request).await?;
+        Ok(response
+            .types
+            .into_iter()
+            .map(ParameterKind::from_parameter_type)
+            .collect())
+    }
+
+    /// Gets the descriptors of parameters.
+    ///
+    /// The `dynamic_typing` field of the descriptors is always false, since it is not
+    /// transmitted by the service.
+    pub async fn describe_parameters_async(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<ParameterDescriptor>, RclrsError> {
+        let request = DescribeParameters_Request {
+            names: names.iter().map(|name| name.to_string()).collect(),
+        };
+        let response = self.describe_parameters_client.call_async(request).await?;
+        Ok(response
+            .descriptors
+            .into_iter()
+            .map(ParameterDescriptor::from_msg)
+            .collect())
+    }
+
+    /// Lists the names of the declared parameters.
+    ///
+    /// If `prefixes` is not empty, only parameters with one of these prefixes are listed.
+    /// The `depth` limits the number of name parts below the prefix, where `None` means no
+    /// limit. For instance, with the prefix `"camera"` and a depth of 1, `"camera.exposure"` is
+    /// listed, but not `"camera.resolution.width"`.
+    pub async fn list_parameters_async(
+        &self,
+        prefixes: &[&str],
+        depth: Option<u64>,
+    ) -> Result<Vec<String>, RclrsError> {
+        let request = ListParameters_Request {
+            prefixes: prefixes.iter().map(|prefix| prefix.to_string()).collect(),
+            depth: depth.unwrap_or(ListParameters_Request::DEPTH_RECURSIVE),
+        };
+        let response = self.list_parameters_client.call_async(request).await?;
+        Ok(response.result.names)
+    }
+
+    /// Sets parameters one by one, and returns whether each change was successful.
+    ///
+    /// A parameter whose value is `None` is undeclared. Changes that the remote node refuses
+    /// are returned as [`ParameterError::Rejected`] with the reason given by the remote node.
+    pub async fn set_parameters_async(
+        &self,
+        parameters: Vec<Parameter>,
+    ) -> Result<Vec<Result<(), ParameterError>>, RclrsError> {
+        let request = SetParameters_Request {
+            parameters: parameters.into_iter().map(to_parameter_msg).collect(),
+        };
+        let response = self.set_parameters_client.call_async(request).await?;
+        Ok(response
+            .results
+            .into_iter()
+            .map(from_set_parameters_result)
+            .collect())
+    }
+
+    /// Sets parameters atomically, i.e. either all changes are applied or none of them.
+    ///
+    /// A parameter whose value is `None` is undeclared. If the remote node refuses the changes,
+    /// a [`ParameterError::Rejected`] with the reason given by the remote node is returned.
+    pub async fn set_parameters_atomically_async(
+        &self,
+        parameters: Vec<Parameter>,
+    ) -> Result<Result<(), ParameterError>, RclrsError> {
+        let request = SetParametersAtomically_Request {
+            parameters: parameters.into_iter().map(to_parameter_msg).collect(),
+        };
+        let response = self
+            .set_parameters_atomically_client
+            .call_async(request)
+            .await?;
+        Ok(from_set_parameters_result(response.result))
+    }
+
+    /// Blocking version of [`ParametersClient::get_parameters_async()`].
+    ///
+    /// Spins the given node or executor, which must contain the node of this client, until the
+    /// response has been received or the timeout has elapsed.
+    pub fn get_parameters<S: SpinUntilFutureComplete + ?Sized>(
+        &self,
+        node_or_executor: &S,
+        names: &[&str],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Option<ParameterValue>>, RclrsError> {
+        node_or_executor.spin_until_future_complete(self.get_parameters_async(names), timeout)?
+    }
+
+    /// Blocking version of [`ParametersClient::get_parameter_types_async()`].
+    ///
+    /// See [`ParametersClient::get_parameters()`] for the meaning of the arguments.
+    pub fn get_parameter_types<S: SpinUntilFutureComplete + ?Sized>(
+        &self,
+        node_or_executor: &S,
+        names: &[&str],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Option<ParameterKind>>, RclrsError> {
+        node_or_executor
+            .spin_until_future_complete(self.get_parameter_types_async(names), timeout)?
+    }
+
+    /// Blocking version of [`ParametersClient::describe_parameters_async()`].
+    ///
+    /// See [`ParametersClient::get_parameters()`] for the meaning of the arguments.
+    pub fn describe_parameters<S: SpinUntilFutureComplete + ?Sized>(
+        &self,
+        node_or_executor: &S,
+        names: &[&str],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<ParameterDescriptor>, RclrsError> {
+        node_or_executor
+            .spin_until_future_complete(self.describe_parameters_async(names), timeout)?
+    }
+
+    /// Blocking version of [`ParametersClient::list_parameters_async()`].
+    ///
+    /// See [`ParametersClient::get_parameters()`] for the meaning of the arguments.
+    pub fn list_parameters<S: SpinUntilFutureComplete + ?Sized>(
+        &self,
+        node_or_executor: &S,
+        prefixes: &[&str],
+        depth: Option<u64>,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<String>, RclrsError> {
+        node_or_executor
+            .spin_until_future_complete(self.list_parameters_async(prefixes, depth), timeout)?
+    }
+
+    /// Blocking version of [`ParametersClient::set_parameters_async()`].
+    ///
+    /// See [`ParametersClient::get_parameters()`] for the meaning of the arguments.
+    pub fn set_parameters<S: SpinUntilFutureComplete + ?Sized>(
+        &self,
+        node_or_executor: &S,
+        parameters: Vec<Parameter>,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Result<(), ParameterError>>, RclrsError> {
+        node_or_executor
+            .spin_until_future_complete(self.set_parameters_async(parameters), timeout)?
+    }
+
+    /// Blocking version of [`ParametersClient::set_parameters_atomically_async()`].
+    ///
+    /// See [`ParametersClient::get_parameters()`] for the meaning of the arguments.
+    pub fn set_parameters_atomically<S: SpinUntilFutureComplete + ?Sized>(
+        &self,
+        node_or_executor: &S,
+        parameters: Vec<Parameter>,
+        timeout: Option<Duration>,
+    ) -> Result<Result<(), ParameterError>, RclrsError> {
+        node_or_executor
+            .spin_until_future_complete(self.set_parameters_atomically_async(parameters), timeout)?
+    }
+}
+
+fn to_parameter_msg(parameter: Parameter) -> ParameterMsg {
+    ParameterMsg {
+        name: parameter.name,
+        // A value that is not set undeclares the parameter
+        value: parameter
+            .value
+            .map(|value| value.to_msg())
+            .unwrap_or_default(),
+    }
+}
+
+fn from_set_parameters_result(result: SetParametersResult) -> Result<(), ParameterError> {
+    if result.successful {
+        Ok(())
+    } else {
+        Err(ParameterError::Rejected {
+            reason: result.reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, SingleThreadedExecutor};
+
+    #[test]
+    fn test_parameters_client() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let server_node = Arc::new(Node::new(&context, "parameters_client_server")?);
+        server_node.declare_parameter("camera.exposure", 0.5)?;
+        server_node.declare_parameter("camera.gain", 2i64)?;
+        let mut client_node = Node::new(&context, "parameters_client_node")?;
+        let client = client_node.create_parameters_client("/parameters_client_server")?;
+        let client_node = Arc::new(client_node);
+        let executor = SingleThreadedExecutor::new();
+        executor.add_node(&server_node);
+        executor.add_node(&client_node);
+        let timeout = Some(Duration::from_secs(5));
+        assert!(client.wait_for_services(timeout)?);
+
+        assert_eq!(
+            client.get_parameters(&executor, &["camera.gain", "missing"], timeout)?,
+            [Some(ParameterValue::Integer(2)), None]
+        );
+        assert_eq!(
+            client.get_parameter_types(&executor, &["camera.exposure"], timeout)?,
+            [Some(ParameterKind::Double)]
+        );
+        assert_eq!(
+            client.list_parameters(&executor, &["camera"], None, timeout)?,
+            ["camera.exposure", "camera.gain"]
+        );
+        assert_eq!(
+            client.describe_parameters(&executor, &["camera.gain"], timeout)?,
+            [ParameterDescriptor::default()]
+        );
+
+        let results = client.set_parameters(
+            &executor,
+            vec![
+                Parameter {
+                    name: String::from("camera.gain"),
+                    value: Some(ParameterValue::Integer(3)),
+                },
+                Parameter {
+                    name: String::from("camera.exposure"),
+                    value: Some(ParameterValue::Bool(true)),
+                },
+            ],
+            timeout,
+        )?;
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(ParameterError::Rejected { .. })));
+        assert_eq!(server_node.get_parameter::<i64>("camera.gain")?, 3);
+
+        let result = client.set_parameters_atomically(
+            &executor,
+            vec![Parameter {
+                name: String::from("camera.gain"),
+                value: None,
+            }],
+            timeout,
+        )?;
+        assert!(result.is_ok());
+        assert!(!server_node.has_parameter("camera.gain"));
+        Ok(())
+    }
+}
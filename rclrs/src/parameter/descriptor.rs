@@ -0,0 +1,301 @@
+use crate::vendor::rcl_interfaces::msg::rmw::{
+    FloatingPointRange as FloatingPointRangeMsg, IntegerRange as IntegerRangeMsg,
+};
+use crate::vendor::rcl_interfaces::msg::ParameterDescriptor as ParameterDescriptorMsg;
+use crate::{ParameterError, ParameterKind, ParameterValue};
+
+// The tolerance for floating-point range checks, the same as in rclcpp.
+const FLOAT_TOLERANCE: f64 = f64::EPSILON * 100.0;
+
+/// Describes a parameter, and constrains the values it can take.
+///
+/// The descriptor is given when declaring the parameter with
+/// [`Node::declare_parameter_with_descriptor()`][1], and can not be changed afterwards.
+/// The default descriptor has no constraints except that the type of the parameter is fixed.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, IntegerRange, ParameterDescriptor, ParameterRange, RclrsError};
+/// let context = Context::new([])?;
+/// let node = rclrs::create_node(&context, "my_node")?;
+/// let descriptor = ParameterDescriptor {
+///     description: String::from("The number of retries"),
+///     range: Some(ParameterRange::Integer(IntegerRange {
+///         from_value: 0,
+///         to_value: 10,
+///         step: 2,
+///     })),
+///     ..Default::default()
+/// };
+/// node.declare_parameter_with_descriptor("retries", 4, descriptor)?;
+/// assert!(node.set_parameter("retries", 6).is_ok());
+/// assert!(node.set_parameter("retries", 7).is_err());
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Node::declare_parameter_with_descriptor
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterDescriptor {
+    /// A description of the parameter, e.g. its purpose and unit.
+    pub description: String,
+    /// A plain-text description of constraints that are not expressed by the other fields.
+    ///
+    /// These constraints are not enforced.
+    pub additional_constraints: String,
+    /// If true, the parameter can not be changed after it has been declared.
+    pub read_only: bool,
+    /// If true, the parameter can be set to a value of a different type.
+    pub dynamic_typing: bool,
+    /// The range of values that the parameter can take.
+    ///
+    /// Only an integer range can be given for an integer parameter, and only a floating-point
+    /// range for a double parameter. A range can't be combined with `dynamic_typing`.
+    pub range: Option<ParameterRange>,
+}
+
+/// The range of values that an integer or double parameter can take.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParameterRange {
+    /// A range for a [`ParameterValue::Integer`].
+    Integer(IntegerRange),
+    /// A range for a [`ParameterValue::Double`].
+    FloatingPoint(FloatingPointRange),
+}
+
+/// An inclusive range of integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IntegerRange {
+    /// The smallest allowed value.
+    pub from_value: i64,
+    /// The largest allowed value.
+    pub to_value: i64,
+    /// If not 0, only `from_value`, `to_value` and the values that are a multiple of `step`
+    /// away from `from_value` are allowed.
+    pub step: u64,
+}
+
+/// An inclusive range of floating-point numbers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FloatingPointRange {
+    /// The smallest allowed value.
+    pub from_value: f64,
+    /// The largest allowed value.
+    pub to_value: f64,
+    /// If not 0, only `from_value`, `to_value` and the values that are a multiple of `step`
+    /// away from `from_value` are allowed.
+    ///
+    /// Values are compared with a small tolerance.
+    pub step: f64,
+}
+
+impl IntegerRange {
+    /// Returns true if the value lies in the range, and on a step if there is one.
+    pub fn contains(&self, value: i64) -> bool {
+        if value < self.from_value || value > self.to_value {
+            return false;
+        }
+        // The difference can not overflow because the value is in the range
+        let offset = value.abs_diff(self.from_value);
+        // A step of 0 means that there are no steps
+        value == self.to_value || matches!(offset.checked_rem(self.step), None | Some(0))
+    }
+}
+
+impl FloatingPointRange {
+    /// Returns true if the value lies in the range, and on a step if there is one.
+    pub fn contains(&self, value: f64) -> bool {
+        let near = |a: f64, b: f64| (a - b).abs() <= FLOAT_TOLERANCE;
+        if near(value, self.from_value) || near(value, self.to_value) {
+            return true;
+        }
+        // Written this way so that NaN is rejected
+        if !(value >= self.from_value && value <= self.to_value) {
+            return false;
+        }
+        if self.step == 0.0 {
+            return true;
+        }
+        let steps = ((value - self.from_value) / self.step).round();
+        near(self.from_value + steps * self.step, value)
+    }
+}
+
+impl ParameterDescriptor {
+    /// Checks that the descriptor can be used for a parameter with the given initial value.
+    pub(crate) fn validate_declaration(
+        &self,
+        name: &str,
+        value: &ParameterValue,
+    ) -> Result<(), ParameterError> {
+        let valid_range = match (&self.range, value.kind()) {
+            (None, _) => true,
+            // A range only fits one type
+            (Some(_), _) if self.dynamic_typing => false,
+            (Some(ParameterRange::Integer(range)), ParameterKind::Integer) => {
+                range.from_value <= range.to_value
+            }
+            (Some(ParameterRange::FloatingPoint(range)), ParameterKind::Double) => {
+                range.from_value <= range.to_value && range.step >= 0.0
+            }
+            (Some(_), _) => false,
+        };
+        if !valid_range {
+            return Err(ParameterError::InvalidRange {
+                name: name.to_string(),
+            });
+        }
+        self.validate_value(name, value)
+    }
+
+    /// Checks that the value lies in the range of the parameter, if it has one.
+    pub(crate) fn validate_value(
+        &self,
+        name: &str,
+        value: &ParameterValue,
+    ) -> Result<(), ParameterError> {
+        let in_range = match (&self.range, value) {
+            (Some(ParameterRange::Integer(range)), ParameterValue::Integer(value)) => {
+                range.contains(*value)
+            }
+            (Some(ParameterRange::FloatingPoint(range)), ParameterValue::Double(value)) => {
+                range.contains(*value)
+            }
+            _ => true,
+        };
+        if in_range {
+            Ok(())
+        } else {
+            Err(ParameterError::OutOfRange {
+                name: name.to_string(),
+            })
+        }
+    }
+
+    /// Converts a descriptor message, which has no field for `dynamic_typing`.
+    pub(crate) fn from_msg(msg: ParameterDescriptorMsg) -> Self {
+        let range = if let Some(range) = msg.integer_range.first() {
+            Some(ParameterRange::Integer(IntegerRange {
+                from_value: range.from_value,
+                to_value: range.to_value,
+                step: range.step,
+            }))
+        } else {
+            msg.floating_point_range.first().map(|range| {
+                ParameterRange::FloatingPoint(FloatingPointRange {
+                    from_value: range.from_value,
+                    to_value: range.to_value,
+                    step: range.step,
+                })
+            })
+        };
+        Self {
+            description: msg.description,
+            additional_constraints: msg.additional_constraints,
+            read_only: msg.read_only,
+            dynamic_typing: false,
+            range,
+        }
+    }
+
+    /// Converts the descriptor to a message, which has no field for `dynamic_typing`.
+    pub(crate) fn to_msg(&self, name: String, type_: u8) -> ParameterDescriptorMsg {
+        let mut msg = ParameterDescriptorMsg {
+            name,
+            type_,
+            description: self.description.clone(),
+            additional_constraints: self.additional_constraints.clone(),
+            read_only: self.read_only,
+            ..Default::default()
+        };
+        match &self.range {
+            Some(ParameterRange::Integer(range)) => {
+                msg.integer_range.extend([IntegerRangeMsg {
+                    from_value: range.from_value,
+                    to_value: range.to_value,
+                    step: range.step,
+                }]);
+            }
+            Some(ParameterRange::FloatingPoint(range)) => {
+                msg.floating_point_range.extend([FloatingPointRangeMsg {
+                    from_value: range.from_value,
+                    to_value: range.to_value,
+                    step: range.step,
+                }]);
+            }
+            None => (),
+        }
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer_range() {
+        let range = IntegerRange {
+            from_value: -3,
+            to_value: 8,
+            step: 5,
+        };
+        let allowed: Vec<i64> = (-10..10).filter(|&v| range.contains(v)).collect();
+        assert_eq!(allowed, [-3, 2, 7, 8]);
+
+        let range = IntegerRange {
+            from_value: i64::MIN,
+            to_value: i64::MAX,
+            step: 0,
+        };
+        assert!(range.contains(i64::MIN) && range.contains(i64::MAX));
+    }
+
+    #[test]
+    fn test_floating_point_range() {
+        let range = FloatingPointRange {
+            from_value: 0.0,
+            to_value: 1.0,
+            step: 0.1,
+        };
+        assert!(range.contains(0.0));
+        assert!(range.contains(0.1 + 0.2));
+        assert!(range.contains(1.0));
+        assert!(!range.contains(0.25));
+        assert!(!range.contains(1.1));
+        assert!(!range.contains(-0.1));
+        assert!(!range.contains(f64::NAN));
+    }
+
+    #[test]
+    fn test_descriptor_validation() {
+        let descriptor = ParameterDescriptor {
+            range: Some(ParameterRange::Integer(IntegerRange {
+                from_value: 0,
+                to_value: 10,
+                step: 0,
+            })),
+            ..Default::default()
+        };
+        assert!(descriptor
+            .validate_declaration("a", &ParameterValue::Integer(5))
+            .is_ok());
+        assert_eq!(
+            descriptor.validate_declaration("a", &ParameterValue::Integer(11)),
+            Err(ParameterError::OutOfRange {
+                name: String::from("a")
+            })
+        );
+        assert_eq!(
+            descriptor.validate_declaration("a", &ParameterValue::Double(5.0)),
+            Err(ParameterError::InvalidRange {
+                name: String::from("a")
+            })
+        );
+
+        let msg = descriptor.to_msg(String::from("a"), 2);
+        assert_eq!(msg.integer_range.len(), 1);
+        assert_eq!(msg.integer_range[0].to_value, 10);
+        assert!(msg.floating_point_range.is_empty());
+        assert_eq!(ParameterDescriptor::from_msg(msg), descriptor);
+    }
+}
@@ -0,0 +1,219 @@
+use std::sync::{Arc, Mutex};
+
+use crate::vendor::rcl_interfaces::msg::{
+    Parameter as ParameterMsg, ParameterEvent as ParameterEventMsg,
+};
+use crate::{
+    CallbackRegistry, Node, Parameter, ParameterCallbackHandle, ParameterValue, RclrsError,
+    Subscription, Time, QOS_PROFILE_PARAMETER_EVENTS,
+};
+
+/// A decoded `rcl_interfaces/msg/ParameterEvent` message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterEvent {
+    /// The fully qualified name of the node whose parameters changed.
+    pub node: String,
+    /// When the parameters changed, according to the ROS time of that node.
+    pub stamp: Time,
+    /// The parameters that have been declared.
+    pub new_parameters: Vec<Parameter>,
+    /// The parameters that have been changed.
+    pub changed_parameters: Vec<Parameter>,
+    /// The names of the parameters that have been undeclared.
+    pub deleted_parameters: Vec<String>,
+}
+
+impl ParameterEvent {
+    fn from_msg(msg: ParameterEventMsg) -> Self {
+        let to_parameter = |msg: ParameterMsg| Parameter {
+            name: msg.name,
+            value: ParameterValue::from_msg(msg.value),
+        };
+        Self {
+            node: msg.node,
+            stamp: msg.stamp.into(),
+            new_parameters: msg.new_parameters.into_iter().map(to_parameter).collect(),
+            changed_parameters: msg
+                .changed_parameters
+                .into_iter()
+                .map(to_parameter)
+                .collect(),
+            deleted_parameters: msg
+                .deleted_parameters
+                .into_iter()
+                .map(|parameter| parameter.name)
+                .collect(),
+        }
+    }
+
+    /// Returns the new value of a parameter that has been declared or changed in this event.
+    pub fn get_parameter(&self, name: &str) -> Option<&Parameter> {
+        self.new_parameters
+            .iter()
+            .chain(&self.changed_parameters)
+            .find(|parameter| parameter.name == name)
+    }
+}
+
+type EventCallback = dyn Fn(&ParameterEvent) + Send + Sync;
+
+/// Calls callbacks for the parameter changes of other nodes.
+///
+/// The handler subscribes to the `/parameter_events` topic, on which nodes publish when they
+/// declare, change or undeclare parameters. Callbacks can be registered for all events, or for
+/// a single parameter of a single node. The callbacks are called when the node that the
+/// handler was created with is spun.
+///
+/// Create a parameter event handler with [`Node::create_parameter_event_handler()`][1].
+///
+/// # Example
+/// ```no_run
+/// # use rclrs::{Context, RclrsError};
+/// let context = Context::new([])?;
+/// let mut node = rclrs::create_node(&context, "my_node")?;
+/// let handler = node.create_parameter_event_handler()?;
+/// let _handle = handler.add_parameter_callback("exposure", "/camera_driver", |parameter| {
+///     println!("The exposure changed to {:?}", parameter.value);
+/// });
+/// rclrs::spin(&node)?;
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Node::create_parameter_event_handler
+pub struct ParameterEventHandler {
+    namespace: String,
+    callbacks: Arc<Mutex<CallbackRegistry<EventCallback>>>,
+    _subscription: Arc<Subscription<ParameterEventMsg>>,
+}
+
+impl ParameterEventHandler {
+    pub(crate) fn new(node: &mut Node) -> Result<Self, RclrsError> {
+        let callbacks = Arc::new(Mutex::new(CallbackRegistry::<EventCallback>::default()));
+        let callbacks_clone = Arc::clone(&callbacks);
+        let subscription = node.create_subscription(
+            "/parameter_events",
+            QOS_PROFILE_PARAMETER_EVENTS,
+            move |msg: ParameterEventMsg| {
+                let event = ParameterEvent::from_msg(msg);
+                let callbacks = callbacks_clone.lock().unwrap().callbacks();
+                for callback in callbacks {
+                    callback(&event);
+                }
+            },
+        )?;
+        Ok(Self {
+            namespace: node.namespace(),
+            callbacks,
+            _subscription: subscription,
+        })
+    }
+
+    /// Adds a callback that is called for every parameter event.
+    ///
+    /// The callback is removed when the returned handle is dropped.
+    pub fn add_parameter_event_callback<F>(&self, callback: F) -> ParameterCallbackHandle
+    where
+        F: Fn(&ParameterEvent) + 'static + Send + Sync,
+    {
+        CallbackRegistry::add(&self.callbacks, Arc::new(callback))
+    }
+
+    /// Adds a callback that is called when a parameter of a node is declared, changed or
+    /// undeclared.
+    ///
+    /// The value of the parameter passed to the callback is `None` if the parameter has been
+    /// undeclared. The node name can be fully qualified, or relative to the namespace of the
+    /// handler's node.
+    ///
+    /// The callback is removed when the returned handle is dropped.
+    pub fn add_parameter_callback<F>(
+        &self,
+        parameter_name: &str,
+        node_name: &str,
+        callback: F,
+    ) -> ParameterCallbackHandle
+    where
+        F: Fn(&Parameter) + 'static + Send + Sync,
+    {
+        let node_name = if node_name.starts_with('/') {
+            node_name.to_string()
+        } else {
+            format!("{}/{}", self.namespace.trim_end_matches('/'), node_name)
+        };
+        let parameter_name = parameter_name.to_string();
+        self.add_parameter_event_callback(move |event| {
+            if event.node != node_name {
+                return;
+            }
+            for parameter in event.new_parameters.iter().chain(&event.changed_parameters) {
+                if parameter.name == parameter_name {
+                    callback(parameter);
+                }
+            }
+            if event.deleted_parameters.contains(&parameter_name) {
+                callback(&Parameter {
+                    name: parameter_name.clone(),
+                    value: None,
+                });
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{spin_until, wait_for_subscription};
+    use crate::Context;
+
+    #[test]
+    fn test_parameter_event_handler() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut listener = Node::builder(&context, "event_handler_listener")
+            .namespace("/event_handler")
+            .build()?;
+        let handler = listener.create_parameter_event_handler()?;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = Arc::clone(&events);
+        let _event_handle = handler.add_parameter_event_callback(move |event| {
+            if event.node == "/event_handler/event_handler_node" {
+                events_clone.lock().unwrap().push(event.clone());
+            }
+        });
+        let values = Arc::new(Mutex::new(Vec::new()));
+        let values_clone = Arc::clone(&values);
+        // The node name is relative to the namespace of the listener
+        let _parameter_handle =
+            handler.add_parameter_callback("speed", "event_handler_node", move |parameter| {
+                values_clone.lock().unwrap().push(parameter.value.clone());
+            });
+        let node = Node::builder(&context, "event_handler_node")
+            .namespace("/event_handler")
+            .build()?;
+        wait_for_subscription(&node, "/parameter_events", &listener);
+        node.declare_parameter("speed", 1.0)?;
+        node.declare_parameter("other", 1.0)?;
+        node.set_parameter("speed", 2.0)?;
+        node.undeclare_parameter("speed")?;
+        spin_until(&listener, || values.lock().unwrap().len() == 3);
+
+        assert_eq!(
+            *values.lock().unwrap(),
+            [
+                Some(ParameterValue::Double(1.0)),
+                Some(ParameterValue::Double(2.0)),
+                None
+            ]
+        );
+        let events = events.lock().unwrap();
+        let other_event = events
+            .iter()
+            .find(|event| event.get_parameter("other").is_some())
+            .unwrap();
+        assert_eq!(
+            other_event.get_parameter("other").unwrap().value,
+            Some(ParameterValue::Double(1.0))
+        );
+        Ok(())
+    }
+}
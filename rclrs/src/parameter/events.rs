@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+
+use crate::rcl_bindings::*;
+use crate::vendor::rcl_interfaces::msg::{Parameter as ParameterMsg, ParameterEvent};
+use crate::{
+    Clock, Parameter, ParameterValue, Publisher, RclrsError, QOS_PROFILE_PARAMETER_EVENTS,
+};
+
+/// Publishes the changes to the parameters of a node on the `/parameter_events` topic.
+pub(crate) struct ParameterEventPublisher {
+    publisher: Publisher<ParameterEvent>,
+    node_name: String,
+    clock: Clock,
+}
+
+impl ParameterEventPublisher {
+    /// Creates the publisher for the node with the given fully qualified name.
+    ///
+    /// The events are stamped with the time of the given clock.
+    pub(crate) fn new(
+        rcl_node_mtx: &Arc<Mutex<rcl_node_t>>,
+        node_name: String,
+        clock: Clock,
+    ) -> Result<Self, RclrsError> {
+        let publisher = Publisher::new(
+            Arc::clone(rcl_node_mtx),
+            "/parameter_events",
+            QOS_PROFILE_PARAMETER_EVENTS,
+        )?;
+        Ok(Self {
+            publisher,
+            node_name,
+            clock,
+        })
+    }
+
+    pub(crate) fn publish_declared(&self, name: &str, value: &ParameterValue) {
+        let mut event = self.new_event();
+        event.new_parameters.push(ParameterMsg {
+            name: name.to_string(),
+            value: value.to_msg(),
+        });
+        self.publish(event);
+    }
+
+    /// Publishes a batch of changes as a single event.
+    ///
+    /// The parameters that have been declared by the changes are reported as new parameters.
+    pub(crate) fn publish_changes(&self, changes: &[Parameter], declared: &[String]) {
+        let mut event = self.new_event();
+        for Parameter { name, value } in changes {
+            match value {
+                Some(value) => {
+                    let msg = ParameterMsg {
+                        name: name.clone(),
+                        value: value.to_msg(),
+                    };
+                    if declared.contains(name) {
+                        event.new_parameters.push(msg);
+                    } else {
+                        event.changed_parameters.push(msg);
+                    }
+                }
+                None => event.deleted_parameters.push(ParameterMsg {
+                    name: name.clone(),
+                    ..Default::default()
+                }),
+            }
+        }
+        self.publish(event);
+    }
+
+    fn new_event(&self) -> ParameterEvent {
+        ParameterEvent {
+            stamp: self.clock.now().into(),
+            node: self.node_name.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn publish(&self, event: ParameterEvent) {
+        // The parameters have already been changed at this point, so failing to publish the
+        // event should not be reported as a failure to change them.
+        let _ = self.publisher.publish(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{spin_until, wait_for_subscription};
+    use crate::{Context, Node, QOS_PROFILE_PARAMETER_EVENTS};
+
+    #[test]
+    fn test_parameter_events() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Node::new(&context, "parameter_events_node")?;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = Arc::clone(&events);
+        let mut listener = Node::new(&context, "parameter_events_listener")?;
+        let _subscription = listener.create_subscription(
+            "/parameter_events",
+            QOS_PROFILE_PARAMETER_EVENTS,
+            move |event: ParameterEvent| {
+                let mentions_speed = [
+                    &event.new_parameters,
+                    &event.changed_parameters,
+                    &event.deleted_parameters,
+                ]
+                .iter()
+                .any(|parameters| parameters.iter().any(|p| p.name == "speed"));
+                // Other events, e.g. for use_sim_time, may or may not have been received
+                if event.node == "/parameter_events_node" && mentions_speed {
+                    events_clone.lock().unwrap().push(event);
+                }
+            },
+        )?;
+        wait_for_subscription(&node, "/parameter_events", &listener);
+
+        node.declare_parameter("speed", 1.0)?;
+        node.set_parameter("speed", 2.0)?;
+        node.undeclare_parameter("speed")?;
+        spin_until(&listener, || events.lock().unwrap().len() == 3);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].new_parameters[0].name, "speed");
+        assert_eq!(
+            events[1].changed_parameters[0].value,
+            ParameterValue::Double(2.0).to_msg()
+        );
+        assert_eq!(events[2].deleted_parameters[0].name, "speed");
+        Ok(())
+    }
+}
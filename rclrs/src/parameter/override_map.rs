@@ -119,21 +119,33 @@ pub(crate) unsafe fn resolve_parameter_overrides(
     for rcl_arguments in [rcl_global_arguments, rcl_node_arguments] {
         let mut rcl_params = std::ptr::null_mut();
         rcl_arguments_get_param_overrides(rcl_arguments, &mut rcl_params).ok()?;
-        // Check for the /** node first, and later overwrite with the more specific node
-        // parameters, if they exist
-        for name_to_match in ["/**", node_fqn] {
-            for (node_name, node_params) in RclParamsIter::new(rcl_params) {
-                if node_name == name_to_match {
-                    for (param_name, variant) in RclNodeParamsIter::new(node_params) {
-                        let value = ParameterValue::from_rcl_variant(variant);
-                        map.insert(param_name, value);
-                    }
+        insert_node_parameters(&mut map, rcl_params, node_fqn);
+        rcl_yaml_node_struct_fini(rcl_params);
+    }
+    Ok(map)
+}
+
+/// Inserts the parameters for the given node into the map, overwriting existing entries.
+///
+/// This function is unsafe since the rcl_params argument might contain incorrect array sizes or
+/// dangling pointers.
+pub(crate) unsafe fn insert_node_parameters(
+    map: &mut ParameterOverrideMap,
+    rcl_params: *const rcl_params_t,
+    node_fqn: &str,
+) {
+    // Check for the /** node first, and later overwrite with the more specific node
+    // parameters, if they exist
+    for name_to_match in ["/**", node_fqn] {
+        for (node_name, node_params) in RclParamsIter::new(rcl_params) {
+            if node_name == name_to_match {
+                for (param_name, variant) in RclNodeParamsIter::new(node_params) {
+                    let value = ParameterValue::from_rcl_variant(variant);
+                    map.insert(param_name, value);
                 }
             }
         }
-        rcl_yaml_node_struct_fini(rcl_params);
     }
-    Ok(map)
 }
 
 #[cfg(test)]
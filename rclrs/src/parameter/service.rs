@@ -1,89 +1,429 @@
-use std::sync::{Arc, Weak, Mutex};
-
-use crate::vendor::rcl_interfaces::srv::rmw::*;
-use crate::vendor::rcl_interfaces::msg::rmw::*;
-use rosidl_runtime_rs::{Sequence, seq};
-
-use crate::{rmw_request_id_t, Node, RclrsError, Service, ServiceBase};
-use crate::rcl_bindings::rcl_node_t;
-
-pub struct ParameterService {
-    describe_parameters_service: Arc<Service<DescribeParameters>>,
-    get_parameter_types_service: Arc<Service<GetParameterTypes>>,
-    get_parameters_service: Arc<Service<GetParameters>>,
-    list_parameters_service: Arc<Service<ListParameters>>,
-    set_parameters_service: Arc<Service<SetParameters>>,
-    set_parameters_atomically_service: Arc<Service<SetParametersAtomically>>,
+use std::sync::Arc;
+
+use crate::vendor::rcl_interfaces::msg::{
+    ListParametersResult, ParameterDescriptor as ParameterDescriptorMsg, ParameterType,
+    ParameterValue as ParameterValueMsg, SetParametersResult,
+};
+use crate::vendor::rcl_interfaces::srv::*;
+use crate::{
+    rmw_request_id_t, Node, Parameter, ParameterError, ParameterInterface, ParameterValue,
+    RclrsError, Service,
+};
+
+// The separator between the parts of a parameter name, e.g. in "camera.resolution.width".
+const SEPARATOR: char = '.';
+
+/// The standard services that make the parameters of a node accessible to other nodes, e.g. to
+/// the `ros2 param` command.
+pub(crate) struct ParameterService {
+    _describe_parameters_service: Arc<Service<DescribeParameters>>,
+    _get_parameter_types_service: Arc<Service<GetParameterTypes>>,
+    _get_parameters_service: Arc<Service<GetParameters>>,
+    _list_parameters_service: Arc<Service<ListParameters>>,
+    _set_parameters_service: Arc<Service<SetParameters>>,
+    _set_parameters_atomically_service: Arc<Service<SetParametersAtomically>>,
 }
 
 impl ParameterService {
-    pub fn new(rcl_node_mtx: Arc<Mutex<rcl_node_t>>) -> Result<Self, RclrsError> {
-        let describe_parameters_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "describe_parameters",
-            |req_id: &rmw_request_id_t, req: DescribeParameters_Request| {
-                DescribeParameters_Response {
-                    descriptors: seq![]
-                }
+    /// Creates the services in the node's default callback group.
+    pub(crate) fn new(node: &mut Node) -> Result<Self, RclrsError> {
+        let parameters = Arc::clone(&node.parameters);
+        let describe_parameters_service = node.create_service(
+            "~/describe_parameters",
+            move |_: &rmw_request_id_t, req: DescribeParameters_Request| {
+                describe_parameters(&parameters, req)
             },
         )?;
-        let get_parameter_types_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "get_parameter_types",
-            |req_id: &rmw_request_id_t, req: GetParameterTypes_Request| {
-                GetParameterTypes_Response {
-                    types: seq![]
-                }
+        let parameters = Arc::clone(&node.parameters);
+        let get_parameter_types_service = node.create_service(
+            "~/get_parameter_types",
+            move |_: &rmw_request_id_t, req: GetParameterTypes_Request| {
+                get_parameter_types(&parameters, req)
             },
         )?;
-        let get_parameters_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "get_parameters",
-            |req_id: &rmw_request_id_t, req: GetParameters_Request| {
-                GetParameters_Response {
-                    values: seq![]
-                }
+        let parameters = Arc::clone(&node.parameters);
+        let get_parameters_service = node.create_service(
+            "~/get_parameters",
+            move |_: &rmw_request_id_t, req: GetParameters_Request| {
+                get_parameters(&parameters, req)
             },
         )?;
-        let list_parameters_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "list_parameters",
-            |req_id: &rmw_request_id_t, req: ListParameters_Request| {
-                ListParameters_Response {
-                    result: ListParametersResult::default()
-                }
+        let parameters = Arc::clone(&node.parameters);
+        let list_parameters_service = node.create_service(
+            "~/list_parameters",
+            move |_: &rmw_request_id_t, req: ListParameters_Request| {
+                list_parameters(&parameters, req)
             },
         )?;
-        let set_parameters_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "set_parameters",
-            |req_id: &rmw_request_id_t, req: SetParameters_Request| {
-                SetParameters_Response {
-                    results: seq![]
-                }
+        let parameters = Arc::clone(&node.parameters);
+        let set_parameters_service = node.create_service(
+            "~/set_parameters",
+            move |_: &rmw_request_id_t, req: SetParameters_Request| {
+                set_parameters(&parameters, req)
             },
         )?;
-        let set_parameters_atomically_service = Service::new(Arc::clone(&rcl_node_mtx),
-            "set_parameters_atomically",
-            |req_id: &rmw_request_id_t, req: SetParametersAtomically_Request| {
-                SetParametersAtomically_Response {
-                    result: SetParametersResult::default()
-                }
+        let parameters = Arc::clone(&node.parameters);
+        let set_parameters_atomically_service = node.create_service(
+            "~/set_parameters_atomically",
+            move |_: &rmw_request_id_t, req: SetParametersAtomically_Request| {
+                set_parameters_atomically(&parameters, req)
             },
         )?;
         Ok(Self {
-            describe_parameters_service: Arc::new(describe_parameters_service),
-            get_parameter_types_service: Arc::new(get_parameter_types_service),
-            get_parameters_service: Arc::new(get_parameters_service),
-            list_parameters_service: Arc::new(list_parameters_service),
-            set_parameters_service: Arc::new(set_parameters_service),
-            set_parameters_atomically_service: Arc::new(set_parameters_atomically_service),
+            _describe_parameters_service: describe_parameters_service,
+            _get_parameter_types_service: get_parameter_types_service,
+            _get_parameters_service: get_parameters_service,
+            _list_parameters_service: list_parameters_service,
+            _set_parameters_service: set_parameters_service,
+            _set_parameters_atomically_service: set_parameters_atomically_service,
+        })
+    }
+}
+
+fn describe_parameters(
+    parameters: &ParameterInterface,
+    req: DescribeParameters_Request,
+) -> DescribeParameters_Response {
+    let descriptors = req
+        .names
+        .into_iter()
+        .map(|name| {
+            let type_ = parameter_type(parameters, &name);
+            match parameters.describe(&name) {
+                Ok(descriptor) => descriptor.to_msg(name, type_),
+                Err(_) => ParameterDescriptorMsg {
+                    name,
+                    type_,
+                    ..Default::default()
+                },
+            }
+        })
+        .collect();
+    DescribeParameters_Response { descriptors }
+}
+
+fn get_parameter_types(
+    parameters: &ParameterInterface,
+    req: GetParameterTypes_Request,
+) -> GetParameterTypes_Response {
+    let types = req
+        .names
+        .iter()
+        .map(|name| parameter_type(parameters, name))
+        .collect();
+    GetParameterTypes_Response { types }
+}
+
+fn get_parameters(
+    parameters: &ParameterInterface,
+    req: GetParameters_Request,
+) -> GetParameters_Response {
+    // Parameters that are not declared are reported as not set
+    let values = req
+        .names
+        .iter()
+        .map(|name| match parameters.get(name) {
+            Ok(value) => value.to_msg(),
+            Err(_) => ParameterValueMsg::default(),
+        })
+        .collect();
+    GetParameters_Response { values }
+}
+
+// Has the same semantics as the list_parameters() function in rclcpp.
+fn list_parameters(
+    parameters: &ParameterInterface,
+    req: ListParameters_Request,
+) -> ListParameters_Response {
+    let within_depth = |name: &str| {
+        req.depth == ListParameters_Request::DEPTH_RECURSIVE
+            || (name.matches(SEPARATOR).count() as u64) < req.depth
+    };
+    let mut result = ListParametersResult::default();
+    for name in parameters.names() {
+        let matches = if req.prefixes.is_empty() {
+            within_depth(&name)
+        } else {
+            req.prefixes.iter().any(|prefix| {
+                if name == *prefix {
+                    return true;
+                }
+                match name
+                    .strip_prefix(prefix.as_str())
+                    .and_then(|rest| rest.strip_prefix(SEPARATOR))
+                {
+                    Some(rest) => within_depth(rest),
+                    None => false,
+                }
+            })
+        };
+        if !matches {
+            continue;
+        }
+        if let Some((prefix, _)) = name.rsplit_once(SEPARATOR) {
+            if !result.prefixes.iter().any(|p| p == prefix) {
+                result.prefixes.push(prefix.to_string());
+            }
+        }
+        result.names.push(name);
+    }
+    ListParameters_Response { result }
+}
+
+fn set_parameters(
+    parameters: &ParameterInterface,
+    req: SetParameters_Request,
+) -> SetParameters_Response {
+    let results = req
+        .parameters
+        .into_iter()
+        .map(|parameter| {
+            let parameter = Parameter {
+                name: parameter.name,
+                value: ParameterValue::from_msg(parameter.value),
+            };
+            to_set_parameters_result(parameters.set_atomically(vec![parameter]))
+        })
+        .collect();
+    SetParameters_Response { results }
+}
+
+fn set_parameters_atomically(
+    parameters: &ParameterInterface,
+    req: SetParametersAtomically_Request,
+) -> SetParametersAtomically_Response {
+    // Setting a parameter to a value of type PARAMETER_NOT_SET undeclares it
+    let changes = req
+        .parameters
+        .into_iter()
+        .map(|parameter| Parameter {
+            name: parameter.name,
+            value: ParameterValue::from_msg(parameter.value),
         })
+        .collect();
+    SetParametersAtomically_Response {
+        result: to_set_parameters_result(parameters.set_atomically(changes)),
     }
+}
+
+fn parameter_type(parameters: &ParameterInterface, name: &str) -> u8 {
+    match parameters.get(name) {
+        Ok(value) => value.kind().to_parameter_type(),
+        Err(_) => ParameterType::PARAMETER_NOT_SET,
+    }
+}
+
+fn to_set_parameters_result(result: Result<(), ParameterError>) -> SetParametersResult {
+    let reason = match result {
+        Ok(()) => {
+            return SetParametersResult {
+                successful: true,
+                reason: String::new(),
+            }
+        }
+        // The reason from a callback is passed on as it is
+        Err(ParameterError::Rejected { reason }) => reason,
+        Err(err) => err.to_string(),
+    };
+    SetParametersResult {
+        successful: false,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_helpers::wait_for_service;
+    use crate::vendor::rcl_interfaces::msg::Parameter as ParameterMsg;
+    use crate::{
+        Context, FloatingPointRange, ParameterDescriptor, ParameterOverrideMap, ParameterRange,
+        SingleThreadedExecutor,
+    };
+
+    fn parameters_with(names: &[&str]) -> ParameterInterface {
+        let parameters = ParameterInterface::new(ParameterOverrideMap::new(), false, None);
+        for (i, name) in names.iter().enumerate() {
+            parameters
+                .declare(
+                    name,
+                    ParameterValue::Integer(i as i64),
+                    ParameterDescriptor::default(),
+                )
+                .unwrap();
+        }
+        parameters
+    }
+
+    fn list(parameters: &ParameterInterface, prefixes: &[&str], depth: u64) -> Vec<String> {
+        let req = ListParameters_Request {
+            prefixes: prefixes.iter().map(|p| p.to_string()).collect(),
+            depth,
+        };
+        list_parameters(parameters, req).result.names
+    }
+
+    #[test]
+    fn test_list_parameters() {
+        let parameters = parameters_with(&["a", "b.c", "b.d.e", "bb"]);
+        assert_eq!(list(&parameters, &[], 0), ["a", "b.c", "b.d.e", "bb"]);
+        assert_eq!(list(&parameters, &[], 1), ["a", "bb"]);
+        assert_eq!(list(&parameters, &[], 2), ["a", "b.c", "bb"]);
+        assert_eq!(list(&parameters, &["b"], 0), ["b.c", "b.d.e"]);
+        assert_eq!(list(&parameters, &["b"], 1), ["b.c"]);
+        assert_eq!(list(&parameters, &["bb"], 0), ["bb"]);
+
+        let req = ListParameters_Request {
+            prefixes: vec![],
+            depth: 0,
+        };
+        assert_eq!(
+            list_parameters(&parameters, req).result.prefixes,
+            ["b", "b.d"]
+        );
+    }
+
+    #[test]
+    fn test_get_parameters() {
+        let parameters = parameters_with(&["a", "b"]);
+        let req = GetParameters_Request {
+            names: vec![String::from("b"), String::from("missing")],
+        };
+        let values = get_parameters(&parameters, req).values;
+        assert_eq!(values[0], ParameterValue::Integer(1).to_msg());
+        assert_eq!(values[1].type_, ParameterType::PARAMETER_NOT_SET);
+
+        let req = GetParameterTypes_Request {
+            names: vec![String::from("a"), String::from("missing")],
+        };
+        assert_eq!(
+            get_parameter_types(&parameters, req).types,
+            [
+                ParameterType::PARAMETER_INTEGER,
+                ParameterType::PARAMETER_NOT_SET
+            ]
+        );
+    }
+
+    #[test]
+    fn test_describe_parameters() {
+        let parameters = parameters_with(&["a"]);
+        let descriptor = ParameterDescriptor {
+            description: String::from("A ratio"),
+            read_only: true,
+            range: Some(ParameterRange::FloatingPoint(FloatingPointRange {
+                from_value: 0.0,
+                to_value: 1.0,
+                step: 0.0,
+            })),
+            ..Default::default()
+        };
+        parameters
+            .declare("b", ParameterValue::Double(0.5), descriptor)
+            .unwrap();
+        let req = DescribeParameters_Request {
+            names: vec![String::from("b"), String::from("missing")],
+        };
+        let descriptors = describe_parameters(&parameters, req).descriptors;
+        assert_eq!(descriptors[0].name, "b");
+        assert_eq!(descriptors[0].type_, ParameterType::PARAMETER_DOUBLE);
+        assert_eq!(descriptors[0].description, "A ratio");
+        assert!(descriptors[0].read_only);
+        assert_eq!(descriptors[0].floating_point_range[0].to_value, 1.0);
+        assert_eq!(descriptors[1].type_, ParameterType::PARAMETER_NOT_SET);
+    }
+
+    #[test]
+    fn test_set_parameters() {
+        let parameters = parameters_with(&["a", "b"]);
+        let parameter_msg = |name: &str, value: ParameterValue| ParameterMsg {
+            name: name.to_string(),
+            value: value.to_msg(),
+        };
+        let req = SetParameters_Request {
+            parameters: vec![
+                parameter_msg("a", ParameterValue::Integer(5)),
+                parameter_msg("b", ParameterValue::Bool(true)),
+            ],
+        };
+        let results = set_parameters(&parameters, req).results;
+        assert!(results[0].successful);
+        assert!(!results[1].successful);
+        assert_eq!(parameters.get("a"), Ok(ParameterValue::Integer(5)));
+
+        // Nothing is changed when one of the parameters can't be set
+        let req = SetParametersAtomically_Request {
+            parameters: vec![
+                parameter_msg("a", ParameterValue::Integer(6)),
+                parameter_msg("missing", ParameterValue::Integer(6)),
+            ],
+        };
+        assert!(
+            !set_parameters_atomically(&parameters, req)
+                .result
+                .successful
+        );
+        assert_eq!(parameters.get("a"), Ok(ParameterValue::Integer(5)));
+
+        // A value that is not set undeclares the parameter
+        let req = SetParametersAtomically_Request {
+            parameters: vec![ParameterMsg {
+                name: String::from("b"),
+                value: ParameterValueMsg::default(),
+            }],
+        };
+        assert!(
+            set_parameters_atomically(&parameters, req)
+                .result
+                .successful
+        );
+        assert!(!parameters.has("b"));
+    }
+
+    #[test]
+    fn test_set_parameters_rejected_by_callback() {
+        let parameters = Arc::new(parameters_with(&["a", "b"]));
+        let _handle = parameters.add_on_set_callback(Arc::new(|changes: &[Parameter]| {
+            if changes.iter().any(|change| change.name == "b") {
+                Err(String::from("b is frozen"))
+            } else {
+                Ok(())
+            }
+        }));
+        let req = SetParametersAtomically_Request {
+            parameters: ["a", "b"]
+                .iter()
+                .map(|name| ParameterMsg {
+                    name: name.to_string(),
+                    value: ParameterValue::Integer(7).to_msg(),
+                })
+                .collect(),
+        };
+        let result = set_parameters_atomically(&parameters, req).result;
+        assert!(!result.successful);
+        assert_eq!(result.reason, "b is frozen");
+        assert_eq!(parameters.get("a"), Ok(ParameterValue::Integer(0)));
+    }
+
+    #[test]
+    fn test_list_parameters_through_service() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let server_node = Arc::new(Node::new(&context, "list_parameters_server")?);
+        server_node.declare_parameter("answer", 42i64)?;
+        let mut client_node = Node::new(&context, "list_parameters_client")?;
+        let client = client_node
+            .create_client::<ListParameters>("/list_parameters_server/list_parameters")?;
+        let client_node = Arc::new(client_node);
+        let executor = SingleThreadedExecutor::new();
+        executor.add_node(&server_node);
+        executor.add_node(&client_node);
 
-    pub fn services(&self) -> Vec<Weak<dyn ServiceBase>> {
-        vec![
-            Arc::downgrade(&self.describe_parameters_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.get_parameter_types_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.get_parameters_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.list_parameters_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.set_parameters_service) as Weak<dyn ServiceBase>,
-            Arc::downgrade(&self.set_parameters_atomically_service) as Weak<dyn ServiceBase>,
-        ]
+        wait_for_service(&client);
+        let future = client.call_async(ListParameters_Request::default());
+        let response = executor.spin_until_future_complete(future, Some(Duration::from_secs(5)))?;
+        assert_eq!(response?.result.names, ["answer", "use_sim_time"]);
+        Ok(())
     }
 }
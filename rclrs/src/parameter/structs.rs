@@ -0,0 +1,219 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{Node, Parameter, ParameterCallbackHandle, ParameterValue, RclrsError};
+
+/// Derives [`Parameters`][1] for a struct, available with the `derive` feature.
+///
+/// [1]: trait@Parameters
+#[cfg(feature = "derive")]
+pub use rclrs_derive::Parameters;
+
+/// A struct whose fields are parameters of a node.
+///
+/// This is usually derived with `#[derive(Parameters)]` instead of being implemented by hand,
+/// which requires the `derive` feature. Each field becomes a parameter named like the field,
+/// and can be configured with a `#[param(...)]` attribute: `default = <expr>`,
+/// `description = "..."`, `additional_constraints = "..."`, `read_only`, `dynamic_typing`,
+/// `integer_range(from = .., to = .., step = ..)`, `floating_point_range(...)`, and `nested`
+/// for fields that are themselves parameter structs. Fields without a default take it from
+/// the `Default` implementation of the struct.
+///
+/// The struct is declared with [`Node::declare_parameters()`][1].
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "derive")] {
+/// use rclrs::{Context, Parameters};
+///
+/// #[derive(Default, Parameters)]
+/// struct CameraConfig {
+///     #[param(default = 0.01, floating_point_range(from = 0.0, to = 1.0))]
+///     exposure: f64,
+///     #[param(read_only)]
+///     topic: String,
+/// }
+///
+/// #[derive(Default, Parameters)]
+/// struct Config {
+///     #[param(default = 1.5, description = "The gain of the controller")]
+///     gain: f64,
+///     topics: Vec<String>,
+///     #[param(nested)]
+///     camera: CameraConfig,
+/// }
+///
+/// let context = Context::new([])?;
+/// let node = rclrs::create_node(&context, "my_node")?;
+/// // Declares "controller.gain", "controller.topics", "controller.camera.exposure" and
+/// // "controller.camera.topic"
+/// let config = node.declare_parameters::<Config>("controller")?;
+/// node.set_parameter("controller.camera.exposure", 0.02)?;
+/// assert_eq!(config.lock().camera.exposure, 0.02);
+/// # }
+/// # Ok::<(), rclrs::RclrsError>(())
+/// ```
+///
+/// [1]: crate::Node::declare_parameters
+pub trait Parameters: Sized + Send + 'static {
+    /// Declares a parameter for each field, and returns the struct with their values.
+    ///
+    /// The parameter names are prefixed with the given prefix and a dot, unless the prefix is
+    /// empty. This function must not change parameters.
+    fn declare_parameters(node: &Node, prefix: &str) -> Result<Self, RclrsError>;
+
+    /// Updates the field for a parameter, whose name is relative to the prefix.
+    ///
+    /// Returns false if there is no such field, or it has a different type.
+    fn update_parameter(&mut self, name: &str, value: ParameterValue) -> bool;
+}
+
+/// A [`Parameters`] struct that is kept in sync with the parameters of its node.
+///
+/// Changes to the parameters, e.g. through [`Node::set_parameter()`][1] or the parameter
+/// services, are applied to the struct after they have been validated. Created with
+/// [`Node::declare_parameters()`][2].
+///
+/// [1]: crate::Node::set_parameter
+/// [2]: crate::Node::declare_parameters
+pub struct ParameterStruct<T> {
+    values: Arc<Mutex<T>>,
+    _callback_handle: ParameterCallbackHandle,
+}
+
+impl<T: Parameters> ParameterStruct<T> {
+    pub(crate) fn new(node: &Node, prefix: &str) -> Result<Self, RclrsError> {
+        // No batch of changes can be applied between declaring the parameters and registering
+        // the callback, so no change is missed.
+        let _set_guard = node.parameters.set_mtx.lock().unwrap();
+        let values = Arc::new(Mutex::new(T::declare_parameters(node, prefix)?));
+        let values_clone = Arc::clone(&values);
+        let prefix = if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}.", prefix)
+        };
+        // The struct is updated before the next batch of changes can be applied, so that
+        // concurrent changes are applied to it in the same order as to the parameters.
+        let callback_handle =
+            node.parameters
+                .add_applied_callback(Arc::new(move |changes: &[Parameter]| {
+                    let mut values = values_clone.lock().unwrap();
+                    for Parameter { name, value } in changes {
+                        // Undeclared parameters keep their last value in the struct
+                        if let (Some(name), Some(value)) = (name.strip_prefix(&prefix), value) {
+                            values.update_parameter(name, value.clone());
+                        }
+                    }
+                }));
+        Ok(Self {
+            values,
+            _callback_handle: callback_handle,
+        })
+    }
+}
+
+impl<T> ParameterStruct<T> {
+    /// Locks the struct to access its current values.
+    ///
+    /// Applying a change to the struct waits for the lock, so the parameters must not be changed
+    /// by the thread that holds it.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.values.lock().unwrap()
+    }
+}
+
+impl<T: Clone> ParameterStruct<T> {
+    /// Returns a copy of the current values.
+    pub fn get(&self) -> T {
+        self.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, ParameterVariant};
+
+    // What the derive macro generates, written by hand so that the test does not need the
+    // derive feature
+    #[derive(Clone, Debug, PartialEq)]
+    struct Config {
+        gain: f64,
+        topics: Vec<String>,
+    }
+
+    impl Parameters for Config {
+        fn declare_parameters(node: &Node, prefix: &str) -> Result<Self, RclrsError> {
+            let full_name = |name: &str| format!("{}.{}", prefix, name);
+            node.declare_parameter(&full_name("gain"), 1.0)?;
+            node.declare_parameter(&full_name("topics"), vec![String::from("a")])?;
+            Ok(Self {
+                gain: node.get_parameter(&full_name("gain"))?,
+                topics: node.get_parameter(&full_name("topics"))?,
+            })
+        }
+
+        fn update_parameter(&mut self, name: &str, value: ParameterValue) -> bool {
+            match name {
+                "gain" => f64::from_parameter_value(value).map(|v| self.gain = v),
+                "topics" => Vec::from_parameter_value(value).map(|v| self.topics = v),
+                _ => None,
+            }
+            .is_some()
+        }
+    }
+
+    #[test]
+    fn test_parameter_struct() -> Result<(), RclrsError> {
+        let args = ["--ros-args", "-p", "config.gain:=2.0"].map(String::from);
+        let context = Context::new(args)?;
+        let node = Node::new(&context, "parameter_struct_node")?;
+        let config = node.declare_parameters::<Config>("config")?;
+        assert_eq!(
+            config.get(),
+            Config {
+                gain: 2.0,
+                topics: vec![String::from("a")]
+            }
+        );
+
+        node.set_parameter("config.topics", vec![String::from("b")])?;
+        assert_eq!(config.lock().topics, [String::from("b")]);
+        // Rejected changes are not applied to the struct
+        assert!(node.set_parameter("config.gain", 3i64).is_err());
+        assert_eq!(config.lock().gain, 2.0);
+
+        // The parameters stay declared when the struct is dropped
+        drop(config);
+        node.set_parameter("config.gain", 4.0)?;
+        assert!(node.declare_parameters::<Config>("config").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameter_struct_with_concurrent_changes() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Arc::new(Node::new(&context, "parameter_struct_concurrent_node")?);
+        let config = node.declare_parameters::<Config>("config")?;
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let node = Arc::clone(&node);
+                std::thread::spawn(move || {
+                    for j in 0..50 {
+                        node.set_parameter("config.gain", f64::from(i * 100 + j))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        // The struct has the value of the last change, not of the last callback
+        assert_eq!(
+            config.lock().gain,
+            node.get_parameter::<f64>("config.gain")?
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use std::ffi::CStr;
 
 use crate::rcl_bindings::*;
+use crate::vendor::rcl_interfaces::msg::{ParameterType, ParameterValue as ParameterValueMsg};
 
 /// A parameter value.
 ///
@@ -29,7 +30,8 @@ pub enum ParameterValue {
     String(String),
     /// An array of u8.
     ///
-    /// YAML example: Not possible to specify as YAML.
+    /// YAML example: `[1, 255]`, which is only read as a byte array for a parameter that has
+    /// been declared as one.
     ByteArray(Vec<u8>),
     /// An array of booleans.
     ///
@@ -49,7 +51,192 @@ pub enum ParameterValue {
     StringArray(Vec<String>),
 }
 
+/// The type of a [`ParameterValue`], i.e. which of its variants it is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParameterKind {
+    /// A [`ParameterValue::Bool`].
+    Bool,
+    /// A [`ParameterValue::Integer`].
+    Integer,
+    /// A [`ParameterValue::Double`].
+    Double,
+    /// A [`ParameterValue::String`].
+    String,
+    /// A [`ParameterValue::ByteArray`].
+    ByteArray,
+    /// A [`ParameterValue::BoolArray`].
+    BoolArray,
+    /// A [`ParameterValue::IntegerArray`].
+    IntegerArray,
+    /// A [`ParameterValue::DoubleArray`].
+    DoubleArray,
+    /// A [`ParameterValue::StringArray`].
+    StringArray,
+}
+
+impl ParameterKind {
+    /// Converts the kind to one of the `ParameterType` constants used in messages.
+    pub(crate) fn to_parameter_type(self) -> u8 {
+        match self {
+            ParameterKind::Bool => ParameterType::PARAMETER_BOOL,
+            ParameterKind::Integer => ParameterType::PARAMETER_INTEGER,
+            ParameterKind::Double => ParameterType::PARAMETER_DOUBLE,
+            ParameterKind::String => ParameterType::PARAMETER_STRING,
+            ParameterKind::ByteArray => ParameterType::PARAMETER_BYTE_ARRAY,
+            ParameterKind::BoolArray => ParameterType::PARAMETER_BOOL_ARRAY,
+            ParameterKind::IntegerArray => ParameterType::PARAMETER_INTEGER_ARRAY,
+            ParameterKind::DoubleArray => ParameterType::PARAMETER_DOUBLE_ARRAY,
+            ParameterKind::StringArray => ParameterType::PARAMETER_STRING_ARRAY,
+        }
+    }
+
+    /// Converts one of the `ParameterType` constants to a kind, or returns `None` for
+    /// `PARAMETER_NOT_SET` and invalid values.
+    pub(crate) fn from_parameter_type(type_: u8) -> Option<Self> {
+        Some(match type_ {
+            ParameterType::PARAMETER_BOOL => ParameterKind::Bool,
+            ParameterType::PARAMETER_INTEGER => ParameterKind::Integer,
+            ParameterType::PARAMETER_DOUBLE => ParameterKind::Double,
+            ParameterType::PARAMETER_STRING => ParameterKind::String,
+            ParameterType::PARAMETER_BYTE_ARRAY => ParameterKind::ByteArray,
+            ParameterType::PARAMETER_BOOL_ARRAY => ParameterKind::BoolArray,
+            ParameterType::PARAMETER_INTEGER_ARRAY => ParameterKind::IntegerArray,
+            ParameterType::PARAMETER_DOUBLE_ARRAY => ParameterKind::DoubleArray,
+            ParameterType::PARAMETER_STRING_ARRAY => ParameterKind::StringArray,
+            _ => return None,
+        })
+    }
+}
+
+/// A Rust type that parameter values can be converted to, used by
+/// [`Node::get_parameter()`][1].
+///
+/// This is implemented for the types contained in the [`ParameterValue`] variants, and for
+/// `ParameterValue` itself, which accepts a value of any type.
+///
+/// [1]: crate::Node::get_parameter
+pub trait ParameterVariant: Into<ParameterValue> + Sized {
+    /// Returns the type of the parameter values that can be converted to this type, or `None` if
+    /// values of any type can be converted.
+    fn kind() -> Option<ParameterKind>;
+
+    /// Converts the parameter value, or returns `None` if it has a different type.
+    fn from_parameter_value(value: ParameterValue) -> Option<Self>;
+}
+
+macro_rules! impl_parameter_variant {
+    ($type:ty, $variant:ident) => {
+        impl From<$type> for ParameterValue {
+            fn from(value: $type) -> Self {
+                ParameterValue::$variant(value)
+            }
+        }
+
+        impl ParameterVariant for $type {
+            fn kind() -> Option<ParameterKind> {
+                Some(ParameterKind::$variant)
+            }
+
+            fn from_parameter_value(value: ParameterValue) -> Option<Self> {
+                match value {
+                    ParameterValue::$variant(value) => Some(value),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_parameter_variant!(bool, Bool);
+impl_parameter_variant!(i64, Integer);
+impl_parameter_variant!(f64, Double);
+impl_parameter_variant!(String, String);
+impl_parameter_variant!(Vec<u8>, ByteArray);
+impl_parameter_variant!(Vec<bool>, BoolArray);
+impl_parameter_variant!(Vec<i64>, IntegerArray);
+impl_parameter_variant!(Vec<f64>, DoubleArray);
+impl_parameter_variant!(Vec<String>, StringArray);
+
+impl From<&str> for ParameterValue {
+    fn from(value: &str) -> Self {
+        ParameterValue::String(value.to_string())
+    }
+}
+
+impl ParameterVariant for ParameterValue {
+    fn kind() -> Option<ParameterKind> {
+        None
+    }
+
+    fn from_parameter_value(value: ParameterValue) -> Option<Self> {
+        Some(value)
+    }
+}
+
 impl ParameterValue {
+    /// Returns the type of the value.
+    pub fn kind(&self) -> ParameterKind {
+        match self {
+            ParameterValue::Bool(_) => ParameterKind::Bool,
+            ParameterValue::Integer(_) => ParameterKind::Integer,
+            ParameterValue::Double(_) => ParameterKind::Double,
+            ParameterValue::String(_) => ParameterKind::String,
+            ParameterValue::ByteArray(_) => ParameterKind::ByteArray,
+            ParameterValue::BoolArray(_) => ParameterKind::BoolArray,
+            ParameterValue::IntegerArray(_) => ParameterKind::IntegerArray,
+            ParameterValue::DoubleArray(_) => ParameterKind::DoubleArray,
+            ParameterValue::StringArray(_) => ParameterKind::StringArray,
+        }
+    }
+
+    /// Converts the value to a message.
+    pub(crate) fn to_msg(&self) -> ParameterValueMsg {
+        let mut msg = ParameterValueMsg {
+            type_: self.kind().to_parameter_type(),
+            ..Default::default()
+        };
+        match self {
+            ParameterValue::Bool(value) => msg.bool_value = *value,
+            ParameterValue::Integer(value) => msg.integer_value = *value,
+            ParameterValue::Double(value) => msg.double_value = *value,
+            ParameterValue::String(value) => msg.string_value = value.clone(),
+            ParameterValue::ByteArray(values) => msg.byte_array_value = values.clone(),
+            ParameterValue::BoolArray(values) => msg.bool_array_value = values.clone(),
+            ParameterValue::IntegerArray(values) => msg.integer_array_value = values.clone(),
+            ParameterValue::DoubleArray(values) => msg.double_array_value = values.clone(),
+            ParameterValue::StringArray(values) => msg.string_array_value = values.clone(),
+        }
+        msg
+    }
+
+    /// Converts a message to a value.
+    ///
+    /// Returns `None` if the message has the type `PARAMETER_NOT_SET`, or an unknown type.
+    pub(crate) fn from_msg(msg: ParameterValueMsg) -> Option<Self> {
+        match msg.type_ {
+            ParameterType::PARAMETER_BOOL => Some(ParameterValue::Bool(msg.bool_value)),
+            ParameterType::PARAMETER_INTEGER => Some(ParameterValue::Integer(msg.integer_value)),
+            ParameterType::PARAMETER_DOUBLE => Some(ParameterValue::Double(msg.double_value)),
+            ParameterType::PARAMETER_STRING => Some(ParameterValue::String(msg.string_value)),
+            ParameterType::PARAMETER_BYTE_ARRAY => {
+                Some(ParameterValue::ByteArray(msg.byte_array_value))
+            }
+            ParameterType::PARAMETER_BOOL_ARRAY => {
+                Some(ParameterValue::BoolArray(msg.bool_array_value))
+            }
+            ParameterType::PARAMETER_INTEGER_ARRAY => {
+                Some(ParameterValue::IntegerArray(msg.integer_array_value))
+            }
+            ParameterType::PARAMETER_DOUBLE_ARRAY => {
+                Some(ParameterValue::DoubleArray(msg.double_array_value))
+            }
+            ParameterType::PARAMETER_STRING_ARRAY => {
+                Some(ParameterValue::StringArray(msg.string_array_value))
+            }
+            _ => None,
+        }
+    }
+
     // Panics if the rcl_variant_t does not have exactly one field set.
     //
     // This function is unsafe because it is possible to pass in an rcl_variant_t
@@ -122,6 +309,21 @@ impl ParameterValue {
             unreachable!()
         }
     }
+
+    // YAML has no byte arrays, so a byte array parameter is read from YAML as an integer array.
+    // This converts it back if the parameter has the given kind and the integers fit into bytes.
+    pub(crate) fn into_kind_from_yaml(self, kind: ParameterKind) -> Self {
+        match self {
+            ParameterValue::IntegerArray(values) if kind == ParameterKind::ByteArray => {
+                let bytes: Option<Vec<u8>> = values.iter().map(|&v| u8::try_from(v).ok()).collect();
+                match bytes {
+                    Some(bytes) => ParameterValue::ByteArray(bytes),
+                    None => ParameterValue::IntegerArray(values),
+                }
+            }
+            value => value,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -171,4 +373,29 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_parameter_value_msg_round_trip() {
+        let values = [
+            ParameterValue::Bool(true),
+            ParameterValue::Integer(-4),
+            ParameterValue::Double(0.5),
+            ParameterValue::String(String::from("abc")),
+            ParameterValue::ByteArray(vec![1, 2]),
+            ParameterValue::BoolArray(vec![false]),
+            ParameterValue::IntegerArray(vec![3, 4]),
+            ParameterValue::DoubleArray(vec![5.0]),
+            ParameterValue::StringArray(vec![String::from("def")]),
+        ];
+        for value in values {
+            let msg = value.to_msg();
+            assert_eq!(msg.type_, value.kind().to_parameter_type());
+            assert_eq!(
+                ParameterKind::from_parameter_type(msg.type_),
+                Some(value.kind())
+            );
+            assert_eq!(ParameterValue::from_msg(msg), Some(value));
+        }
+        assert_eq!(ParameterValue::from_msg(ParameterValueMsg::default()), None);
+    }
 }
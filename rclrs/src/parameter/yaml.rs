@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::rcl_bindings::*;
+use crate::{
+    insert_node_parameters, to_rclrs_result, ParameterOverrideMap, ParameterValue, RclReturnCode,
+    RclrsError,
+};
+
+/// Reads the parameters for the node with the given fully qualified name from a parameter file.
+///
+/// Like for the `--params-file` argument, the parameters for `/**` are read first, and then
+/// overwritten by those for the node itself.
+pub(crate) fn parameters_from_yaml_file(
+    path: &Path,
+    node_fqn: &str,
+) -> Result<ParameterOverrideMap, RclrsError> {
+    let path = path.to_string_lossy().into_owned();
+    let path_c = CString::new(path.as_str()).map_err(|err| RclrsError::StringContainsNul {
+        err,
+        s: path.clone(),
+    })?;
+    // SAFETY: No preconditions for this function.
+    let rcl_params = unsafe { rcl_yaml_node_struct_init(rcutils_get_default_allocator()) };
+    if rcl_params.is_null() {
+        to_rclrs_result(RclReturnCode::BadAlloc as i32)?;
+    }
+    // SAFETY: The path is a valid C string, and the struct has just been initialized.
+    if !unsafe { rcl_parse_yaml_file(path_c.as_ptr(), rcl_params) } {
+        // The struct has already been finalized by rcl_parse_yaml_file() in this case
+        to_rclrs_result(RclReturnCode::Error as i32)?;
+    }
+    let mut map = ParameterOverrideMap::new();
+    // SAFETY: The struct has been filled in by rcl, and is finalized only afterwards.
+    unsafe {
+        insert_node_parameters(&mut map, rcl_params, node_fqn);
+        rcl_yaml_node_struct_fini(rcl_params);
+    }
+    Ok(map)
+}
+
+/// The parameters below a common prefix, e.g. `camera` for `camera.exposure`.
+#[derive(Default)]
+struct YamlTree<'a> {
+    value: Option<&'a ParameterValue>,
+    children: BTreeMap<&'a str, YamlTree<'a>>,
+}
+
+/// Writes the parameters in the layout of a parameter file.
+///
+/// Parameter names with dots are written as nested maps, like `ros2 param dump` does.
+pub(crate) fn parameters_to_yaml(
+    node_fqn: &str,
+    parameters: &BTreeMap<String, ParameterValue>,
+) -> String {
+    let mut root = YamlTree::default();
+    for (name, value) in parameters {
+        let node = name.split('.').fold(&mut root, |node, segment| {
+            node.children.entry(segment).or_default()
+        });
+        node.value = Some(value);
+    }
+    let mut yaml = format!("{}:\n  ros__parameters:\n", node_fqn);
+    write_tree(&mut yaml, &root, 2);
+    yaml
+}
+
+fn write_tree(yaml: &mut String, tree: &YamlTree, depth: usize) {
+    let indent = "  ".repeat(depth);
+    for (key, child) in &tree.children {
+        match child.value {
+            // A parameter can have the same name as the prefix of other parameters. Since the
+            // key can't be repeated for a nested map, those are written with dotted keys, which
+            // rcl reads the same way.
+            Some(value) => {
+                // Writing to a String can't fail
+                writeln!(yaml, "{}{}: {}", indent, key, value_to_yaml(value)).unwrap();
+                write_flat(yaml, child, &indent, key);
+            }
+            None => {
+                writeln!(yaml, "{}{}:", indent, key).unwrap();
+                write_tree(yaml, child, depth + 1);
+            }
+        }
+    }
+}
+
+fn write_flat(yaml: &mut String, tree: &YamlTree, indent: &str, prefix: &str) {
+    for (key, child) in &tree.children {
+        let name = format!("{}.{}", prefix, key);
+        if let Some(value) = child.value {
+            writeln!(yaml, "{}{}: {}", indent, name, value_to_yaml(value)).unwrap();
+        }
+        write_flat(yaml, child, indent, &name);
+    }
+}
+
+fn value_to_yaml(value: &ParameterValue) -> String {
+    fn list<T>(values: &[T], f: impl Fn(&T) -> String) -> String {
+        let values: Vec<String> = values.iter().map(f).collect();
+        format!("[{}]", values.join(", "))
+    }
+    match value {
+        ParameterValue::Bool(value) => value.to_string(),
+        ParameterValue::Integer(value) => value.to_string(),
+        ParameterValue::Double(value) => double_to_yaml(*value),
+        ParameterValue::String(value) => string_to_yaml(value),
+        // Byte arrays are read back as integer arrays, and converted when they are loaded into
+        // a byte array parameter
+        ParameterValue::ByteArray(values) => list(values, u8::to_string),
+        ParameterValue::BoolArray(values) => list(values, bool::to_string),
+        ParameterValue::IntegerArray(values) => list(values, i64::to_string),
+        ParameterValue::DoubleArray(values) => list(values, |value| double_to_yaml(*value)),
+        ParameterValue::StringArray(values) => list(values, |value| string_to_yaml(value)),
+    }
+}
+
+fn double_to_yaml(value: f64) -> String {
+    if value.is_nan() {
+        String::from(".nan")
+    } else if value.is_infinite() {
+        String::from(if value > 0.0 { ".inf" } else { "-.inf" })
+    } else {
+        // The debug format is precise enough to read back the same value, and always has a
+        // decimal point or an exponent. A decimal point and a signed exponent are added to the
+        // latter, e.g. 1.0e+20 instead of 1e20, which is what YAML requires for a float.
+        let debug = format!("{:?}", value);
+        match debug.split_once('e') {
+            Some((mantissa, exponent)) => {
+                let point = if mantissa.contains('.') { "" } else { ".0" };
+                let sign = if exponent.starts_with('-') { "" } else { "+" };
+                format!("{}{}e{}{}", mantissa, point, sign, exponent)
+            }
+            None => debug,
+        }
+    }
+}
+
+fn string_to_yaml(value: &str) -> String {
+    // Quoted strings are always read as strings, even if they look like a number or boolean
+    let mut yaml = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => yaml.push_str("\\\""),
+            '\\' => yaml.push_str("\\\\"),
+            '\n' => yaml.push_str("\\n"),
+            '\r' => yaml.push_str("\\r"),
+            '\t' => yaml.push_str("\\t"),
+            c if c.is_control() => write!(yaml, "\\u{:04x}", c as u32).unwrap(),
+            c => yaml.push(c),
+        }
+    }
+    yaml.push('"');
+    yaml
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+    use crate::{Context, Node, ParameterDescriptor};
+
+    fn test_values() -> Vec<(&'static str, ParameterValue)> {
+        vec![
+            ("bool", ParameterValue::Bool(true)),
+            ("integer", ParameterValue::Integer(-3)),
+            ("double", ParameterValue::Double(0.1)),
+            ("nested.double", ParameterValue::Double(f64::INFINITY)),
+            ("nested.string", "1.0 \"quoted\"\n".into()),
+            ("nested.string.length", ParameterValue::Integer(12)),
+            ("bytes", ParameterValue::ByteArray(vec![0, 7, 255])),
+            ("bools", ParameterValue::BoolArray(vec![false, true])),
+            ("integers", ParameterValue::IntegerArray(vec![i64::MIN, 0])),
+            ("doubles", ParameterValue::DoubleArray(vec![1e20, -2.5])),
+            (
+                "strings",
+                ParameterValue::StringArray(vec!["true".into(), "".into()]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_dump_and_load_parameters() -> Result<(), Box<dyn std::error::Error>> {
+        let context = Context::new([])?;
+        let node = Node::new(&context, "yaml_node")?;
+        for (name, value) in test_values() {
+            node.declare_parameter(name, value)?;
+        }
+        let read_only = ParameterDescriptor {
+            read_only: true,
+            ..Default::default()
+        };
+        node.declare_parameter_with_descriptor("read_only", 1i64, read_only)?;
+        let mut file = NamedTempFile::new()?;
+        write!(file, "{}", node.dump_parameters())?;
+
+        node.set_parameter("bytes", ParameterValue::ByteArray(vec![1]))?;
+        node.set_parameter("nested.string", "changed")?;
+        node.load_parameters_from_file(file.path())?;
+        for (name, value) in test_values() {
+            assert_eq!(node.get_parameter::<ParameterValue>(name)?, value);
+        }
+
+        let mut file = NamedTempFile::new()?;
+        write!(
+            file,
+            "/**:\n  ros__parameters:\n    integer: 5\n    bool: 1\n"
+        )?;
+        // Nothing is changed if one of the values is invalid
+        assert!(node.load_parameters_from_file(file.path()).is_err());
+        assert_eq!(node.get_parameter::<i64>("integer")?, -3);
+        assert!(node.load_parameters_from_file("/nonexistent.yaml").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameters_to_yaml() {
+        let parameters = BTreeMap::from([
+            (String::from("camera"), ParameterValue::Bool(true)),
+            (String::from("camera.exposure"), ParameterValue::Double(1.0)),
+            (String::from("camera.name"), "say \"hi\"\n".into()),
+            (String::from("camera.lens.zoom"), ParameterValue::Integer(2)),
+            (String::from("camera_id"), ParameterValue::Integer(3)),
+            (String::from("filter.alpha"), ParameterValue::Double(2.5e-3)),
+            (
+                String::from("gains"),
+                ParameterValue::DoubleArray(vec![1e-9, 1e20, 1.5e300, f64::NEG_INFINITY]),
+            ),
+            (
+                String::from("mask"),
+                ParameterValue::ByteArray(vec![0, 255]),
+            ),
+        ]);
+        assert_eq!(
+            parameters_to_yaml("/ns/node", &parameters),
+            r#"/ns/node:
+  ros__parameters:
+    camera: true
+    camera.exposure: 1.0
+    camera.lens.zoom: 2
+    camera.name: "say \"hi\"\n"
+    camera_id: 3
+    filter:
+      alpha: 0.0025
+    gains: [1.0e-9, 1.0e+20, 1.5e+300, -.inf]
+    mask: [0, 255]
+"#
+        );
+    }
+}
@@ -352,6 +352,23 @@ pub const QOS_PROFILE_PARAMETER_EVENTS: QoSProfile = QoSProfile {
     avoid_ros_namespace_conventions: false,
 };
 
+/// Equivalent to `ClockQoS` from the [`rclcpp` package][1].
+///
+/// This is the QoS profile used for the `/clock` topic: only the most recent time is of
+/// interest, and it is published too frequently for retransmissions to be useful.
+///
+/// [1]: https://github.com/ros2/rclcpp/blob/master/rclcpp/include/rclcpp/qos.hpp
+pub const QOS_PROFILE_CLOCK: QoSProfile = QoSProfile {
+    history: QoSHistoryPolicy::KeepLast { depth: 1 },
+    reliability: QoSReliabilityPolicy::BestEffort,
+    durability: QoSDurabilityPolicy::Volatile,
+    deadline: QoSDuration::SystemDefault,
+    lifespan: QoSDuration::SystemDefault,
+    liveliness: QoSLivelinessPolicy::SystemDefault,
+    liveliness_lease_duration: QoSDuration::SystemDefault,
+    avoid_ros_namespace_conventions: false,
+};
+
 /// Equivalent to `rmw_qos_profile_system_default` from the [`rmw` package][1].
 ///
 /// [1]: https://github.com/ros2/rmw/blob/master/rmw/include/rmw/qos_profiles.h
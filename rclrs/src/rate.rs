@@ -0,0 +1,212 @@
+use crate::{Clock, ClockType, Context, Duration, RclrsError, Time};
+
+/// A helper for running a loop at a fixed frequency, measured by a [`Clock`].
+///
+/// [`Rate::sleep()`] sleeps for the remainder of the period, so that the duration of the loop
+/// body is compensated for and the loop does not drift.
+///
+/// A rate created with [`Node::create_rate()`][1] uses the node's clock, and therefore follows
+/// simulated time when `use_sim_time` is enabled. In that case, the node has to be spun in
+/// another thread, so that the time is updated while the rate is sleeping.
+/// For a rate that always uses the wall-clock time, see [`WallRate`].
+///
+/// Sleeping is interrupted when the context is shut down.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, RclrsError};
+/// # use std::time::Duration;
+/// let context = Context::new([])?;
+/// let node = rclrs::create_node(&context, "rate_node")?;
+/// // Loop at 100 Hz
+/// let mut rate = node.create_rate(Duration::from_millis(10));
+/// for _ in 0..3 {
+///     // Do work here
+///     if !rate.sleep()? {
+///         // The work took longer than the period
+///     }
+/// }
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::Node::create_rate
+pub struct Rate {
+    context: Context,
+    clock: Clock,
+    period: Duration,
+    last_interval: Time,
+}
+
+impl Rate {
+    /// Creates a rate with the given period, measured by the given clock.
+    ///
+    /// The first period starts immediately.
+    pub fn new(context: &Context, clock: Clock, period: std::time::Duration) -> Self {
+        let last_interval = clock.now();
+        Self {
+            context: context.clone(),
+            clock,
+            period: Duration::from(period),
+            last_interval,
+        }
+    }
+
+    /// Sleeps for the remainder of the current period.
+    ///
+    /// Returns `false` without sleeping if the period was overrun, i.e. if the time since the last
+    /// call already exceeds the period. If the loop fell behind by more than one full period, the
+    /// next period starts now, instead of trying to catch up.
+    ///
+    /// If the time of the clock jumped backwards, the next period also starts now.
+    ///
+    /// Returns [`RclrsError::ContextShutDown`] if the context is shut down while sleeping.
+    pub fn sleep(&mut self) -> Result<bool, RclrsError> {
+        let now = self.clock.now();
+        let mut next_interval = self.last_interval + self.period;
+        if now < self.last_interval {
+            next_interval = now + self.period;
+        }
+        self.last_interval = next_interval;
+        if next_interval <= now {
+            if now > next_interval + self.period {
+                self.last_interval = now;
+            }
+            return Ok(false);
+        }
+        self.clock.sleep_until(next_interval, &self.context)?;
+        Ok(true)
+    }
+
+    /// Restarts the current period at the current time.
+    pub fn reset(&mut self) {
+        self.last_interval = self.clock.now();
+    }
+
+    /// Returns the period of the rate.
+    pub fn period(&self) -> std::time::Duration {
+        // The period was created from a std::time::Duration, so it is never negative
+        self.period.to_std().unwrap_or_default()
+    }
+
+    /// Returns the clock that the rate uses to measure its period.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+}
+
+/// A [`Rate`] that is measured by a steady clock, and thus unaffected by ROS time.
+pub struct WallRate {
+    rate: Rate,
+}
+
+impl WallRate {
+    /// Creates a rate with the given period, measured by a steady clock.
+    ///
+    /// The first period starts immediately.
+    pub fn new(context: &Context, period: std::time::Duration) -> Result<Self, RclrsError> {
+        Ok(Self {
+            rate: Rate::new(context, Clock::new(ClockType::SteadyTime)?, period),
+        })
+    }
+
+    /// Sleeps for the remainder of the current period.
+    ///
+    /// See [`Rate::sleep()`].
+    pub fn sleep(&mut self) -> Result<bool, RclrsError> {
+        self.rate.sleep()
+    }
+
+    /// Restarts the current period at the current time.
+    pub fn reset(&mut self) {
+        self.rate.reset()
+    }
+
+    /// Returns the period of the rate.
+    pub fn period(&self) -> std::time::Duration {
+        self.rate.period()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{Context, Node};
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn rate_is_send_and_sync() {
+        assert_send::<Rate>();
+        assert_sync::<Rate>();
+        assert_send::<WallRate>();
+        assert_sync::<WallRate>();
+    }
+
+    #[test]
+    fn test_wall_rate_compensates_for_work() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let period = std::time::Duration::from_millis(20);
+        let mut rate = WallRate::new(&context, period)?;
+        assert_eq!(rate.period(), period);
+        let start = Instant::now();
+        for _ in 0..3 {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            assert!(rate.sleep()?);
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= 3 * period);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_overrun_and_reset() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let clock = Clock::new(ClockType::RosTime)?;
+        clock.set_ros_time_override_enabled(true)?;
+        let time = |sec| Time::new(sec, 0, ClockType::RosTime);
+        clock.set_ros_time_override(time(100))?;
+        let mut rate = Rate::new(&context, clock.clone(), std::time::Duration::from_secs(10));
+
+        // The period was overrun by less than a period, so the loop can still catch up
+        clock.set_ros_time_override(time(115))?;
+        assert!(!rate.sleep()?);
+        assert_eq!(rate.last_interval, time(110));
+        // The loop fell behind by more than a period, so it starts over
+        clock.set_ros_time_override(time(135))?;
+        assert!(!rate.sleep()?);
+        assert_eq!(rate.last_interval, time(135));
+
+        clock.set_ros_time_override(time(140))?;
+        rate.reset();
+        assert_eq!(rate.last_interval, time(140));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_follows_sim_time() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Node::builder(&context, "test_rate_follows_sim_time")
+            .arguments(["--ros-args", "-p", "use_sim_time:=true"].map(String::from))
+            .build()?;
+        let clock = node.get_clock();
+        clock.set_ros_time_override(Time::new(100, 0, ClockType::RosTime))?;
+        let mut rate = node.create_rate(std::time::Duration::from_secs(1));
+
+        let clock_for_thread = clock.clone();
+        let handle = std::thread::spawn(move || {
+            for sec in 101..=103 {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                clock_for_thread
+                    .set_ros_time_override(Time::new(sec, 0, ClockType::RosTime))
+                    .unwrap();
+            }
+        });
+        assert!(rate.sleep()?);
+        assert!(clock.now() >= Time::new(101, 0, ClockType::RosTime));
+        handle.join().unwrap();
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 #include <rcl/graph.h>
+#include <rcl/logging.h>
 #include <rcl/rcl.h>
 #include <rcl_yaml_param_parser/parser.h>
 #include <rcutils/error_handling.h>
@@ -0,0 +1,52 @@
+//! Helpers that are shared by the tests of several modules.
+
+use std::time::{Duration, Instant};
+
+use crate::{spin_once, Client, Node};
+
+// How long the helpers wait before failing the test.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Waits until the publishing node has discovered the subscription of the subscribing node on the
+/// given topic, so that messages which are published afterwards are received.
+pub(crate) fn wait_for_subscription(publishing_node: &Node, topic: &str, subscribing_node: &Node) {
+    let deadline = Instant::now() + TIMEOUT;
+    let (name, namespace) = (subscribing_node.name(), subscribing_node.namespace());
+    while !publishing_node
+        .get_subscriptions_info_by_topic(topic)
+        .unwrap()
+        .iter()
+        .any(|info| info.node_name == name && info.node_namespace == namespace)
+    {
+        assert!(
+            Instant::now() < deadline,
+            "The subscription of {} on {} was not discovered",
+            name,
+            topic
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Waits until the client has discovered its service, so that requests sent afterwards are
+/// received.
+pub(crate) fn wait_for_service<T: rosidl_runtime_rs::Service>(client: &Client<T>) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !client.service_is_ready().unwrap() {
+        assert!(Instant::now() < deadline, "The service was not discovered");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Spins the node until the condition is true.
+pub(crate) fn spin_until(node: &Node, condition: impl Fn() -> bool) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !condition() {
+        assert!(
+            Instant::now() < deadline,
+            "The condition did not become true"
+        );
+        // Timeouts are expected while waiting
+        let _ = spin_once(node, Some(Duration::from_millis(100)));
+    }
+}
@@ -0,0 +1,402 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Neg, Sub};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::vendor::builtin_interfaces;
+use crate::ClockType;
+
+const NANOSECONDS_PER_SECOND: i64 = 1_000_000_000;
+
+/// A point in time, measured by a clock of a certain [`ClockType`].
+///
+/// Times can only be compared and subtracted if they come from the same type of clock. Comparing
+/// times of different clock types yields `None` from `partial_cmp()`, and subtracting them
+/// panics.
+///
+/// Adding or subtracting a [`Duration`] panics if the result overflows, like for
+/// [`std::time::Instant`]. Use [`checked_add()`][1] and [`checked_sub()`][2] to handle this case.
+///
+/// When converting from a `builtin_interfaces` message, the clock type is [`ClockType::RosTime`].
+///
+/// # Example
+/// ```
+/// # use rclrs::{ClockType, Duration, Time};
+/// let time = Time::new(10, 500_000_000, ClockType::RosTime);
+/// let later = time + Duration::from_nanoseconds(500_000_000);
+/// assert_eq!(later.nanoseconds(), 11_000_000_000);
+/// assert_eq!(later - time, Duration::from_nanoseconds(500_000_000));
+/// assert!(time < later);
+/// ```
+///
+/// [1]: Time::checked_add
+/// [2]: Time::checked_sub
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Time {
+    nanoseconds: i64,
+    clock_type: ClockType,
+}
+
+/// A signed span of time, with nanosecond precision.
+///
+/// In contrast to [`std::time::Duration`], this type can be negative, e.g. when it is the
+/// difference between two [`Time`]s. Like for [`Time`], the arithmetic operators panic on
+/// overflow, and there are `checked_*` methods that return `None` instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration {
+    nanoseconds: i64,
+}
+
+impl Time {
+    /// Creates a time from seconds and nanoseconds, like in the `builtin_interfaces/Time` message.
+    pub fn new(sec: i32, nanosec: u32, clock_type: ClockType) -> Self {
+        Self {
+            nanoseconds: i64::from(sec) * NANOSECONDS_PER_SECOND + i64::from(nanosec),
+            clock_type,
+        }
+    }
+
+    /// Creates a time from nanoseconds since the epoch of the clock.
+    pub fn from_nanoseconds(nanoseconds: i64, clock_type: ClockType) -> Self {
+        Self {
+            nanoseconds,
+            clock_type,
+        }
+    }
+
+    /// Returns the nanoseconds since the epoch of the clock.
+    pub fn nanoseconds(&self) -> i64 {
+        self.nanoseconds
+    }
+
+    /// Returns the seconds since the epoch of the clock, as a floating-point number.
+    pub fn seconds(&self) -> f64 {
+        self.nanoseconds as f64 / NANOSECONDS_PER_SECOND as f64
+    }
+
+    /// Returns the type of the clock that this time comes from.
+    pub fn clock_type(&self) -> ClockType {
+        self.clock_type
+    }
+
+    /// Adds a duration, or returns `None` if the result overflows.
+    pub fn checked_add(&self, duration: Duration) -> Option<Self> {
+        self.nanoseconds
+            .checked_add(duration.nanoseconds)
+            .map(|nanoseconds| Self::from_nanoseconds(nanoseconds, self.clock_type))
+    }
+
+    /// Subtracts a duration, or returns `None` if the result overflows.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Self> {
+        self.nanoseconds
+            .checked_sub(duration.nanoseconds)
+            .map(|nanoseconds| Self::from_nanoseconds(nanoseconds, self.clock_type))
+    }
+}
+
+impl PartialOrd for Time {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.clock_type != other.clock_type {
+            return None;
+        }
+        Some(self.nanoseconds.cmp(&other.nanoseconds))
+    }
+}
+
+impl Add<Duration> for Time {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self {
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to time")
+    }
+}
+
+impl Sub<Duration> for Time {
+    type Output = Self;
+
+    fn sub(self, rhs: Duration) -> Self {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting duration from time")
+    }
+}
+
+impl Sub for Time {
+    type Output = Duration;
+
+    fn sub(self, rhs: Self) -> Duration {
+        assert_eq!(
+            self.clock_type, rhs.clock_type,
+            "Cannot subtract times of different clock types"
+        );
+        let nanoseconds = self
+            .nanoseconds
+            .checked_sub(rhs.nanoseconds)
+            .expect("overflow when subtracting times");
+        Duration::from_nanoseconds(nanoseconds)
+    }
+}
+
+/// Converts a `SystemTime` to a [`Time`] of the [`ClockType::SystemTime`] clock.
+impl From<SystemTime> for Time {
+    fn from(system_time: SystemTime) -> Self {
+        let nanoseconds = match system_time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => Duration::from(duration).nanoseconds,
+            Err(err) => -Duration::from(err.duration()).nanoseconds,
+        };
+        Self::from_nanoseconds(nanoseconds, ClockType::SystemTime)
+    }
+}
+
+/// Interprets the [`Time`] as time since the Unix epoch, regardless of its clock type.
+impl From<Time> for SystemTime {
+    fn from(time: Time) -> Self {
+        let duration = std::time::Duration::from_nanos(time.nanoseconds.unsigned_abs());
+        if time.nanoseconds >= 0 {
+            UNIX_EPOCH + duration
+        } else {
+            UNIX_EPOCH - duration
+        }
+    }
+}
+
+impl From<builtin_interfaces::msg::Time> for Time {
+    fn from(msg: builtin_interfaces::msg::Time) -> Self {
+        Self::new(msg.sec, msg.nanosec, ClockType::RosTime)
+    }
+}
+
+impl From<builtin_interfaces::msg::rmw::Time> for Time {
+    fn from(msg: builtin_interfaces::msg::rmw::Time) -> Self {
+        Self::new(msg.sec, msg.nanosec, ClockType::RosTime)
+    }
+}
+
+impl From<Time> for builtin_interfaces::msg::Time {
+    fn from(time: Time) -> Self {
+        let (sec, nanosec) = split_nanoseconds(time.nanoseconds);
+        Self { sec, nanosec }
+    }
+}
+
+impl From<Time> for builtin_interfaces::msg::rmw::Time {
+    fn from(time: Time) -> Self {
+        let (sec, nanosec) = split_nanoseconds(time.nanoseconds);
+        Self { sec, nanosec }
+    }
+}
+
+impl Duration {
+    /// Creates a duration from seconds and nanoseconds, like in the
+    /// `builtin_interfaces/Duration` message.
+    pub fn new(sec: i32, nanosec: u32) -> Self {
+        Self {
+            nanoseconds: i64::from(sec) * NANOSECONDS_PER_SECOND + i64::from(nanosec),
+        }
+    }
+
+    /// Creates a duration from nanoseconds.
+    pub fn from_nanoseconds(nanoseconds: i64) -> Self {
+        Self { nanoseconds }
+    }
+
+    /// Returns the duration in nanoseconds.
+    pub fn nanoseconds(&self) -> i64 {
+        self.nanoseconds
+    }
+
+    /// Returns the duration in seconds, as a floating-point number.
+    pub fn seconds(&self) -> f64 {
+        self.nanoseconds as f64 / NANOSECONDS_PER_SECOND as f64
+    }
+
+    /// Converts the duration to a [`std::time::Duration`], or returns `None` if it is negative.
+    pub fn to_std(&self) -> Option<std::time::Duration> {
+        u64::try_from(self.nanoseconds)
+            .ok()
+            .map(std::time::Duration::from_nanos)
+    }
+
+    /// Adds another duration, or returns `None` if the result overflows.
+    pub fn checked_add(&self, rhs: Self) -> Option<Self> {
+        self.nanoseconds
+            .checked_add(rhs.nanoseconds)
+            .map(Self::from_nanoseconds)
+    }
+
+    /// Subtracts another duration, or returns `None` if the result overflows.
+    pub fn checked_sub(&self, rhs: Self) -> Option<Self> {
+        self.nanoseconds
+            .checked_sub(rhs.nanoseconds)
+            .map(Self::from_nanoseconds)
+    }
+
+    /// Negates the duration, or returns `None` if the result overflows.
+    pub fn checked_neg(&self) -> Option<Self> {
+        self.nanoseconds.checked_neg().map(Self::from_nanoseconds)
+    }
+}
+
+impl Add for Duration {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.checked_add(rhs)
+            .expect("overflow when adding durations")
+    }
+}
+
+impl Sub for Duration {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting durations")
+    }
+}
+
+impl Neg for Duration {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.checked_neg().expect("overflow when negating duration")
+    }
+}
+
+/// Converts a [`std::time::Duration`], saturating at the largest representable duration.
+impl From<std::time::Duration> for Duration {
+    fn from(duration: std::time::Duration) -> Self {
+        Self::from_nanoseconds(i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<builtin_interfaces::msg::Duration> for Duration {
+    fn from(msg: builtin_interfaces::msg::Duration) -> Self {
+        Self::new(msg.sec, msg.nanosec)
+    }
+}
+
+impl From<builtin_interfaces::msg::rmw::Duration> for Duration {
+    fn from(msg: builtin_interfaces::msg::rmw::Duration) -> Self {
+        Self::new(msg.sec, msg.nanosec)
+    }
+}
+
+impl From<Duration> for builtin_interfaces::msg::Duration {
+    fn from(duration: Duration) -> Self {
+        let (sec, nanosec) = split_nanoseconds(duration.nanoseconds);
+        Self { sec, nanosec }
+    }
+}
+
+impl From<Duration> for builtin_interfaces::msg::rmw::Duration {
+    fn from(duration: Duration) -> Self {
+        let (sec, nanosec) = split_nanoseconds(duration.nanoseconds);
+        Self { sec, nanosec }
+    }
+}
+
+// Splits nanoseconds into the seconds and nanoseconds used by builtin_interfaces messages.
+// The nanoseconds are always positive, so e.g. -0.5 s becomes -1 s + 500000000 ns.
+// Like in rclcpp, the seconds are truncated if they do not fit into an i32.
+fn split_nanoseconds(nanoseconds: i64) -> (i32, u32) {
+    let sec = nanoseconds.div_euclid(NANOSECONDS_PER_SECOND);
+    let nanosec = nanoseconds.rem_euclid(NANOSECONDS_PER_SECOND);
+    (sec as i32, nanosec as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_arithmetic() {
+        let time = Time::from_nanoseconds(1_000, ClockType::SteadyTime);
+        let duration = Duration::from_nanoseconds(250);
+        assert_eq!((time + duration).nanoseconds(), 1_250);
+        assert_eq!((time - duration).nanoseconds(), 750);
+        assert_eq!((time - duration).clock_type(), ClockType::SteadyTime);
+        assert_eq!((time - duration) - time, -duration);
+        assert_eq!(duration + duration - duration, duration);
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let time = Time::from_nanoseconds(i64::MAX - 1, ClockType::RosTime);
+        let duration = Duration::from_nanoseconds(1);
+        assert_eq!(
+            time.checked_add(duration),
+            Some(Time::from_nanoseconds(i64::MAX, ClockType::RosTime))
+        );
+        assert_eq!(time.checked_add(duration + duration), None);
+        assert_eq!(time.checked_sub(-duration - duration), None);
+        let min = Duration::from_nanoseconds(i64::MIN);
+        assert_eq!(min.checked_sub(duration), None);
+        assert_eq!(min.checked_neg(), None);
+        assert_eq!(
+            min.checked_add(duration),
+            Some(Duration::from_nanoseconds(i64::MIN + 1))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_time_overflow_panics() {
+        let _ =
+            Time::from_nanoseconds(i64::MAX, ClockType::RosTime) + Duration::from_nanoseconds(1);
+    }
+
+    #[test]
+    fn test_time_comparison() {
+        let ros_time = Time::from_nanoseconds(1_000, ClockType::RosTime);
+        let later_ros_time = Time::from_nanoseconds(2_000, ClockType::RosTime);
+        let system_time = Time::from_nanoseconds(1_000, ClockType::SystemTime);
+        assert!(ros_time < later_ros_time);
+        assert_ne!(ros_time, system_time);
+        assert_eq!(ros_time.partial_cmp(&system_time), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_subtracting_times_of_different_clocks_panics() {
+        let _ = Time::from_nanoseconds(1_000, ClockType::RosTime)
+            - Time::from_nanoseconds(1_000, ClockType::SystemTime);
+    }
+
+    #[test]
+    fn test_time_msg_round_trip() {
+        let time = Time::new(12, 345, ClockType::RosTime);
+        assert_eq!(time.nanoseconds(), 12_000_000_345);
+        let msg = builtin_interfaces::msg::Time::from(time);
+        assert_eq!((msg.sec, msg.nanosec), (12, 345));
+        assert_eq!(Time::from(msg), time);
+        let rmw_msg = builtin_interfaces::msg::rmw::Time::from(time);
+        assert_eq!((rmw_msg.sec, rmw_msg.nanosec), (12, 345));
+        assert_eq!(Time::from(rmw_msg), time);
+    }
+
+    #[test]
+    fn test_negative_duration_msg_round_trip() {
+        let duration = Duration::from_nanoseconds(-500_000_000);
+        let msg = builtin_interfaces::msg::Duration::from(duration);
+        assert_eq!((msg.sec, msg.nanosec), (-1, 500_000_000));
+        assert_eq!(Duration::from(msg), duration);
+        let rmw_msg = builtin_interfaces::msg::rmw::Duration::from(duration);
+        assert_eq!(Duration::from(rmw_msg), duration);
+    }
+
+    #[test]
+    fn test_std_conversions() {
+        let std_duration = std::time::Duration::from_millis(1_500);
+        let duration = Duration::from(std_duration);
+        assert_eq!(duration.nanoseconds(), 1_500_000_000);
+        assert_eq!(duration.seconds(), 1.5);
+        assert_eq!(duration.to_std(), Some(std_duration));
+        assert_eq!((-duration).to_std(), None);
+
+        let system_time = UNIX_EPOCH + std_duration;
+        let time = Time::from(system_time);
+        assert_eq!(time.clock_type(), ClockType::SystemTime);
+        assert_eq!(time.nanoseconds(), 1_500_000_000);
+        assert_eq!(SystemTime::from(time), system_time);
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::rcl_bindings::*;
+use crate::vendor::rosgraph_msgs::msg::Clock as ClockMsg;
+use crate::{
+    ros_error, CallbackGroup, Clock, Logger, Parameter, ParameterCallbackHandle,
+    ParameterDescriptor, ParameterInterface, ParameterValue, RclrsError, Subscription,
+    SubscriptionBase, Time, QOS_PROFILE_CLOCK,
+};
+
+/// Drives the ROS time of a node's clock.
+///
+/// When `use_sim_time` is enabled, the clock's ROS time is overridden by the time received on the
+/// `/clock` topic, like in `rclcpp::TimeSource`. Timers using the clock then only advance when
+/// new messages arrive on `/clock`, and stop when the simulation is paused.
+///
+/// The time source follows the `use_sim_time` parameter of the node, so it can also be enabled
+/// or disabled after the node has been created.
+pub(crate) struct TimeSource {
+    sim_time: Arc<SimTime>,
+    // Removes the callback for the use_sim_time parameter when the node is dropped
+    _use_sim_time_callback: ParameterCallbackHandle,
+}
+
+// The part of the time source that is shared with the callback for the use_sim_time parameter.
+struct SimTime {
+    clock: Clock,
+    // Weak, so that the callback, which is owned by the parameters, doesn't keep the node alive
+    rcl_node_mtx: Weak<Mutex<rcl_node_t>>,
+    callback_group: Weak<CallbackGroup>,
+    entity_generation: Arc<AtomicUsize>,
+    clock_subscription: Mutex<Option<Arc<Subscription<ClockMsg>>>>,
+}
+
+impl TimeSource {
+    /// Creates a time source for the given ROS clock and declares the `use_sim_time` parameter.
+    ///
+    /// The `/clock` subscription is created on the given node, and added to the given callback
+    /// group, which keeps only a weak reference. Adding it increments the entity generation of
+    /// the node.
+    pub(crate) fn new(
+        clock: Clock,
+        rcl_node_mtx: &Arc<Mutex<rcl_node_t>>,
+        callback_group: &Arc<CallbackGroup>,
+        entity_generation: &Arc<AtomicUsize>,
+        parameters: &ParameterInterface,
+        logger: &Logger,
+    ) -> Result<Self, RclrsError> {
+        let sim_time = Arc::new(SimTime {
+            clock,
+            rcl_node_mtx: Arc::downgrade(rcl_node_mtx),
+            callback_group: Arc::downgrade(callback_group),
+            entity_generation: Arc::clone(entity_generation),
+            clock_subscription: Mutex::new(None),
+        });
+        if let ParameterValue::Bool(true) =
+            parameters.declare("use_sim_time", false.into(), ParameterDescriptor::default())?
+        {
+            sim_time.set_enabled(true)?;
+        }
+        // The parameter can only be set to a bool, since its type is fixed. Undeclaring it
+        // leaves the time source as it is.
+        let sim_time_for_callback = Arc::clone(&sim_time);
+        let logger = logger.clone();
+        let _use_sim_time_callback =
+            parameters.add_applied_callback(Arc::new(move |changes: &[Parameter]| {
+                for change in changes {
+                    if let ("use_sim_time", Some(ParameterValue::Bool(use_sim_time))) =
+                        (change.name.as_str(), &change.value)
+                    {
+                        if let Err(err) = sim_time_for_callback.set_enabled(*use_sim_time) {
+                            ros_error!(
+                                logger,
+                                "Failed to set use_sim_time to {}: {}",
+                                use_sim_time,
+                                err
+                            );
+                        }
+                    }
+                }
+            }));
+        Ok(Self {
+            sim_time,
+            _use_sim_time_callback,
+        })
+    }
+
+    /// Returns the clock that is driven by this time source.
+    pub(crate) fn clock(&self) -> &Clock {
+        &self.sim_time.clock
+    }
+}
+
+impl SimTime {
+    /// Enables or disables `use_sim_time`.
+    ///
+    /// Enabling it subscribes to `/clock` and overrides the ROS time of the clock. Until the
+    /// first message is received, the ROS time is zero.
+    fn set_enabled(&self, use_sim_time: bool) -> Result<(), RclrsError> {
+        let mut clock_subscription = self.clock_subscription.lock().unwrap();
+        if use_sim_time == clock_subscription.is_some() {
+            return Ok(());
+        }
+        if !use_sim_time {
+            // Wait sets notice that the subscription has been dropped by themselves
+            *clock_subscription = None;
+            return self.clock.set_ros_time_override_enabled(false);
+        }
+        let (rcl_node_mtx, callback_group) =
+            match (self.rcl_node_mtx.upgrade(), self.callback_group.upgrade()) {
+                (Some(rcl_node_mtx), Some(callback_group)) => (rcl_node_mtx, callback_group),
+                // The node is being dropped
+                _ => return Ok(()),
+            };
+        let clock = self.clock.clone();
+        let subscription = Arc::new(Subscription::new(
+            rcl_node_mtx,
+            "/clock",
+            QOS_PROFILE_CLOCK,
+            move |msg: ClockMsg| {
+                // The clock is valid, so setting the time cannot fail.
+                let _ = clock.set_ros_time_override(Time::from(msg.clock));
+            },
+        )?);
+        callback_group
+            .subscriptions
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        self.entity_generation.fetch_add(1, Ordering::Release);
+        *clock_subscription = Some(subscription);
+        self.clock.set_ros_time_override_enabled(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::vendor::builtin_interfaces;
+    use crate::{ClockType, Context, Node, RclReturnCode};
+
+    use super::*;
+
+    fn create_sim_time_node(context: &Context, node_name: &str) -> Result<Node, RclrsError> {
+        Node::builder(context, node_name)
+            .arguments(["--ros-args", "-p", "use_sim_time:=true"].map(String::from))
+            .build()
+    }
+
+    #[test]
+    fn test_ros_time_is_wall_time_by_default() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Node::new(&context, "test_ros_time_is_wall_time_by_default")?;
+        assert!(!node.get_clock().ros_time_is_active());
+        let system_time = Time::from(std::time::SystemTime::now());
+        let ros_time = node.now();
+        assert_eq!(ros_time.clock_type(), ClockType::RosTime);
+        assert!(ros_time.nanoseconds() >= system_time.nanoseconds());
+        Ok(())
+    }
+
+    #[test]
+    fn test_use_sim_time_can_be_set_at_runtime() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = Node::new(&context, "test_use_sim_time_can_be_set_at_runtime")?;
+        let subscription_count = node.live_subscriptions().len();
+        let generation = node.entity_generation.load(Ordering::Acquire);
+
+        node.set_parameter("use_sim_time", true)?;
+        assert!(node.get_clock().ros_time_is_active());
+        assert_eq!(node.live_subscriptions().len(), subscription_count + 1);
+        // Wait sets that were built for the node have to pick up the /clock subscription
+        assert!(node.entity_generation.load(Ordering::Acquire) > generation);
+
+        node.set_parameter("use_sim_time", false)?;
+        assert!(!node.get_clock().ros_time_is_active());
+        assert_eq!(node.live_subscriptions().len(), subscription_count);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sim_time_follows_clock_topic() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let node = create_sim_time_node(&context, "test_sim_time_follows_clock_topic")?;
+        assert!(node.get_clock().ros_time_is_active());
+        assert_eq!(node.now().nanoseconds(), 0);
+
+        let publisher_node = Node::new(&context, "test_sim_time_clock_publisher")?;
+        let publisher = publisher_node.create_publisher::<ClockMsg>("/clock", QOS_PROFILE_CLOCK)?;
+        // Messages that are published before the subscription has been discovered are lost, so
+        // retry until the clock has changed
+        for _ in 0..50 {
+            publisher.publish(ClockMsg {
+                clock: builtin_interfaces::msg::Time {
+                    sec: 42,
+                    nanosec: 5,
+                },
+            })?;
+            match crate::spin_once(&node, Some(Duration::from_millis(100))) {
+                Ok(())
+                | Err(RclrsError::RclError {
+                    code: RclReturnCode::Timeout,
+                    ..
+                }) => {}
+                Err(err) => return Err(err),
+            }
+            if node.now().nanoseconds() != 0 {
+                break;
+            }
+        }
+        assert_eq!(node.now(), Time::new(42, 5, ClockType::RosTime));
+        Ok(())
+    }
+
+    #[test]
+    fn test_timer_pauses_with_sim_time() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = create_sim_time_node(&context, "test_timer_pauses_with_sim_time")?;
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_for_callback = Arc::clone(&counter);
+        let _timer = node.create_timer(Duration::from_millis(1), move || {
+            counter_for_callback.fetch_add(1, Ordering::Relaxed);
+        })?;
+
+        // No time has been published on /clock, so the timer must not fire
+        std::thread::sleep(Duration::from_millis(5));
+        let _ = crate::spin_once(&node, Some(Duration::from_millis(10)));
+        assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+        node.get_clock()
+            .set_ros_time_override(Time::new(1, 0, ClockType::RosTime))?;
+        crate::spin_once(&node, Some(Duration::from_secs(1)))?;
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        Ok(())
+    }
+}
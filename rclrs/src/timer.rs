@@ -0,0 +1,292 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::error::{RclReturnCode, ToResult};
+use crate::rcl_bindings::*;
+use crate::{Clock, RclrsError};
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_timer_t {}
+
+type TimerCallback = Box<dyn FnMut() + 'static + Send>;
+
+/// A timer that periodically runs a callback.
+///
+/// The callback is run by [`spin_once`][1] or [`spin`][2] on the timer's node once the period
+/// has elapsed. If the node is not spun often enough, calls are not queued up – the callback
+/// will run only once per `spin_once` in which the timer was ready.
+///
+/// The only available way to instantiate timers is via [`Node::create_timer()`][3], this
+/// is to ensure that [`Node`][4]s can track all the timers that have been created.
+///
+/// # Example
+/// ```
+/// # use rclrs::{Context, RclrsError};
+/// # use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+/// # use std::time::Duration;
+/// let context = Context::new([])?;
+/// let mut node = rclrs::create_node(&context, "timer_node")?;
+///
+/// let fired = Arc::new(AtomicBool::new(false));
+/// let fired_for_callback = Arc::clone(&fired);
+/// let _timer = node.create_timer(Duration::from_millis(1), move || {
+///     fired_for_callback.store(true, Ordering::Relaxed);
+/// })?;
+///
+/// rclrs::spin_once(&node, Some(Duration::from_secs(1)))?;
+/// assert!(fired.load(Ordering::Relaxed));
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::spin_once
+/// [2]: crate::spin
+/// [3]: crate::Node::create_timer
+/// [4]: crate::Node
+pub struct Timer {
+    rcl_timer_mtx: Mutex<rcl_timer_t>,
+    // The timer stores a pointer to the clock, so the clock must be kept alive for as long as the
+    // timer exists. It is dropped after the timer is finalized in drop().
+    clock: Clock,
+    // Used to ensure the context is alive while the timer is alive.
+    _rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+    /// The callback function that runs when the timer is ready.
+    callback: Mutex<TimerCallback>,
+    pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        // SAFETY: No preconditions for this function (besides passing in a valid timer).
+        unsafe {
+            rcl_timer_fini(self.rcl_timer_mtx.get_mut().unwrap());
+        }
+    }
+}
+
+impl Timer {
+    /// Creates a new timer.
+    pub(crate) fn new<F>(
+        clock: Clock,
+        rcl_context_mtx: Arc<Mutex<rcl_context_t>>,
+        period: Duration,
+        callback: F,
+    ) -> Result<Self, RclrsError>
+    // This uses pub(crate) visibility to avoid instantiating this struct outside
+    // [`Node::create_timer`], see the struct's documentation for the rationale
+    where
+        F: FnMut() + 'static + Send,
+    {
+        let period_ns = duration_to_nanoseconds(period)?;
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_timer = unsafe { rcl_get_zero_initialized_timer() };
+        unsafe {
+            // SAFETY: The rcl_timer is zero-initialized as expected by this function.
+            // The rcl_clock is behind an Arc, so its address does not change, and it is kept
+            // alive for as long as the rcl_timer because the clock is co-owned by the timer.
+            // The rcl_context is kept alive because it is co-owned by the timer.
+            // Passing in a null callback is explicitly allowed, the callback is run by rclrs
+            // instead.
+            rcl_timer_init(
+                &mut rcl_timer,
+                &mut *clock.lock(),
+                &mut *rcl_context_mtx.lock().unwrap(),
+                period_ns,
+                None,
+                rcutils_get_default_allocator(),
+            )
+            .ok()?;
+        }
+
+        Ok(Self {
+            rcl_timer_mtx: Mutex::new(rcl_timer),
+            clock,
+            _rcl_context_mtx: rcl_context_mtx,
+            callback: Mutex::new(Box::new(callback)),
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_timer_t> {
+        self.rcl_timer_mtx.lock().unwrap()
+    }
+
+    /// Returns the clock that the timer uses to measure its period.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Returns the period of the timer.
+    pub fn period(&self) -> Result<Duration, RclrsError> {
+        let mut period_ns = 0;
+        unsafe {
+            // SAFETY: The timer is valid and the output pointer is valid.
+            rcl_timer_get_period(&*self.lock(), &mut period_ns).ok()?;
+        }
+        Ok(nanoseconds_to_duration(period_ns))
+    }
+
+    /// Cancels the timer.
+    ///
+    /// A canceled timer will not run its callback until it is [reset][1].
+    ///
+    /// [1]: Timer::reset
+    pub fn cancel(&self) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The timer is valid.
+            rcl_timer_cancel(&mut *self.lock()).ok()
+        }
+    }
+
+    /// Checks whether the timer is canceled.
+    pub fn is_canceled(&self) -> Result<bool, RclrsError> {
+        let mut is_canceled = false;
+        unsafe {
+            // SAFETY: The timer is valid and the output pointer is valid.
+            rcl_timer_is_canceled(&*self.lock(), &mut is_canceled).ok()?;
+        }
+        Ok(is_canceled)
+    }
+
+    /// Resets the timer.
+    ///
+    /// The time until the next call is set to the full period, and the timer is un-canceled if it
+    /// was canceled.
+    pub fn reset(&self) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The timer is valid.
+            rcl_timer_reset(&mut *self.lock()).ok()
+        }
+    }
+
+    /// Checks whether the period has elapsed and the timer is not canceled.
+    pub fn is_ready(&self) -> Result<bool, RclrsError> {
+        let mut is_ready = false;
+        unsafe {
+            // SAFETY: The timer is valid and the output pointer is valid.
+            rcl_timer_is_ready(&*self.lock(), &mut is_ready).ok()?;
+        }
+        Ok(is_ready)
+    }
+
+    /// Returns the time until the timer will be ready next.
+    ///
+    /// If the timer is already overdue, this returns [`Duration::ZERO`][1].
+    ///
+    /// If the timer is canceled, a [`TimerCanceled`][2] error is returned.
+    ///
+    /// [1]: std::time::Duration::ZERO
+    /// [2]: crate::RclReturnCode
+    pub fn time_until_next_call(&self) -> Result<Duration, RclrsError> {
+        let mut time_ns = 0;
+        unsafe {
+            // SAFETY: The timer is valid and the output pointer is valid.
+            rcl_timer_get_time_until_next_call(&*self.lock(), &mut time_ns).ok()?;
+        }
+        Ok(nanoseconds_to_duration(time_ns))
+    }
+
+    /// Returns the time since the timer was last called, or since it was created or reset.
+    pub fn time_since_last_call(&self) -> Result<Duration, RclrsError> {
+        let mut time_ns = 0;
+        unsafe {
+            // SAFETY: The timer is valid and the output pointer is valid.
+            rcl_timer_get_time_since_last_call(&*self.lock(), &mut time_ns).ok()?;
+        }
+        Ok(nanoseconds_to_duration(time_ns))
+    }
+
+    /// Marks the timer as called and runs the callback.
+    pub(crate) fn execute(&self) -> Result<(), RclrsError> {
+        let ret = unsafe {
+            // SAFETY: The timer is valid. Since no C callback was given in Timer::new(), this only
+            // updates the time of the last call.
+            rcl_timer_call(&mut *self.lock())
+        };
+        match ret.ok() {
+            Ok(()) => (),
+            Err(RclrsError::RclError {
+                code: RclReturnCode::TimerCanceled,
+                ..
+            }) => {
+                // The timer may have been canceled after the wait set indicated that it is
+                // ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        }
+        (*self.callback.lock().unwrap())();
+        Ok(())
+    }
+}
+
+// Converts a Duration to the nanosecond representation used by rcl, failing if it overflows i64.
+fn duration_to_nanoseconds(duration: Duration) -> Result<i64, RclrsError> {
+    i64::try_from(duration.as_nanos()).map_err(|_| RclrsError::RclError {
+        code: RclReturnCode::InvalidArgument,
+        msg: None,
+    })
+}
+
+// Converts nanoseconds from rcl to a Duration, saturating negative values to zero.
+fn nanoseconds_to_duration(nanoseconds: i64) -> Duration {
+    Duration::from_nanos(nanoseconds.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{Context, Node};
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn timer_is_send_and_sync() {
+        assert_send::<Timer>();
+        assert_sync::<Timer>();
+    }
+
+    #[test]
+    fn test_timer_cancel_and_reset() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(&context, "test_timer_cancel_and_reset")?;
+        let timer = node.create_timer(Duration::from_secs(10), || {})?;
+        assert_eq!(timer.period()?, Duration::from_secs(10));
+        assert!(!timer.is_canceled()?);
+        assert!(!timer.is_ready()?);
+        assert!(timer.time_until_next_call()? <= Duration::from_secs(10));
+
+        timer.cancel()?;
+        assert!(timer.is_canceled()?);
+        assert!(timer.time_until_next_call().is_err());
+
+        timer.reset()?;
+        assert!(!timer.is_canceled()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_timer_is_executed_by_spin_once() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(&context, "test_timer_is_executed_by_spin_once")?;
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_for_callback = Arc::clone(&counter);
+        let timer = node.create_timer(Duration::from_millis(1), move || {
+            counter_for_callback.fetch_add(1, Ordering::Relaxed);
+        })?;
+
+        crate::spin_once(&node, Some(Duration::from_secs(1)))?;
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+
+        // A canceled timer does not wake up the wait set.
+        timer.cancel()?;
+        std::thread::sleep(Duration::from_millis(2));
+        let _ = crate::spin_once(&node, Some(Duration::from_millis(10)));
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        Ok(())
+    }
+}
@@ -15,20 +15,29 @@
 // DISTRIBUTION A. Approved for public release; distribution unlimited.
 // OPSEC #4584.
 
-use std::sync::{Arc, Mutex};
+use std::borrow::Borrow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, TryLockError, Weak};
 use std::time::Duration;
 use std::vec::Vec;
 
 use crate::error::{to_rclrs_result, RclReturnCode, RclrsError, ToResult};
 use crate::rcl_bindings::*;
-use crate::{ClientBase, Context, Node, ServiceBase, SubscriptionBase};
+use crate::{ClientBase, Context, Node, ServiceBase, SubscriptionBase, Timer};
 
 mod exclusivity_guard;
 mod guard_condition;
+mod waitable;
 use exclusivity_guard::*;
 pub use guard_condition::*;
+pub use waitable::*;
 
 /// A struct for waiting on subscriptions and other waitable entities to become ready.
+///
+/// A wait set can be waited on repeatedly. The entities stay in the wait set until it is
+/// [cleared][1] or dropped, so it only needs to be rebuilt when the set of entities changes.
+///
+/// [1]: WaitSet::clear
 pub struct WaitSet {
     rcl_wait_set: rcl_wait_set_t,
     // Used to ensure the context is alive while the wait set is alive.
@@ -36,11 +45,28 @@ pub struct WaitSet {
     // The subscriptions that are currently registered in the wait set.
     // This correspondence is an invariant that must be maintained by all functions,
     // even in the error case.
-    subscriptions: Vec<ExclusivityGuard<Arc<dyn SubscriptionBase>>>,
-    clients: Vec<ExclusivityGuard<Arc<dyn ClientBase>>>,
+    subscriptions: Vec<WaitSetEntry<dyn SubscriptionBase, rcl_subscription_t>>,
+    clients: Vec<WaitSetEntry<dyn ClientBase, rcl_client_t>>,
     // The guard conditions that are currently registered in the wait set.
-    guard_conditions: Vec<ExclusivityGuard<Arc<GuardCondition>>>,
-    services: Vec<ExclusivityGuard<Arc<dyn ServiceBase>>>,
+    guard_conditions: Vec<WaitSetEntry<GuardCondition, rcl_guard_condition_t>>,
+    services: Vec<WaitSetEntry<dyn ServiceBase, rcl_service_t>>,
+    timers: Vec<WaitSetEntry<Timer, rcl_timer_t>>,
+    waitables: Vec<Arc<dyn Waitable>>,
+    // Whether rcl_wait() has been called since the entities were added to the rcl_wait_set.
+    needs_reregistration: bool,
+}
+
+// An entity in the wait set, together with the pointer to its rcl handle.
+//
+// The pointer is cached so that the entity does not have to be locked when it is added to the
+// rcl_wait_set again before every wait. It stays valid because the guard keeps the entity alive,
+// and no other wait set uses the entity at the same time.
+struct WaitSetEntry<T: ?Sized, H> {
+    entity: ExclusivityGuard<Arc<T>>,
+    rcl_handle: *const H,
+    // Whether the entity was added by a waitable, in which case it is not reported as ready on
+    // its own.
+    from_waitable: bool,
 }
 
 /// A list of entities that are ready, returned by [`WaitSet::wait`].
@@ -53,6 +79,10 @@ pub struct ReadyEntities {
     pub guard_conditions: Vec<Arc<GuardCondition>>,
     /// A list of services that have potentially received requests.
     pub services: Vec<Arc<dyn ServiceBase>>,
+    /// A list of timers whose period has elapsed.
+    pub timers: Vec<Arc<Timer>>,
+    /// A list of waitables that are ready to be executed.
+    pub waitables: Vec<Arc<dyn Waitable>>,
 }
 
 impl Drop for rcl_wait_set_t {
@@ -69,6 +99,10 @@ impl Drop for rcl_wait_set_t {
 // they are running in. Therefore, this type can be safely sent to another thread.
 unsafe impl Send for rcl_wait_set_t {}
 
+// SAFETY: The cached rcl handles point to entities that are kept alive by the wait set, and
+// rcl doesn't care about the thread that the entities are used from.
+unsafe impl Send for WaitSet {}
+
 // SAFETY: While the rcl_wait_set_t does have some interior mutability (because it has
 // members of non-const pointer type), this interior mutability is hidden/not used by
 // the WaitSet type. Therefore, sharing &WaitSet between threads does not risk data races.
@@ -114,6 +148,9 @@ impl WaitSet {
             guard_conditions: Vec::new(),
             clients: Vec::new(),
             services: Vec::new(),
+            timers: Vec::new(),
+            waitables: Vec::new(),
+            needs_reregistration: false,
         })
     }
 
@@ -121,39 +158,108 @@ impl WaitSet {
     ///
     /// The wait set is sized to fit the node exactly, so there is no capacity for adding other entities.
     pub fn new_for_node(node: &Node) -> Result<Self, RclrsError> {
-        let live_subscriptions = node.live_subscriptions();
-        let live_clients = node.live_clients();
-        let live_guard_conditions = node.live_guard_conditions();
-        let live_services = node.live_services();
-        let ctx = Context {
-            rcl_context_mtx: node.rcl_context_mtx.clone(),
+        Self::new_for_nodes(&[node], &[])
+    }
+
+    /// Creates a new wait set and adds all waitable entities in the given nodes to it, as well as
+    /// the given additional guard conditions.
+    ///
+    /// The wait set uses the context of the first node, so all nodes should belong to the same
+    /// context. There must be at least one node.
+    pub(crate) fn new_for_nodes<N: Borrow<Node>>(
+        nodes: &[N],
+        extra_guard_conditions: &[Arc<GuardCondition>],
+    ) -> Result<Self, RclrsError> {
+        let mut wait_set = WaitSet::new(0, 0, 0, 0, 0, 0, &nodes[0].borrow().context)?;
+        wait_set.add_nodes(nodes, extra_guard_conditions)?;
+        Ok(wait_set)
+    }
+
+    // Adds all waitable entities in the given nodes, as well as the given additional guard
+    // conditions, to the empty wait set, after resizing it to fit them exactly.
+    fn add_nodes<N: Borrow<Node>>(
+        &mut self,
+        nodes: &[N],
+        extra_guard_conditions: &[Arc<GuardCondition>],
+    ) -> Result<(), RclrsError> {
+        let mut live_subscriptions = Vec::new();
+        let mut live_clients = Vec::new();
+        let mut live_guard_conditions = extra_guard_conditions.to_vec();
+        let mut live_services = Vec::new();
+        let mut live_timers = Vec::new();
+        let mut live_waitables = Vec::new();
+        for node in nodes.iter().map(Borrow::borrow) {
+            live_subscriptions.extend(node.live_subscriptions());
+            live_clients.extend(node.live_clients());
+            live_guard_conditions.extend(node.live_guard_conditions());
+            live_services.extend(node.live_services());
+            live_timers.extend(node.live_timers());
+            live_waitables.extend(node.live_waitables());
+        }
+        let mut count = WaitableCount {
+            subscriptions: live_subscriptions.len(),
+            guard_conditions: live_guard_conditions.len(),
+            timers: live_timers.len(),
+            clients: live_clients.len(),
+            services: live_services.len(),
         };
-        let mut wait_set = WaitSet::new(
-            live_subscriptions.len(),
-            live_guard_conditions.len(),
-            0,
-            live_clients.len(),
-            live_services.len(),
-            0,
-            &ctx,
-        )?;
+        for live_waitable in &live_waitables {
+            count.add(live_waitable.count_entities());
+        }
+        self.resize(&count)?;
 
-        for live_subscription in &live_subscriptions {
-            wait_set.add_subscription(live_subscription.clone())?;
+        for live_subscription in live_subscriptions {
+            self.add_subscription(live_subscription)?;
         }
 
-        for live_client in &live_clients {
-            wait_set.add_client(live_client.clone())?;
+        for live_client in live_clients {
+            self.add_client(live_client)?;
         }
 
-        for live_guard_condition in &live_guard_conditions {
-            wait_set.add_guard_condition(live_guard_condition.clone())?;
+        for live_guard_condition in live_guard_conditions {
+            self.add_guard_condition(live_guard_condition)?;
         }
 
-        for live_service in &live_services {
-            wait_set.add_service(live_service.clone())?;
+        for live_service in live_services {
+            self.add_service(live_service)?;
         }
-        Ok(wait_set)
+
+        for live_timer in live_timers {
+            self.add_timer(live_timer)?;
+        }
+
+        for live_waitable in live_waitables {
+            self.add_waitable(live_waitable)?;
+        }
+        Ok(())
+    }
+
+    // Changes the capacity of the wait set, which must be empty. Nothing is reallocated if the
+    // capacity stays the same.
+    pub(crate) fn resize(&mut self, count: &WaitableCount) -> Result<(), RclrsError> {
+        let rcl_wait_set = &self.rcl_wait_set;
+        if rcl_wait_set.size_of_subscriptions == count.subscriptions
+            && rcl_wait_set.size_of_guard_conditions == count.guard_conditions
+            && rcl_wait_set.size_of_timers == count.timers
+            && rcl_wait_set.size_of_clients == count.clients
+            && rcl_wait_set.size_of_services == count.services
+            && rcl_wait_set.size_of_events == 0
+        {
+            return Ok(());
+        }
+        // SAFETY: The wait set is valid, and since it is empty, no entries are lost.
+        unsafe {
+            rcl_wait_set_resize(
+                &mut self.rcl_wait_set,
+                count.subscriptions,
+                count.guard_conditions,
+                count.timers,
+                count.clients,
+                count.services,
+                0,
+            )
+        }
+        .ok()
     }
 
     /// Removes all entities from the wait set.
@@ -165,6 +271,9 @@ impl WaitSet {
         self.guard_conditions.clear();
         self.clients.clear();
         self.services.clear();
+        self.timers.clear();
+        self.waitables.clear();
+        self.needs_reregistration = false;
         // This cannot fail – the rcl_wait_set_clear function only checks that the input handle is
         // valid, which it always is in our case. Hence, only debug_assert instead of returning
         // Result.
@@ -187,22 +296,7 @@ impl WaitSet {
         &mut self,
         subscription: Arc<dyn SubscriptionBase>,
     ) -> Result<(), RclrsError> {
-        let exclusive_subscription = ExclusivityGuard::new(
-            Arc::clone(&subscription),
-            Arc::clone(&subscription.handle().in_use_by_wait_set),
-        )?;
-        unsafe {
-            // SAFETY: I'm not sure if it's required, but the subscription pointer will remain valid
-            // for as long as the wait set exists, because it's stored in self.subscriptions.
-            // Passing in a null pointer for the third argument is explicitly allowed.
-            rcl_wait_set_add_subscription(
-                &mut self.rcl_wait_set,
-                &*subscription.handle().lock(),
-                std::ptr::null_mut(),
-            )
-        }
-        .ok()?;
-        self.subscriptions.push(exclusive_subscription);
+        self.entries(false).add_subscription(subscription)?;
         Ok(())
     }
 
@@ -220,21 +314,7 @@ impl WaitSet {
         &mut self,
         guard_condition: Arc<GuardCondition>,
     ) -> Result<(), RclrsError> {
-        let exclusive_guard_condition = ExclusivityGuard::new(
-            Arc::clone(&guard_condition),
-            Arc::clone(&guard_condition.in_use_by_wait_set),
-        )?;
-
-        unsafe {
-            // SAFETY: Safe if the wait set and guard condition are initialized
-            rcl_wait_set_add_guard_condition(
-                &mut self.rcl_wait_set,
-                &*guard_condition.rcl_guard_condition.lock().unwrap(),
-                std::ptr::null_mut(),
-            )
-            .ok()?;
-        }
-        self.guard_conditions.push(exclusive_guard_condition);
+        self.entries(false).add_guard_condition(guard_condition)?;
         Ok(())
     }
 
@@ -249,22 +329,7 @@ impl WaitSet {
     /// [1]: crate::RclrsError
     /// [2]: crate::RclReturnCode
     pub fn add_client(&mut self, client: Arc<dyn ClientBase>) -> Result<(), RclrsError> {
-        let exclusive_client = ExclusivityGuard::new(
-            Arc::clone(&client),
-            Arc::clone(&client.handle().in_use_by_wait_set),
-        )?;
-        unsafe {
-            // SAFETY: I'm not sure if it's required, but the client pointer will remain valid
-            // for as long as the wait set exists, because it's stored in self.clients.
-            // Passing in a null pointer for the third argument is explicitly allowed.
-            rcl_wait_set_add_client(
-                &mut self.rcl_wait_set,
-                &*client.handle().lock() as *const _,
-                core::ptr::null_mut(),
-            )
-        }
-        .ok()?;
-        self.clients.push(exclusive_client);
+        self.entries(false).add_client(client)?;
         Ok(())
     }
 
@@ -279,25 +344,78 @@ impl WaitSet {
     /// [1]: crate::RclrsError
     /// [2]: crate::RclReturnCode
     pub fn add_service(&mut self, service: Arc<dyn ServiceBase>) -> Result<(), RclrsError> {
-        let exclusive_service = ExclusivityGuard::new(
-            Arc::clone(&service),
-            Arc::clone(&service.handle().in_use_by_wait_set),
-        )?;
-        unsafe {
-            // SAFETY: I'm not sure if it's required, but the service pointer will remain valid
-            // for as long as the wait set exists, because it's stored in self.services.
-            // Passing in a null pointer for the third argument is explicitly allowed.
-            rcl_wait_set_add_service(
-                &mut self.rcl_wait_set,
-                &*service.handle().lock() as *const _,
-                core::ptr::null_mut(),
-            )
+        self.entries(false).add_service(service)?;
+        Ok(())
+    }
+
+    /// Adds a timer to the wait set.
+    ///
+    /// The wait set will become ready when the timer's period has elapsed.
+    /// Canceled timers never make the wait set ready.
+    ///
+    /// # Errors
+    /// - If the timer was already added to this wait set or another one,
+    ///   [`AlreadyAddedToWaitSet`][1] will be returned
+    /// - If the number of timers in the wait set is larger than the
+    ///   capacity set in [`WaitSet::new`], [`WaitSetFull`][2] will be returned
+    ///
+    /// [1]: crate::RclrsError
+    /// [2]: crate::RclReturnCode
+    pub fn add_timer(&mut self, timer: Arc<Timer>) -> Result<(), RclrsError> {
+        self.entries(false).add_timer(timer)?;
+        Ok(())
+    }
+
+    /// Adds a [`Waitable`] to the wait set.
+    ///
+    /// The wait set needs capacity for all the entities in the waitable's
+    /// [`count_entities()`][1].
+    ///
+    /// # Errors
+    /// - If one of the waitable's entities was already added to this wait set or another one,
+    ///   [`AlreadyAddedToWaitSet`][2] will be returned
+    /// - If the number of entities in the wait set is larger than the
+    ///   capacity set in [`WaitSet::new`], [`WaitSetFull`][3] will be returned
+    ///
+    /// [1]: Waitable::count_entities
+    /// [2]: crate::RclrsError
+    /// [3]: crate::RclReturnCode
+    pub fn add_waitable(&mut self, waitable: Arc<dyn Waitable>) -> Result<(), RclrsError> {
+        let lengths = (
+            self.subscriptions.len(),
+            self.guard_conditions.len(),
+            self.timers.len(),
+            self.clients.len(),
+            self.services.len(),
+        );
+        if let Err(err) = waitable.add_to_wait_set(&mut self.entries(true)) {
+            // Removing the entities that were added so far releases them. They are still in the
+            // rcl_wait_set, so it is filled again from the remaining entries before the next wait.
+            self.subscriptions.truncate(lengths.0);
+            self.guard_conditions.truncate(lengths.1);
+            self.timers.truncate(lengths.2);
+            self.clients.truncate(lengths.3);
+            self.services.truncate(lengths.4);
+            self.needs_reregistration = true;
+            return Err(err);
         }
-        .ok()?;
-        self.services.push(exclusive_service);
+        self.waitables.push(waitable);
         Ok(())
     }
 
+    // Entities that are added by a waitable are only reported as ready through the waitable.
+    fn entries(&mut self, from_waitable: bool) -> WaitSetEntries<'_> {
+        WaitSetEntries {
+            rcl_wait_set: &mut self.rcl_wait_set,
+            subscriptions: &mut self.subscriptions,
+            guard_conditions: &mut self.guard_conditions,
+            timers: &mut self.timers,
+            clients: &mut self.clients,
+            services: &mut self.services,
+            from_waitable,
+        }
+    }
+
     /// Blocks until the wait set is ready, or until the timeout has been exceeded.
     ///
     /// If the timeout is `None` then this function will block indefinitely until
@@ -310,7 +428,8 @@ impl WaitSet {
     /// that period of time has elapsed or the wait set becomes ready, which ever
     /// comes first.
     ///
-    /// This function does not change the entities registered in the wait set.
+    /// This function does not change the entities registered in the wait set, so it can be called
+    /// again to wait for the same entities.
     ///
     /// # Errors
     ///
@@ -321,7 +440,7 @@ impl WaitSet {
     /// This list is not comprehensive, since further errors may occur in the `rmw` or `rcl` layers.
     ///
     /// [1]: std::time::Duration::ZERO
-    pub fn wait(mut self, timeout: Option<Duration>) -> Result<ReadyEntities, RclrsError> {
+    pub fn wait(&mut self, timeout: Option<Duration>) -> Result<ReadyEntities, RclrsError> {
         let timeout_ns = match timeout.map(|d| d.as_nanos()) {
             None => -1,
             Some(ns) if ns <= i64::MAX as u128 => ns as i64,
@@ -337,22 +456,28 @@ impl WaitSet {
         // We cannot currently guarantee that the wait sets may not share content, but it is
         // mentioned in the doc comment for `add_subscription`.
         // Also, the rcl_wait_set is obviously valid.
+        if self.needs_reregistration {
+            self.reregister_entities()?;
+        }
+        self.needs_reregistration = true;
         unsafe { rcl_wait(&mut self.rcl_wait_set, timeout_ns) }.ok()?;
         let mut ready_entities = ReadyEntities {
             subscriptions: Vec::new(),
             clients: Vec::new(),
             guard_conditions: Vec::new(),
             services: Vec::new(),
+            timers: Vec::new(),
+            waitables: Vec::new(),
         };
         for (i, subscription) in self.subscriptions.iter().enumerate() {
             // SAFETY: The `subscriptions` entry is an array of pointers, and this dereferencing is
             // equivalent to
             // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
             let wait_set_entry = unsafe { *self.rcl_wait_set.subscriptions.add(i) };
-            if !wait_set_entry.is_null() {
+            if !wait_set_entry.is_null() && !subscription.from_waitable {
                 ready_entities
                     .subscriptions
-                    .push(Arc::clone(&subscription.waitable));
+                    .push(Arc::clone(&subscription.entity.waitable));
             }
         }
 
@@ -361,8 +486,10 @@ impl WaitSet {
             // equivalent to
             // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
             let wait_set_entry = unsafe { *self.rcl_wait_set.clients.add(i) };
-            if !wait_set_entry.is_null() {
-                ready_entities.clients.push(Arc::clone(&client.waitable));
+            if !wait_set_entry.is_null() && !client.from_waitable {
+                ready_entities
+                    .clients
+                    .push(Arc::clone(&client.entity.waitable));
             }
         }
 
@@ -371,10 +498,10 @@ impl WaitSet {
             // equivalent to
             // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
             let wait_set_entry = unsafe { *self.rcl_wait_set.guard_conditions.add(i) };
-            if !wait_set_entry.is_null() {
+            if !wait_set_entry.is_null() && !guard_condition.from_waitable {
                 ready_entities
                     .guard_conditions
-                    .push(Arc::clone(&guard_condition.waitable));
+                    .push(Arc::clone(&guard_condition.entity.waitable));
             }
         }
 
@@ -383,12 +510,291 @@ impl WaitSet {
             // equivalent to
             // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
             let wait_set_entry = unsafe { *self.rcl_wait_set.services.add(i) };
-            if !wait_set_entry.is_null() {
-                ready_entities.services.push(Arc::clone(&service.waitable));
+            if !wait_set_entry.is_null() && !service.from_waitable {
+                ready_entities
+                    .services
+                    .push(Arc::clone(&service.entity.waitable));
+            }
+        }
+
+        for (i, timer) in self.timers.iter().enumerate() {
+            // SAFETY: The `timers` entry is an array of pointers, and this dereferencing is
+            // equivalent to
+            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
+            let wait_set_entry = unsafe { *self.rcl_wait_set.timers.add(i) };
+            if !wait_set_entry.is_null() && !timer.from_waitable {
+                ready_entities
+                    .timers
+                    .push(Arc::clone(&timer.entity.waitable));
+            }
+        }
+
+        let wait_set_entries = WaitSetEntries {
+            rcl_wait_set: &mut self.rcl_wait_set,
+            subscriptions: &mut self.subscriptions,
+            guard_conditions: &mut self.guard_conditions,
+            timers: &mut self.timers,
+            clients: &mut self.clients,
+            services: &mut self.services,
+            from_waitable: true,
+        };
+        for waitable in &self.waitables {
+            if waitable.is_ready(&wait_set_entries) {
+                ready_entities.waitables.push(Arc::clone(waitable));
             }
         }
         Ok(ready_entities)
     }
+
+    /// Returns true if the wait set holds the last reference to any of its entities.
+    ///
+    /// This means that the entity has been dropped everywhere else, and the wait set should be
+    /// rebuilt so that it does not keep the entity alive.
+    pub(crate) fn has_dropped_entities(&self) -> bool {
+        self.subscriptions
+            .iter()
+            .any(|entry| Arc::strong_count(&entry.entity.waitable) == 1)
+            || self
+                .clients
+                .iter()
+                .any(|entry| Arc::strong_count(&entry.entity.waitable) == 1)
+            || self
+                .guard_conditions
+                .iter()
+                .any(|entry| Arc::strong_count(&entry.entity.waitable) == 1)
+            || self
+                .services
+                .iter()
+                .any(|entry| Arc::strong_count(&entry.entity.waitable) == 1)
+            || self
+                .timers
+                .iter()
+                .any(|entry| Arc::strong_count(&entry.entity.waitable) == 1)
+            || self
+                .waitables
+                .iter()
+                .any(|waitable| Arc::strong_count(waitable) == 1)
+    }
+
+    // Returns true if the entity with the given exclusivity flag is in the wait set.
+    fn holds_entity(&self, in_use_by_wait_set: &Arc<AtomicBool>) -> bool {
+        self.subscriptions
+            .iter()
+            .any(|entry| entry.entity.guards(in_use_by_wait_set))
+            || self
+                .clients
+                .iter()
+                .any(|entry| entry.entity.guards(in_use_by_wait_set))
+            || self
+                .guard_conditions
+                .iter()
+                .any(|entry| entry.entity.guards(in_use_by_wait_set))
+            || self
+                .services
+                .iter()
+                .any(|entry| entry.entity.guards(in_use_by_wait_set))
+            || self
+                .timers
+                .iter()
+                .any(|entry| entry.entity.guards(in_use_by_wait_set))
+    }
+
+    // rcl_wait() sets the entries of the entities that were not ready to null, so all entities
+    // have to be added to the rcl_wait_set again before it can be reused. Since the capacity stays
+    // the same, this does not allocate, and since the rcl handles are cached, the entities don't
+    // need to be locked. The entities are added in the same order as before, so the indices that
+    // waitables got from WaitSetEntries stay valid.
+    fn reregister_entities(&mut self) -> Result<(), RclrsError> {
+        // SAFETY: No preconditions for this function (besides passing in a valid wait set).
+        unsafe { rcl_wait_set_clear(&mut self.rcl_wait_set) }.ok()?;
+        reregister(
+            &mut self.rcl_wait_set,
+            &self.subscriptions,
+            rcl_wait_set_add_subscription,
+        )?;
+        reregister(
+            &mut self.rcl_wait_set,
+            &self.clients,
+            rcl_wait_set_add_client,
+        )?;
+        reregister(
+            &mut self.rcl_wait_set,
+            &self.guard_conditions,
+            rcl_wait_set_add_guard_condition,
+        )?;
+        reregister(
+            &mut self.rcl_wait_set,
+            &self.services,
+            rcl_wait_set_add_service,
+        )?;
+        reregister(&mut self.rcl_wait_set, &self.timers, rcl_wait_set_add_timer)?;
+        Ok(())
+    }
+}
+
+// The signature of the rcl_wait_set_add_* functions.
+type RclWaitSetAdd<H> =
+    unsafe extern "C" fn(*mut rcl_wait_set_t, *const H, *mut usize) -> rcl_ret_t;
+
+// Adds the cached rcl handles of the entries to the wait set, in the same order as before.
+fn reregister<T: ?Sized, H>(
+    rcl_wait_set: &mut rcl_wait_set_t,
+    entries: &[WaitSetEntry<T, H>],
+    rcl_wait_set_add: RclWaitSetAdd<H>,
+) -> Result<(), RclrsError> {
+    for entry in entries {
+        // SAFETY: The entity is valid and kept alive by the entry, as explained in the add_*
+        // functions of the wait set.
+        unsafe { rcl_wait_set_add(rcl_wait_set, entry.rcl_handle, std::ptr::null_mut()) }.ok()?;
+    }
+    Ok(())
+}
+
+// The caches that keep their entities between the calls of WaitSetCache::wait_once(). Since an
+// entity can only be in one wait set at a time, they give up an entity when another wait set asks
+// for it.
+static RELEASABLE_WAIT_SET_CACHES: Mutex<Vec<Weak<Mutex<WaitSetCache>>>> = Mutex::new(Vec::new());
+
+/// A wait set for the entities of some nodes, which is reused until entities are added to or
+/// removed from the nodes.
+#[derive(Default)]
+pub(crate) struct WaitSetCache {
+    wait_set: Option<WaitSet>,
+    // The address and entity generation of each node that the wait set was built for.
+    node_generations: Vec<(usize, usize)>,
+    // Guard conditions that are added to the wait set in addition to the nodes' entities.
+    extra_guard_conditions: Vec<Arc<GuardCondition>>,
+    // Wakes up the wait when the context of one of the nodes is shut down.
+    shutdown_guard_condition: Option<Arc<GuardCondition>>,
+}
+
+impl WaitSetCache {
+    /// Waits for the entities of the given nodes, rebuilding the wait set if necessary.
+    ///
+    /// There must be at least one node.
+    pub(crate) fn wait<N: Borrow<Node>>(
+        &mut self,
+        nodes: &[N],
+        timeout: Option<Duration>,
+    ) -> Result<ReadyEntities, RclrsError> {
+        if self.is_stale(nodes) {
+            if self.shutdown_guard_condition.is_none() {
+                let guard_condition = Arc::new(GuardCondition::new(&nodes[0].borrow().context));
+                self.extra_guard_conditions
+                    .push(Arc::clone(&guard_condition));
+                self.shutdown_guard_condition = Some(guard_condition);
+            }
+            if let Some(guard_condition) = &self.shutdown_guard_condition {
+                for node in nodes {
+                    node.borrow()
+                        .context
+                        .add_shutdown_guard_condition(guard_condition);
+                }
+            }
+            self.node_generations.clear();
+            match &mut self.wait_set {
+                // The rcl wait set is reused, only the entities are replaced
+                Some(wait_set) => {
+                    wait_set.clear();
+                    wait_set.add_nodes(nodes, &self.extra_guard_conditions)?;
+                }
+                None => {
+                    self.wait_set =
+                        Some(WaitSet::new_for_nodes(nodes, &self.extra_guard_conditions)?);
+                }
+            }
+            self.node_generations
+                .extend(nodes.iter().map(Borrow::borrow).map(|node: &Node| {
+                    (
+                        node as *const Node as usize,
+                        node.entity_generation.load(Ordering::Acquire),
+                    )
+                }));
+        }
+        match &mut self.wait_set {
+            Some(wait_set) => wait_set.wait(timeout),
+            None => unreachable!(),
+        }
+    }
+
+    /// Creates a cache for [`WaitSetCache::wait_once`].
+    ///
+    /// The cache keeps its entities between waits. When another wait set needs one of them while
+    /// the cache is not waiting, the cache releases its entities.
+    pub(crate) fn new_releasable() -> Arc<Mutex<Self>> {
+        let cache = Arc::new(Mutex::new(Self::default()));
+        let mut caches = RELEASABLE_WAIT_SET_CACHES.lock().unwrap();
+        caches.retain(|cache| cache.strong_count() > 0);
+        caches.push(Arc::downgrade(&cache));
+        cache
+    }
+
+    /// Waits once for the entities of the given nodes, using the cache in the mutex.
+    ///
+    /// The cache must have been created with [`WaitSetCache::new_releasable`]. If it is in use by
+    /// another thread, a temporary cache is used instead.
+    pub(crate) fn wait_once<N: Borrow<Node>>(
+        cache: &Mutex<Self>,
+        nodes: &[N],
+        timeout: Option<Duration>,
+    ) -> Result<ReadyEntities, RclrsError> {
+        match cache.try_lock() {
+            Ok(mut cache) => cache.wait(nodes, timeout),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().wait(nodes, timeout),
+            Err(TryLockError::WouldBlock) => Self::default().wait(nodes, timeout),
+        }
+    }
+
+    /// Removes the entity with the given exclusivity flag from the releasable cache that holds
+    /// it, so that it can be added to another wait set.
+    ///
+    /// Returns false if no cache holds the entity, or if the cache is waiting right now.
+    pub(crate) fn release_entity(in_use_by_wait_set: &Arc<AtomicBool>) -> bool {
+        let caches: Vec<_> = RELEASABLE_WAIT_SET_CACHES
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
+        for cache_mtx in &caches {
+            let mut cache = match cache_mtx.try_lock() {
+                Ok(cache) => cache,
+                Err(TryLockError::Poisoned(err)) => err.into_inner(),
+                // The cache is waiting, or it is the one asking for the entity
+                Err(TryLockError::WouldBlock) => continue,
+            };
+            let holds_entity = match &cache.wait_set {
+                Some(wait_set) => wait_set.holds_entity(in_use_by_wait_set),
+                None => false,
+            };
+            if holds_entity {
+                // The wait set itself is kept for the next wait
+                if let Some(wait_set) = &mut cache.wait_set {
+                    wait_set.clear();
+                }
+                cache.node_generations.clear();
+                return true;
+            }
+        }
+        false
+    }
+
+    fn is_stale<N: Borrow<Node>>(&self, nodes: &[N]) -> bool {
+        let wait_set = match &self.wait_set {
+            Some(wait_set) => wait_set,
+            None => return true,
+        };
+        self.node_generations.len() != nodes.len()
+            || self
+                .node_generations
+                .iter()
+                .zip(nodes.iter().map(Borrow::borrow))
+                .any(|(&(address, generation), node)| {
+                    address != node as *const Node as usize
+                        || generation != node.entity_generation.load(Ordering::Acquire)
+                })
+            || wait_set.has_dropped_entities()
+    }
 }
 
 #[cfg(test)]
@@ -419,4 +825,73 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn wait_set_can_be_reused() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+
+        let guard_condition_1 = Arc::new(GuardCondition::new(&context));
+        let guard_condition_2 = Arc::new(GuardCondition::new(&context));
+
+        let mut wait_set = WaitSet::new(0, 2, 0, 0, 0, 0, &context)?;
+        wait_set.add_guard_condition(Arc::clone(&guard_condition_1))?;
+        wait_set.add_guard_condition(Arc::clone(&guard_condition_2))?;
+
+        guard_condition_1.trigger()?;
+        let readies = wait_set.wait(Some(std::time::Duration::from_millis(10)))?;
+        assert!(readies.guard_conditions == [Arc::clone(&guard_condition_1)]);
+
+        // The guard condition that was not ready is still in the wait set
+        guard_condition_2.trigger()?;
+        let readies = wait_set.wait(Some(std::time::Duration::from_millis(10)))?;
+        assert!(readies.guard_conditions == [Arc::clone(&guard_condition_2)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn wait_set_cache_is_rebuilt_when_entities_change() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(&context, "wait_set_cache_node")?;
+        let guard_condition = node.create_guard_condition();
+        let mut cache = WaitSetCache::default();
+
+        guard_condition.trigger()?;
+        cache.wait(&[&node], Some(Duration::from_millis(10)))?;
+        assert!(!cache.is_stale(&[&node]));
+
+        let new_guard_condition = node.create_guard_condition();
+        assert!(cache.is_stale(&[&node]));
+        new_guard_condition.trigger()?;
+        let readies = cache.wait(&[&node], Some(Duration::from_millis(10)))?;
+        assert!(readies.guard_conditions == [Arc::clone(&new_guard_condition)]);
+
+        drop(new_guard_condition);
+        assert!(cache.is_stale(&[&node]));
+        Ok(())
+    }
+
+    #[test]
+    fn wait_once_releases_the_entities_on_request() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(&context, "wait_once_node")?;
+        let guard_condition = node.create_guard_condition();
+        let cache = WaitSetCache::new_releasable();
+
+        guard_condition.trigger()?;
+        let readies = WaitSetCache::wait_once(&cache, &[&node], Some(Duration::from_millis(10)))?;
+        assert!(readies.guard_conditions == [Arc::clone(&guard_condition)]);
+        // The entities are kept for the next wait
+        assert!(!cache.lock().unwrap().is_stale(&[&node]));
+
+        // Until another wait set needs them, which keeps the wait set of the cache
+        WaitSet::new_for_node(&node)?;
+        assert!(cache.lock().unwrap().is_stale(&[&node]));
+        assert!(cache.lock().unwrap().wait_set.is_some());
+
+        guard_condition.trigger()?;
+        let readies = WaitSetCache::wait_once(&cache, &[&node], Some(Duration::from_millis(10)))?;
+        assert!(readies.guard_conditions == [Arc::clone(&guard_condition)]);
+        Ok(())
+    }
 }
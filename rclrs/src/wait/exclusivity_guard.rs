@@ -33,6 +33,11 @@ impl<T> ExclusivityGuard<T> {
             waitable,
         })
     }
+
+    /// Returns true if this guard uses the given flag, i.e. if it guards the same entity.
+    pub fn guards(&self, in_use_by_wait_set: &Arc<AtomicBool>) -> bool {
+        Arc::ptr_eq(&self.in_use_by_wait_set, in_use_by_wait_set)
+    }
 }
 
 #[cfg(test)]
@@ -12,6 +12,10 @@ use crate::{Context, RclrsError, ToResult};
 /// The guard condition may be reused multiple times, but like other waitable entities, can not be used in
 /// multiple wait sets concurrently.
 ///
+/// A guard condition can have a callback, which is run by [`spin_once`][1], [`spin`][2] and the
+/// executors when they find the guard condition triggered. When waiting on a [`WaitSet`][3]
+/// directly, the callback can be run with [`GuardCondition::execute`].
+///
 /// # Example
 /// ```
 /// # use rclrs::{Context, GuardCondition, WaitSet, RclrsError};
@@ -32,17 +36,24 @@ use crate::{Context, RclrsError, ToResult};
 /// let mut ws = WaitSet::new(0, 1, 0, 0, 0, 0, &context)?;
 /// ws.add_guard_condition(Arc::clone(&gc))?;
 ///
-/// // Trigger the guard condition, firing the callback and waking the wait set being waited on, if any.
+/// // Trigger the guard condition, waking the wait set being waited on, if any.
 /// gc.trigger()?;
 ///
+/// // The wait call will now immediately return.
+/// let ready_entities = ws.wait(Some(std::time::Duration::from_millis(10)))?;
+/// for guard_condition in ready_entities.guard_conditions {
+///     guard_condition.execute();
+/// }
+///
 /// // The provided callback has now been called.
 /// assert_eq!(atomic_bool.load(Ordering::Relaxed), true);
 ///
-/// // The wait call will now immediately return.
-/// ws.wait(Some(std::time::Duration::from_millis(10)))?;
-///
 /// # Ok::<(), RclrsError>(())
 /// ```
+///
+/// [1]: crate::spin_once
+/// [2]: crate::spin
+/// [3]: crate::WaitSet
 pub struct GuardCondition {
     /// The rcl_guard_condition_t that this struct encapsulates.
     pub(crate) rcl_guard_condition: Mutex<rcl_guard_condition_t>,
@@ -120,16 +131,26 @@ impl GuardCondition {
         }
     }
 
-    /// Triggers this guard condition, activating the wait set, and calling the optionally assigned callback.
+    /// Triggers this guard condition, activating the wait set.
+    ///
+    /// The callback, if any, is not called here, but by whoever waits on the guard condition,
+    /// see [`GuardCondition::execute`].
     pub fn trigger(&self) -> Result<(), RclrsError> {
         unsafe {
             // SAFETY: The rcl_guard_condition_t is valid.
             rcl_trigger_guard_condition(&mut *self.rcl_guard_condition.lock().unwrap()).ok()?;
         }
+        Ok(())
+    }
+
+    /// Calls the callback of this guard condition, if it has one.
+    ///
+    /// This is done by the spin functions and executors for guard conditions that have been
+    /// triggered.
+    pub fn execute(&self) {
         if let Some(callback) = &self.callback {
             callback();
         }
-        Ok(())
     }
 }
 
@@ -152,7 +173,9 @@ mod tests {
         });
 
         guard_condition.trigger()?;
+        assert!(!atomic_bool.load(Ordering::Relaxed));
 
+        guard_condition.execute();
         assert!(atomic_bool.load(Ordering::Relaxed));
 
         Ok(())
@@ -173,8 +196,34 @@ mod tests {
         wait_set.add_guard_condition(Arc::clone(&guard_condition))?;
         guard_condition.trigger()?;
 
+        let ready_entities = wait_set.wait(Some(std::time::Duration::from_millis(10)))?;
+        assert!(ready_entities.guard_conditions.contains(&guard_condition));
+        assert!(!atomic_bool.load(Ordering::Relaxed));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_guard_condition_callback_is_run_by_spin() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = crate::create_node(&context, "test_guard_condition_callback_node")?;
+
+        let atomic_bool = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let atomic_bool_for_closure = Arc::clone(&atomic_bool);
+        let guard_condition = node.create_guard_condition_with_callback(move || {
+            atomic_bool_for_closure.store(true, Ordering::Relaxed);
+        });
+
+        // Trigger from another thread, which wakes the spinning thread
+        let guard_condition_for_thread = Arc::clone(&guard_condition);
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            guard_condition_for_thread.trigger().unwrap();
+        });
+        crate::spin_once(&node, Some(std::time::Duration::from_secs(5)))?;
+        handle.join().unwrap();
+
         assert!(atomic_bool.load(Ordering::Relaxed));
-        wait_set.wait(Some(std::time::Duration::from_millis(10)))?;
 
         Ok(())
     }
@@ -0,0 +1,400 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use super::{ExclusivityGuard, RclWaitSetAdd, WaitSetCache, WaitSetEntry};
+use crate::rcl_bindings::*;
+use crate::{
+    ClientBase, GuardCondition, RclrsError, ServiceBase, SubscriptionBase, Timer, ToResult,
+};
+
+/// The number of rcl entities of each kind that a [`Waitable`] adds to a wait set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WaitableCount {
+    /// The number of subscriptions.
+    pub subscriptions: usize,
+    /// The number of guard conditions.
+    pub guard_conditions: usize,
+    /// The number of timers.
+    pub timers: usize,
+    /// The number of clients.
+    pub clients: usize,
+    /// The number of services.
+    pub services: usize,
+}
+
+impl WaitableCount {
+    pub(crate) fn add(&mut self, other: WaitableCount) {
+        self.subscriptions += other.subscriptions;
+        self.guard_conditions += other.guard_conditions;
+        self.timers += other.timers;
+        self.clients += other.clients;
+        self.services += other.services;
+    }
+}
+
+/// An entity that is made up of several rcl entities, and can be waited on and executed as one.
+///
+/// Waitables make it possible to plug composite entities into the wait set and the executors,
+/// e.g. something that needs a subscription and a timer to do its work. The entities that a
+/// waitable uses should not be created through a [`Node`][1], since they would otherwise be
+/// executed on their own as well.
+///
+/// When the waitable is added to a wait set, [`add_to_wait_set()`][2] is called once to register
+/// the waitable's entities. After every wait, [`is_ready()`][3] decides whether
+/// [`execute()`][4] is called.
+/// A waitable is added to a node with [`Node::add_waitable()`][5], and can be added to a
+/// [`WaitSet`][6] directly with [`WaitSet::add_waitable()`][7].
+///
+/// Like other entities, the entities of a waitable can only be in one wait set at a time.
+///
+/// [1]: crate::Node
+/// [2]: Waitable::add_to_wait_set
+/// [3]: Waitable::is_ready
+/// [4]: Waitable::execute
+/// [5]: crate::Node::add_waitable
+/// [6]: crate::WaitSet
+/// [7]: crate::WaitSet::add_waitable
+pub trait Waitable: Send + Sync {
+    /// Returns how many rcl entities of each kind the waitable adds to a wait set.
+    fn count_entities(&self) -> WaitableCount;
+
+    /// Adds the rcl entities of the waitable to the wait set.
+    ///
+    /// The indices returned by the `add_*` functions of the [`WaitSetEntries`] are needed for
+    /// checking readiness in [`is_ready()`][1], so they should be stored in the waitable. They
+    /// stay valid for as long as the waitable is in the wait set.
+    /// The number of added entities must not exceed [`count_entities()`][2].
+    ///
+    /// [1]: Waitable::is_ready
+    /// [2]: Waitable::count_entities
+    fn add_to_wait_set(&self, wait_set: &mut WaitSetEntries<'_>) -> Result<(), RclrsError>;
+
+    /// Returns true if the waitable should be executed, after the wait set has been waited on.
+    fn is_ready(&self, wait_set: &WaitSetEntries<'_>) -> bool;
+
+    /// Does the work of the waitable, such as taking messages and calling callbacks.
+    fn execute(&self) -> Result<(), RclrsError>;
+}
+
+/// Access to the entries of a wait set, used by [`Waitable`]s.
+///
+/// Like for the `add_*` functions of [`WaitSet`][1], the wait set keeps the added entities alive,
+/// and an entity can't be in more than one wait set at a time.
+///
+/// [1]: crate::WaitSet
+pub struct WaitSetEntries<'a> {
+    pub(super) rcl_wait_set: &'a mut rcl_wait_set_t,
+    pub(super) subscriptions: &'a mut Vec<WaitSetEntry<dyn SubscriptionBase, rcl_subscription_t>>,
+    pub(super) guard_conditions: &'a mut Vec<WaitSetEntry<GuardCondition, rcl_guard_condition_t>>,
+    pub(super) timers: &'a mut Vec<WaitSetEntry<Timer, rcl_timer_t>>,
+    pub(super) clients: &'a mut Vec<WaitSetEntry<dyn ClientBase, rcl_client_t>>,
+    pub(super) services: &'a mut Vec<WaitSetEntry<dyn ServiceBase, rcl_service_t>>,
+    pub(super) from_waitable: bool,
+}
+
+impl<'a> WaitSetEntries<'a> {
+    /// Adds a subscription, and returns its index in the wait set.
+    ///
+    /// Fails with [`AlreadyAddedToWaitSet`][1] if the subscription is already in a wait set.
+    ///
+    /// [1]: crate::RclrsError::AlreadyAddedToWaitSet
+    pub fn add_subscription(
+        &mut self,
+        subscription: Arc<dyn SubscriptionBase>,
+    ) -> Result<usize, RclrsError> {
+        let (rcl_handle, in_use_by_wait_set) = {
+            let handle = subscription.handle();
+            let rcl_handle: *const rcl_subscription_t = &*handle.lock();
+            (rcl_handle, Arc::clone(&handle.in_use_by_wait_set))
+        };
+        add_entry(
+            self.rcl_wait_set,
+            self.subscriptions,
+            subscription,
+            in_use_by_wait_set,
+            rcl_handle,
+            rcl_wait_set_add_subscription,
+            self.from_waitable,
+        )
+    }
+
+    /// Adds a guard condition, and returns its index in the wait set.
+    ///
+    /// Fails with [`AlreadyAddedToWaitSet`][1] if the guard condition is already in a wait set.
+    ///
+    /// [1]: crate::RclrsError::AlreadyAddedToWaitSet
+    pub fn add_guard_condition(
+        &mut self,
+        guard_condition: Arc<GuardCondition>,
+    ) -> Result<usize, RclrsError> {
+        let rcl_handle = {
+            let rcl_handle: *const rcl_guard_condition_t =
+                &*guard_condition.rcl_guard_condition.lock().unwrap();
+            rcl_handle
+        };
+        let in_use_by_wait_set = Arc::clone(&guard_condition.in_use_by_wait_set);
+        add_entry(
+            self.rcl_wait_set,
+            self.guard_conditions,
+            guard_condition,
+            in_use_by_wait_set,
+            rcl_handle,
+            rcl_wait_set_add_guard_condition,
+            self.from_waitable,
+        )
+    }
+
+    /// Adds a timer, and returns its index in the wait set.
+    ///
+    /// Fails with [`AlreadyAddedToWaitSet`][1] if the timer is already in a wait set.
+    ///
+    /// [1]: crate::RclrsError::AlreadyAddedToWaitSet
+    pub fn add_timer(&mut self, timer: Arc<Timer>) -> Result<usize, RclrsError> {
+        let rcl_handle = {
+            let rcl_handle: *const rcl_timer_t = &*timer.lock();
+            rcl_handle
+        };
+        let in_use_by_wait_set = Arc::clone(&timer.in_use_by_wait_set);
+        add_entry(
+            self.rcl_wait_set,
+            self.timers,
+            timer,
+            in_use_by_wait_set,
+            rcl_handle,
+            rcl_wait_set_add_timer,
+            self.from_waitable,
+        )
+    }
+
+    /// Adds a client, and returns its index in the wait set.
+    ///
+    /// Fails with [`AlreadyAddedToWaitSet`][1] if the client is already in a wait set.
+    ///
+    /// [1]: crate::RclrsError::AlreadyAddedToWaitSet
+    pub fn add_client(&mut self, client: Arc<dyn ClientBase>) -> Result<usize, RclrsError> {
+        let (rcl_handle, in_use_by_wait_set) = {
+            let handle = client.handle();
+            let rcl_handle: *const rcl_client_t = &*handle.lock();
+            (rcl_handle, Arc::clone(&handle.in_use_by_wait_set))
+        };
+        add_entry(
+            self.rcl_wait_set,
+            self.clients,
+            client,
+            in_use_by_wait_set,
+            rcl_handle,
+            rcl_wait_set_add_client,
+            self.from_waitable,
+        )
+    }
+
+    /// Adds a service, and returns its index in the wait set.
+    ///
+    /// Fails with [`AlreadyAddedToWaitSet`][1] if the service is already in a wait set.
+    ///
+    /// [1]: crate::RclrsError::AlreadyAddedToWaitSet
+    pub fn add_service(&mut self, service: Arc<dyn ServiceBase>) -> Result<usize, RclrsError> {
+        let (rcl_handle, in_use_by_wait_set) = {
+            let handle = service.handle();
+            let rcl_handle: *const rcl_service_t = &*handle.lock();
+            (rcl_handle, Arc::clone(&handle.in_use_by_wait_set))
+        };
+        add_entry(
+            self.rcl_wait_set,
+            self.services,
+            service,
+            in_use_by_wait_set,
+            rcl_handle,
+            rcl_wait_set_add_service,
+            self.from_waitable,
+        )
+    }
+
+    /// Returns true if the subscription at the given index is ready.
+    pub fn is_subscription_ready(&self, index: usize) -> bool {
+        index < self.rcl_wait_set.size_of_subscriptions
+            // SAFETY: The index is within the bounds of the array.
+            && !unsafe { *self.rcl_wait_set.subscriptions.add(index) }.is_null()
+    }
+
+    /// Returns true if the guard condition at the given index has been triggered.
+    pub fn is_guard_condition_ready(&self, index: usize) -> bool {
+        index < self.rcl_wait_set.size_of_guard_conditions
+            // SAFETY: The index is within the bounds of the array.
+            && !unsafe { *self.rcl_wait_set.guard_conditions.add(index) }.is_null()
+    }
+
+    /// Returns true if the period of the timer at the given index has elapsed.
+    pub fn is_timer_ready(&self, index: usize) -> bool {
+        index < self.rcl_wait_set.size_of_timers
+            // SAFETY: The index is within the bounds of the array.
+            && !unsafe { *self.rcl_wait_set.timers.add(index) }.is_null()
+    }
+
+    /// Returns true if the client at the given index is ready.
+    pub fn is_client_ready(&self, index: usize) -> bool {
+        index < self.rcl_wait_set.size_of_clients
+            // SAFETY: The index is within the bounds of the array.
+            && !unsafe { *self.rcl_wait_set.clients.add(index) }.is_null()
+    }
+
+    /// Returns true if the service at the given index is ready.
+    pub fn is_service_ready(&self, index: usize) -> bool {
+        index < self.rcl_wait_set.size_of_services
+            // SAFETY: The index is within the bounds of the array.
+            && !unsafe { *self.rcl_wait_set.services.add(index) }.is_null()
+    }
+}
+
+// Adds the entity to the rcl_wait_set and to the entries, which keep it alive, and returns its
+// index in the rcl_wait_set.
+fn add_entry<T: ?Sized, H>(
+    rcl_wait_set: &mut rcl_wait_set_t,
+    entries: &mut Vec<WaitSetEntry<T, H>>,
+    entity: Arc<T>,
+    in_use_by_wait_set: Arc<AtomicBool>,
+    rcl_handle: *const H,
+    rcl_wait_set_add: RclWaitSetAdd<H>,
+    from_waitable: bool,
+) -> Result<usize, RclrsError> {
+    let entity = match ExclusivityGuard::new(Arc::clone(&entity), Arc::clone(&in_use_by_wait_set)) {
+        // The entity may still be kept by the cache of a spin_once() call
+        Err(RclrsError::AlreadyAddedToWaitSet)
+            if WaitSetCache::release_entity(&in_use_by_wait_set) =>
+        {
+            ExclusivityGuard::new(entity, in_use_by_wait_set)?
+        }
+        result => result?,
+    };
+    let mut index = 0;
+    // SAFETY: The wait set and entity are valid. The rcl handle stays valid while it is in the
+    // wait set, since the entry keeps the entity alive, and the exclusivity guard ensures that
+    // no other wait set uses the entity at the same time.
+    unsafe { rcl_wait_set_add(rcl_wait_set, rcl_handle, &mut index) }.ok()?;
+    entries.push(WaitSetEntry {
+        entity,
+        rcl_handle,
+        from_waitable,
+    });
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{Context, Node, WaitSet};
+
+    // A waitable that is executed when either of its guard conditions is triggered.
+    struct GuardConditionPair {
+        guard_conditions: [Arc<GuardCondition>; 2],
+        indices: Mutex<[usize; 2]>,
+        executed: AtomicUsize,
+    }
+
+    impl Waitable for GuardConditionPair {
+        fn count_entities(&self) -> WaitableCount {
+            WaitableCount {
+                guard_conditions: 2,
+                ..Default::default()
+            }
+        }
+
+        fn add_to_wait_set(&self, wait_set: &mut WaitSetEntries<'_>) -> Result<(), RclrsError> {
+            let mut indices = self.indices.lock().unwrap();
+            for (index, guard_condition) in indices.iter_mut().zip(&self.guard_conditions) {
+                *index = wait_set.add_guard_condition(Arc::clone(guard_condition))?;
+            }
+            Ok(())
+        }
+
+        fn is_ready(&self, wait_set: &WaitSetEntries<'_>) -> bool {
+            let indices = self.indices.lock().unwrap();
+            indices
+                .iter()
+                .any(|&index| wait_set.is_guard_condition_ready(index))
+        }
+
+        fn execute(&self) -> Result<(), RclrsError> {
+            self.executed.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn waitable_is_executed_by_spin() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let mut node = Node::new(&context, "waitable_is_executed_by_spin")?;
+        let waitable = Arc::new(GuardConditionPair {
+            guard_conditions: [
+                Arc::new(GuardCondition::new(&context)),
+                Arc::new(GuardCondition::new(&context)),
+            ],
+            indices: Mutex::new([0, 0]),
+            executed: AtomicUsize::new(0),
+        });
+        node.add_waitable(waitable.clone());
+
+        // Nothing has been triggered yet, so waiting times out
+        assert!(crate::spin_once(&node, Some(Duration::ZERO)).is_err());
+        assert_eq!(waitable.executed.load(Ordering::Relaxed), 0);
+
+        waitable.guard_conditions[1].trigger()?;
+        crate::spin_once(&node, Some(Duration::from_secs(1)))?;
+        assert_eq!(waitable.executed.load(Ordering::Relaxed), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn waitable_in_reused_wait_set() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let waitable = Arc::new(GuardConditionPair {
+            guard_conditions: [
+                Arc::new(GuardCondition::new(&context)),
+                Arc::new(GuardCondition::new(&context)),
+            ],
+            indices: Mutex::new([0, 0]),
+            executed: AtomicUsize::new(0),
+        });
+        let mut wait_set = WaitSet::new(0, 2, 0, 0, 0, 0, &context)?;
+        wait_set.add_waitable(waitable.clone())?;
+
+        for guard_condition in &waitable.guard_conditions {
+            guard_condition.trigger()?;
+            let ready_entities = wait_set.wait(Some(Duration::from_secs(1)))?;
+            assert_eq!(ready_entities.waitables.len(), 1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn waitable_that_fails_to_be_added_releases_its_entities() -> Result<(), RclrsError> {
+        let context = Context::new([])?;
+        let waitable = Arc::new(GuardConditionPair {
+            guard_conditions: [
+                Arc::new(GuardCondition::new(&context)),
+                Arc::new(GuardCondition::new(&context)),
+            ],
+            indices: Mutex::new([0, 0]),
+            executed: AtomicUsize::new(0),
+        });
+        let mut other_wait_set = WaitSet::new(0, 1, 0, 0, 0, 0, &context)?;
+        other_wait_set.add_guard_condition(Arc::clone(&waitable.guard_conditions[1]))?;
+        let mut wait_set = WaitSet::new(0, 2, 0, 0, 0, 0, &context)?;
+        assert!(matches!(
+            wait_set.add_waitable(waitable.clone()),
+            Err(RclrsError::AlreadyAddedToWaitSet)
+        ));
+
+        // The first guard condition is no longer in the wait set
+        drop(other_wait_set);
+        let mut other_wait_set = WaitSet::new(0, 1, 0, 0, 0, 0, &context)?;
+        other_wait_set.add_guard_condition(Arc::clone(&waitable.guard_conditions[0]))?;
+        waitable.guard_conditions[0].trigger()?;
+        assert!(wait_set.wait(Some(Duration::ZERO)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+//! Derive macros for [rclrs](https://docs.rs/rclrs).
+//!
+//! These are re-exported by rclrs when its `derive` feature is enabled, and should be used
+//! through it.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parenthesized, parse_macro_input, Data, DeriveInput, Error, Expr, Fields, Ident, LitStr, Token,
+};
+
+/// Implements `rclrs::Parameters` for a struct with named fields.
+///
+/// Each field becomes a parameter named like the field, and its type must implement
+/// `rclrs::ParameterVariant`. The fields are configured with `#[param(...)]` attributes:
+///
+/// - `default = <expr>`: The default value of the parameter. Without it, the value of the field
+///   in `Default::default()` is used, so the struct must then implement `Default`.
+/// - `description = "..."` and `additional_constraints = "..."`: Documentation for the
+///   parameter descriptor.
+/// - `read_only` and `dynamic_typing`: The flags of the parameter descriptor.
+/// - `integer_range(from = <expr>, to = <expr>, step = <expr>)` and
+///   `floating_point_range(...)`: The range of the parameter, where `step` is optional.
+/// - `nested`: The field is itself a struct that implements `Parameters`, and its parameters
+///   are declared with the field name and a dot as a prefix, e.g. `camera.exposure`.
+#[proc_macro_derive(Parameters, attributes(param))]
+pub fn derive_parameters(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_parameters(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn expand_parameters(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "Parameters can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "Parameters can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut needs_defaults = false;
+    let mut declarations = Vec::new();
+    let mut updates = Vec::new();
+    for field in fields {
+        // Named fields always have an identifier
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let name = ident.to_string();
+        let options = FieldOptions::from_attributes(field)?;
+
+        if options.nested {
+            let prefix = format!("{}.", name);
+            declarations.push(quote! {
+                #ident: <#ty as ::rclrs::Parameters>::declare_parameters(
+                    node,
+                    &full_name(#name),
+                )?
+            });
+            updates.push(quote! {
+                if let Some(name) = name.strip_prefix(#prefix) {
+                    return <#ty as ::rclrs::Parameters>::update_parameter(
+                        &mut self.#ident,
+                        name,
+                        value,
+                    );
+                }
+            });
+            continue;
+        }
+
+        let default_value = match &options.default {
+            Some(expr) => quote! { ::core::convert::Into::into(#expr) },
+            None => {
+                needs_defaults = true;
+                quote! { defaults.#ident }
+            }
+        };
+        let description = options
+            .description
+            .map(|lit| lit.value())
+            .unwrap_or_default();
+        let additional_constraints = options
+            .additional_constraints
+            .map(|lit| lit.value())
+            .unwrap_or_default();
+        let read_only = options.read_only;
+        let dynamic_typing = options.dynamic_typing;
+        let range = match options.range {
+            Some(range) => quote! { ::core::option::Option::Some(#range) },
+            None => quote! { ::core::option::Option::None },
+        };
+        declarations.push(quote! {
+            #ident: {
+                let name = full_name(#name);
+                let default_value: #ty = #default_value;
+                let descriptor = ::rclrs::ParameterDescriptor {
+                    description: ::std::string::String::from(#description),
+                    additional_constraints: ::std::string::String::from(#additional_constraints),
+                    read_only: #read_only,
+                    dynamic_typing: #dynamic_typing,
+                    range: #range,
+                };
+                node.declare_parameter_with_descriptor(&name, default_value, descriptor)?;
+                node.get_parameter::<#ty>(&name)?
+            }
+        });
+        updates.push(quote! {
+            if name == #name {
+                return match <#ty as ::rclrs::ParameterVariant>::from_parameter_value(value) {
+                    ::core::option::Option::Some(value) => {
+                        self.#ident = value;
+                        true
+                    }
+                    ::core::option::Option::None => false,
+                };
+            }
+        });
+    }
+
+    let defaults = if needs_defaults {
+        quote! { let defaults = <Self as ::core::default::Default>::default(); }
+    } else {
+        quote! {}
+    };
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::rclrs::Parameters for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn declare_parameters(
+                node: &::rclrs::Node,
+                prefix: &str,
+            ) -> ::core::result::Result<Self, ::rclrs::RclrsError> {
+                let full_name = |name: &str| {
+                    if prefix.is_empty() {
+                        ::std::string::String::from(name)
+                    } else {
+                        ::std::format!("{}.{}", prefix, name)
+                    }
+                };
+                #defaults
+                ::core::result::Result::Ok(Self {
+                    #(#declarations,)*
+                })
+            }
+
+            #[allow(unused_variables)]
+            fn update_parameter(&mut self, name: &str, value: ::rclrs::ParameterValue) -> bool {
+                #(#updates)*
+                false
+            }
+        }
+    })
+}
+
+/// The options of a field, from its `#[param(...)]` attributes.
+#[derive(Default)]
+struct FieldOptions {
+    default: Option<Expr>,
+    description: Option<LitStr>,
+    additional_constraints: Option<LitStr>,
+    read_only: bool,
+    dynamic_typing: bool,
+    nested: bool,
+    range: Option<TokenStream2>,
+}
+
+impl FieldOptions {
+    fn from_attributes(field: &syn::Field) -> Result<Self, Error> {
+        let mut options = FieldOptions::default();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("param"))
+        {
+            let args =
+                attr.parse_args_with(Punctuated::<FieldOption, Token![,]>::parse_terminated)?;
+            for arg in args {
+                options.apply(arg)?;
+            }
+        }
+        let has_descriptor = options.default.is_some()
+            || options.description.is_some()
+            || options.additional_constraints.is_some()
+            || options.read_only
+            || options.dynamic_typing
+            || options.range.is_some();
+        if options.nested && has_descriptor {
+            return Err(Error::new(
+                field.span(),
+                "a nested field can not have a default value or descriptor",
+            ));
+        }
+        Ok(options)
+    }
+
+    fn apply(&mut self, option: FieldOption) -> Result<(), Error> {
+        let span = option.span;
+        let duplicate = match option.kind {
+            FieldOptionKind::Default(expr) => self.default.replace(*expr).is_some(),
+            FieldOptionKind::Description(lit) => self.description.replace(lit).is_some(),
+            FieldOptionKind::AdditionalConstraints(lit) => {
+                self.additional_constraints.replace(lit).is_some()
+            }
+            FieldOptionKind::ReadOnly => std::mem::replace(&mut self.read_only, true),
+            FieldOptionKind::DynamicTyping => std::mem::replace(&mut self.dynamic_typing, true),
+            FieldOptionKind::Nested => std::mem::replace(&mut self.nested, true),
+            FieldOptionKind::Range(range) => self.range.replace(range).is_some(),
+        };
+        if duplicate {
+            Err(Error::new(span, "duplicate parameter option"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+struct FieldOption {
+    span: proc_macro2::Span,
+    kind: FieldOptionKind,
+}
+
+enum FieldOptionKind {
+    // Boxed because an expression is much larger than the other options
+    Default(Box<Expr>),
+    Description(LitStr),
+    AdditionalConstraints(LitStr),
+    ReadOnly,
+    DynamicTyping,
+    Nested,
+    Range(TokenStream2),
+}
+
+impl Parse for FieldOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        let kind = match key.to_string().as_str() {
+            "default" => {
+                input.parse::<Token![=]>()?;
+                FieldOptionKind::Default(Box::new(input.parse()?))
+            }
+            "description" => {
+                input.parse::<Token![=]>()?;
+                FieldOptionKind::Description(input.parse()?)
+            }
+            "additional_constraints" => {
+                input.parse::<Token![=]>()?;
+                FieldOptionKind::AdditionalConstraints(input.parse()?)
+            }
+            "read_only" => FieldOptionKind::ReadOnly,
+            "dynamic_typing" => FieldOptionKind::DynamicTyping,
+            "nested" => FieldOptionKind::Nested,
+            "integer_range" => {
+                let RangeOptions { from, to, step } = input.parse()?;
+                let step = match step {
+                    Some(step) => quote! { #step },
+                    None => quote! { 0 },
+                };
+                FieldOptionKind::Range(quote! {
+                    ::rclrs::ParameterRange::Integer(::rclrs::IntegerRange {
+                        from_value: #from,
+                        to_value: #to,
+                        step: #step,
+                    })
+                })
+            }
+            "floating_point_range" => {
+                let RangeOptions { from, to, step } = input.parse()?;
+                let step = match step {
+                    Some(step) => quote! { #step },
+                    None => quote! { 0.0 },
+                };
+                FieldOptionKind::Range(quote! {
+                    ::rclrs::ParameterRange::FloatingPoint(::rclrs::FloatingPointRange {
+                        from_value: #from,
+                        to_value: #to,
+                        step: #step,
+                    })
+                })
+            }
+            _ => return Err(Error::new(key.span(), "unknown parameter option")),
+        };
+        Ok(FieldOption {
+            span: key.span(),
+            kind,
+        })
+    }
+}
+
+/// The contents of `integer_range(...)` or `floating_point_range(...)`.
+struct RangeOptions {
+    from: Expr,
+    to: Expr,
+    step: Option<Expr>,
+}
+
+impl Parse for RangeOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        let parens = parenthesized!(content in input);
+        let (mut from, mut to, mut step) = (None, None, None);
+        for KeyValue { key, value } in
+            Punctuated::<KeyValue, Token![,]>::parse_terminated(&content)?
+        {
+            let slot = match key.to_string().as_str() {
+                "from" => &mut from,
+                "to" => &mut to,
+                "step" => &mut step,
+                _ => return Err(Error::new(key.span(), "expected `from`, `to` or `step`")),
+            };
+            if slot.replace(value).is_some() {
+                return Err(Error::new(key.span(), "duplicate range option"));
+            }
+        }
+        match (from, to) {
+            (Some(from), Some(to)) => Ok(RangeOptions { from, to, step }),
+            _ => Err(Error::new(
+                parens.span,
+                "a range needs both `from` and `to`",
+            )),
+        }
+    }
+}
+
+struct KeyValue {
+    key: Ident,
+    value: Expr,
+}
+
+impl Parse for KeyValue {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(KeyValue { key, value })
+    }
+}
@@ -1,4 +1,7 @@
-use rclrs::{Client, Service};
+use std::time::{Duration, Instant};
+
+use rclrs::{Client, Context, RclrsError, Service};
+use test_msgs::srv::{BasicTypes, BasicTypes_Request, BasicTypes_Response};
 
 fn assert_send<T: Send>() {}
 fn assert_sync<T: Sync>() {}
@@ -14,3 +17,37 @@ fn service_is_send_and_sync() {
     assert_send::<Service<test_msgs::srv::Arrays>>();
     assert_sync::<Service<test_msgs::srv::Arrays>>();
 }
+
+#[test]
+fn test_spin_until_future_complete_with_call_async() -> Result<(), RclrsError> {
+    let context = Context::new([])?;
+    let mut node = rclrs::create_node(&context, "test_spin_until_future_complete_node")?;
+    let _service = node.create_service::<BasicTypes, _>(
+        "spin_until_future_complete_service",
+        |_, request: BasicTypes_Request| BasicTypes_Response {
+            int32_value: request.int32_value + 1,
+            ..Default::default()
+        },
+    )?;
+    let client = node.create_client::<BasicTypes>("spin_until_future_complete_service")?;
+
+    wait_for_service(&client);
+    let request = BasicTypes_Request {
+        int32_value: 41,
+        ..Default::default()
+    };
+    let future = client.call_async(request);
+    let response = rclrs::spin_until_future_complete(&node, future, Some(Duration::from_secs(5)))?;
+    assert_eq!(response?.int32_value, 42);
+    Ok(())
+}
+
+// Requests that are sent before the client has discovered the service are lost, so tests wait
+// for the service first.
+fn wait_for_service(client: &Client<BasicTypes>) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !client.service_is_ready().unwrap() {
+        assert!(Instant::now() < deadline, "The service was not discovered");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
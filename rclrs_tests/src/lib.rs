@@ -2,4 +2,5 @@
 
 mod client_service_tests;
 mod graph_tests;
+mod parameter_tests;
 mod pub_sub_tests;
@@ -0,0 +1,62 @@
+use rclrs::{Context, Node, ParameterRange, ParameterValue, Parameters, RclrsError};
+
+#[derive(Clone, Debug, Default, PartialEq, Parameters)]
+struct MotorConfig {
+    #[param(default = 100i64, integer_range(from = 0, to = 1000, step = 10))]
+    max_rpm: i64,
+    #[param(read_only)]
+    joint: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Parameters)]
+struct Config {
+    #[param(default = 0.5, description = "The gain of the controller")]
+    gain: f64,
+    topics: Vec<String>,
+    #[param(nested)]
+    left: MotorConfig,
+    #[param(nested)]
+    right: MotorConfig,
+}
+
+#[test]
+fn test_derived_parameters() -> Result<(), RclrsError> {
+    let args = ["--ros-args", "-p", "robot.right.max_rpm:=200"].map(String::from);
+    let context = Context::new(args)?;
+    let node = Node::new(&context, "derived_parameters_node")?;
+    let config = node.declare_parameters::<Config>("robot")?;
+    assert_eq!(
+        config.get(),
+        Config {
+            gain: 0.5,
+            topics: vec![],
+            left: MotorConfig {
+                max_rpm: 100,
+                joint: String::new(),
+            },
+            right: MotorConfig {
+                max_rpm: 200,
+                joint: String::new(),
+            },
+        }
+    );
+
+    let descriptor = node.describe_parameter("robot.gain")?;
+    assert_eq!(descriptor.description, "The gain of the controller");
+    let descriptor = node.describe_parameter("robot.left.max_rpm")?;
+    assert!(matches!(descriptor.range, Some(ParameterRange::Integer(_))));
+    assert!(node.describe_parameter("robot.left.joint")?.read_only);
+
+    node.set_parameter("robot.left.max_rpm", 300i64)?;
+    node.set_parameter("robot.topics", vec![String::from("/cmd_vel")])?;
+    assert_eq!(config.lock().left.max_rpm, 300);
+    assert_eq!(config.lock().topics, [String::from("/cmd_vel")]);
+    // Values outside of the range are rejected, and not applied to the struct
+    assert!(node.set_parameter("robot.right.max_rpm", 205i64).is_err());
+    assert_eq!(config.lock().right.max_rpm, 200);
+    assert_eq!(
+        node.get_parameter::<ParameterValue>("robot.right.max_rpm")?,
+        ParameterValue::Integer(200)
+    );
+    Ok(())
+}